@@ -16,9 +16,11 @@ impl FromStr for CLIEnvVarDescriptor {
     type Err = SimpleError;
 
     /// Convert string to EnvVarDescriptor
-    /// Syntax: identifier(:address)?(=value)?
+    /// Syntax: identifier(:address(:datatype(:qualifier(:ptr_levels)?)?)?)?(=value)?
+    /// Omitted fields default to a mutable f64 held directly by the host, e.g. grid:4:f64:mut:*
+    /// describes a host pointer to a mutable f64 while grid:4 describes the value itself.
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let re = Regex::new(r"(?P<identifier>[A-Za-z][a-zA-Z0-9_]*)(?P<qualifier>[*]*)(:(?P<address>[0-9]+))(:(?P<datatype>[A-Za-z][a-zA-Z0-9_]*))?(=(?P<value>[+-]?([0-9]*[.])?[0-9]+))?").unwrap();
+        let re = Regex::new(r"^(?P<identifier>[A-Za-z_][a-zA-Z0-9_]*)(:(?P<address>[0-9]+)(:(?P<datatype>[A-Za-z][a-zA-Z0-9_]*)(:(?P<qualifier>[A-Za-z]+)(:(?P<ptr_levels>[*]+))?)?)?)?(=(?P<value>[+-]?([0-9]*[.])?[0-9]+))?$").unwrap();
 
         if let Some(caps) = re.captures(input) {
             let identifier = caps.name("identifier")
@@ -31,14 +33,14 @@ impl FromStr for CLIEnvVarDescriptor {
                 .and_then(|m| Some(String::from(m.as_str())))
                 .and_then(|s| s.parse::<f64>().ok());
             let datatype = caps.name("datatype")
-                .and_then(|m| Some(String::from(m.as_str())))
-                .ok_or(SimpleError::new("Environment variable must have a valid datatype"))?;
+                .map_or(String::from("f64"), |m| m.as_str().to_lowercase());
             let qualifier = caps.name("qualifier")
-                .and_then(|m| Some(String::from(m.as_str())))
-                .ok_or(SimpleError::new("Environment variable must have a valid qualifier"))?;
+                .map_or(String::from("mut"), |m| m.as_str().to_lowercase());
+            if qualifier != "mut" && qualifier != "const" {
+                bail!("Environment variable qualifier must be mut or const, found {}", qualifier)
+            }
             let ptr_levels = caps.name("ptr_levels")
-                .and_then(|m| Some(String::from(m.as_str())))
-                .ok_or(SimpleError::new("Environment variable must have a valid pointer level"))?;
+                .map_or(String::new(), |m| String::from(m.as_str()));
 
             Ok(Self {
                 identifier,
@@ -49,7 +51,48 @@ impl FromStr for CLIEnvVarDescriptor {
                 ptr_levels
             })
         } else {
-            bail!("Environment variable must be of the form identifier(:address:datatype)?(=value)?")
+            bail!("Environment variable must be of the form identifier(:address(:datatype(:qualifier(:ptr_levels)?)?)?)?(=value)?")
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CLIEnvVarDescriptor;
+
+    fn parse(input: &str) -> CLIEnvVarDescriptor {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn test_short_form() {
+        let descriptor = parse("x");
+        assert_eq!("x", descriptor.identifier);
+        assert_eq!(None, descriptor.given_address);
+        assert_eq!(("f64", "mut", ""), (descriptor.datatype.as_str(), descriptor.qualifier.as_str(), descriptor.ptr_levels.as_str()));
+
+        let descriptor = parse("OUTPUT:3");
+        assert_eq!("OUTPUT", descriptor.identifier);
+        assert_eq!(Some(3), descriptor.given_address);
+        assert_eq!(("f64", "mut", ""), (descriptor.datatype.as_str(), descriptor.qualifier.as_str(), descriptor.ptr_levels.as_str()));
+    }
+
+    #[test]
+    fn test_full_form() {
+        let descriptor = parse("grid:4:f32:const:**");
+        assert_eq!("grid", descriptor.identifier);
+        assert_eq!(Some(4), descriptor.given_address);
+        assert_eq!(("f32", "const", "**"), (descriptor.datatype.as_str(), descriptor.qualifier.as_str(), descriptor.ptr_levels.as_str()));
+
+        let descriptor = parse("count:2:I32=5");
+        assert_eq!(("i32", "mut", ""), (descriptor.datatype.as_str(), descriptor.qualifier.as_str(), descriptor.ptr_levels.as_str()));
+        assert_eq!(Some(5.0), descriptor.given_value);
+    }
+
+    #[test]
+    fn test_invalid_forms() {
+        for input in ["", "1x", "x:", "x:a", "grid:4:f64:var", "grid:4:f64:mut:&", "grid:4:f64:mut:*:1"] {
+            assert!(input.parse::<CLIEnvVarDescriptor>().is_err(), "{} should be rejected", input);
+        }
+    }
+}
@@ -5,6 +5,8 @@ mod program_code;
 
 pub use program_code::{
     ProgramCode,
+    ProgramSymbol,
+    SymbolLocation,
     BarracudaOperators,
     FixedBarracudaOperators,
     BarracudaInstructions
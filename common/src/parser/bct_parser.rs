@@ -14,7 +14,8 @@ use std::str::FromStr;
 /// program. If the instruction name matches a number. The value is put on the values list and a VALUE
 /// instruction is added to the program instructions. If the instruction is an 'instruction' it is added
 /// to the instruction list.
-/// Empty lines are ignored as well as comments starting with # (excl whitespace)
+/// Empty lines are ignored as well as comments starting with #, either on their own line or
+/// after an instruction, e.g. 'ADD  # accumulate'.
 pub struct BarracudaCodeTextParser {
     delimiter: String
 }
@@ -76,7 +77,11 @@ impl ProgramCodeParser for BarracudaCodeTextParser  {
         let mut instructions: Vec<BarracudaInstructions> = Vec::new();
 
         for token in code_tokens {
-            let token = token.trim();
+            // Everything from a comment token to the end of the line is ignored
+            let token = match token.find(Self::COMMENT_TOKEN) {
+                Some(comment_start) => &token[..comment_start],
+                None => token
+            }.trim();
 
             if let Some(operation) = Self::parse_token_as_operation(token) {
                 instructions.push(BarracudaInstructions::OP);
@@ -86,7 +91,7 @@ impl ProgramCodeParser for BarracudaCodeTextParser  {
                 values.push(value);
             } else if let Some(instruction) = Self::parse_token_as_instruction(token) {
                 instructions.push(instruction);
-            } else if token.len()==0 || token.starts_with(Self::COMMENT_TOKEN ) {
+            } else if token.len()==0 {
                 continue;
             } else {
                 return Err(Error::new(std::io::ErrorKind::InvalidInput,
@@ -190,6 +195,19 @@ mod tests {
         assert_eq!(expected_program_code, code)
     }
 
+    #[test]
+    fn test_text_parser_with_inline_comments() {
+        let expected_program_code = ProgramCode::new(
+            vec![0.0, 8.0, 4.5],
+            vec![FIXED(PRINTFF), FIXED(ADD)],
+            vec![GOTO, VALUE, OP, OP, VALUE, VALUE]
+        );
+
+        let text = "4.5 # first\n8#second\nADD  # accumulate\nPRINTFF\t# show it\n0\nGOTO # ## done\n";
+        let code = BarracudaCodeTextParser::new().parse_str(text).unwrap();
+        assert_eq!(expected_program_code, code)
+    }
+
     #[test]
     fn test_text_value_parsing_int() {
         let expected_program_code = ProgramCode::new(
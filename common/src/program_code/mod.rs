@@ -39,6 +39,45 @@ impl ProgramCodeDecorations {
     }
 }
 
+/// Where the value of a source variable is stored when the program runs.
+/// Frame offsets are relative to the frame pointer, local variables are above it and
+/// function parameters are below it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolLocation {
+    Stack(i64),
+
+    /// Arrays hold a pointer to their elements in user space in their stack slot
+    MutableArray { frame_offset: i64, address: usize },
+    ConstantArray { frame_offset: i64, address: usize }
+}
+
+/// ProgramSymbol relates a variable of the source program to where it is stored. Variables in
+/// different scopes can share a name, the instruction it is declared at tells them apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramSymbol {
+    pub identifier: String,
+    pub instruction: usize,
+    pub location: SymbolLocation
+}
+
+impl fmt::Display for ProgramSymbol {
+    /// Symbols are written one per line in symbol map files (.sym) as:
+    /// <instruction> <identifier> <frame offset> (mut|const <user space address>)?
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            SymbolLocation::Stack(frame_offset) => {
+                write!(f, "{} {} {}", self.instruction, self.identifier, frame_offset)
+            }
+            SymbolLocation::MutableArray { frame_offset, address } => {
+                write!(f, "{} {} {} mut {}", self.instruction, self.identifier, frame_offset, address)
+            }
+            SymbolLocation::ConstantArray { frame_offset, address } => {
+                write!(f, "{} {} {} const {}", self.instruction, self.identifier, frame_offset, address)
+            }
+        }
+    }
+}
+
 /// ProgramCode describes the tables required to run barracuda code in the VM.
 #[derive(Debug)]
 pub struct ProgramCode {
@@ -62,6 +101,20 @@ pub struct ProgramCode {
 
     pub constant_user_space: Vec<f64>,
 
+    /// Warnings found while generating the program code. These don't prevent the program
+    /// from running but may indicate the program won't behave as written.
+    pub warnings: Vec<String>,
+
+    /// Variables of the source program and where they are stored, in the order they are declared.
+    pub symbols: Vec<ProgramSymbol>,
+
+    /// Number of function implementations in the program. Functions with multiple dispatch count
+    /// once for each implementation.
+    pub function_count: usize,
+
+    /// Names of the built-in functions the program calls, sorted by name.
+    pub builtin_functions: Vec<String>,
+
     /// Render decorations is used when formatting to determine if to include decorations.
     render_decorations: bool,
 
@@ -82,6 +135,10 @@ impl ProgramCode {
             user_space_size: vec![0, 0],
             mutable_user_space: vec![],
             constant_user_space: vec![],
+            warnings: vec![],
+            symbols: vec![],
+            function_count: 0,
+            builtin_functions: vec![],
             render_decorations: false,
             decorations: ProgramCodeDecorations::new()
         }
@@ -97,6 +154,10 @@ impl ProgramCode {
             user_space_size: vec![0, 0],
             mutable_user_space: vec![],
             constant_user_space: vec![],
+            warnings: vec![],
+            symbols: vec![],
+            function_count: 0,
+            builtin_functions: vec![],
             render_decorations: false,
             decorations: ProgramCodeDecorations::new()
         }
@@ -139,6 +200,26 @@ impl ProgramCode {
         self.constant_user_space.push(value);
     }
 
+    /// Adds a warning to the program code
+    pub fn push_warning(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    /// Adds a variable to the symbol map, declared at the current line
+    pub fn push_symbol(&mut self, identifier: String, location: SymbolLocation) {
+        self.symbols.push(ProgramSymbol { identifier, instruction: self.instructions.len(), location });
+    }
+
+    /// Formats the symbol map for writing to a symbol map file (.sym)
+    pub fn symbol_map(&self) -> String {
+        self.symbols.iter().map(|symbol| format!("{}\n", symbol)).collect()
+    }
+
+    /// Adds comment to program code decorations at the given line
+    pub fn push_comment_at(&mut self, line: usize, comment: String) {
+        self.decorations.add_comment(line, comment);
+    }
+
     /// Builder function adds comment to program code decorations at current line
     pub fn push_comment(&mut self, comment: String) {
         self.decorations.add_comment(self.instructions.len(), comment);
@@ -155,11 +155,11 @@ pub enum FixedBarracudaOperators {
     #[assoc(consume=1)]
     #[assoc(produce=1)]
     READ_F64   = 0x03F2,
-    #[assoc(consume=1)]
-    #[assoc(produce=1)]
+    #[assoc(consume=2)]
+    #[assoc(produce=0)]
     WRITE_F32  = 0x03F3,
-    #[assoc(consume=1)]
-    #[assoc(produce=1)]
+    #[assoc(consume=2)]
+    #[assoc(produce=0)]
     WRITE_F64  = 0x03F4,
     #[assoc(consume=1)]
     #[assoc(produce=1)]
@@ -170,8 +170,8 @@ pub enum FixedBarracudaOperators {
     #[assoc(consume=1)]
     #[assoc(produce=1)]
     READ_CHAR  = 0x03F7,
-    #[assoc(consume=1)]
-    #[assoc(produce=1)]
+    #[assoc(consume=2)]
+    #[assoc(produce=0)]
     WRITE_CHAR = 0x03F8,
 
 
@@ -449,6 +449,10 @@ pub enum FixedBarracudaOperators {
     #[assoc(consume=2)]
     #[assoc(produce=0)]
     PRINTFFT   = 0x0B67 ,
+    /// Prints a value as a signed integer, so large and negative integers aren't shown in float notation.
+    #[assoc(consume=1)]
+    #[assoc(produce=0)]
+    PRINTI     = 0x0B68 ,
 
     #[assoc(consume=0)]
     #[assoc(produce=1)]
@@ -511,6 +515,21 @@ pub enum FixedBarracudaOperators {
     #[assoc(produce=0)]
     SYNCGRID = 0x1A96,
 
+    #[assoc(consume=0)]
+    #[assoc(produce=0)]
+    FENCE = 0x1A97,
+
+    /// Pseudo random float in [0, 1). The generator is implemented by the VM, which must give
+    /// the same sequence for the same seed so seeded programs are reproducible.
+    #[assoc(consume=0)]
+    #[assoc(produce=1)]
+    RAND = 0x1E60,
+
+    /// Seeds the VM's pseudo random generator used by RAND.
+    #[assoc(consume=1)]
+    #[assoc(produce=0)]
+    RAND_SEED = 0x1E61,
+
     
 }
 
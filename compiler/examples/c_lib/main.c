@@ -9,8 +9,8 @@ const char* identifier = "count";
 int main(int argc, char *argv[]) {
     printf("Testing calling barracuda compiler from a c file.\n");
 
-    // Create Request
-    CompilerRequest request;
+    // Create Request, zero initialised so unset options use their defaults
+    CompilerRequest request = {0};
     request.code_text = strdup(test_code);
 
     request.env_vars.ptr = (EnvironmentVariable_t*)malloc(sizeof(EnvironmentVariable_t));
@@ -26,6 +26,11 @@ int main(int argc, char *argv[]) {
         CompilerResponse response = compile(&request);
 
         // Process Response
+        if (!response.success) {
+            printf("Error on line %zu: %s\n", response.diagnostics.ptr[0].line, response.diagnostics.ptr[0].message);
+            free_compile_response(response);
+            return 1;
+        }
         printf("Code:\n%s\n\n", test_code);
         printf("Stack Size: %d\n", response.recommended_stack_size);
         printf("Compiled:\n%s\n", response.code_text);
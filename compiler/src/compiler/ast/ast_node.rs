@@ -46,6 +46,13 @@ pub enum ASTNode {
     ///                 ^^^^^ -> Literal
     LITERAL(Literal),
 
+    /// Member names a field of a struct. Member accesses are represented as array indexes
+    /// so they can be chained with them, the semantic analyser replaces them with the field offset.
+    /// # Example:
+    ///     let length = point.x;
+    ///                       ^^ -> Member
+    MEMBER(String),
+
     /// Array is a list of expressions.
     /// # Example:
     ///     let array : [4] = [1, 2, 3+4, 5];
@@ -55,6 +62,15 @@ pub enum ASTNode {
         qualifier: Box<ASTNode>
     },
 
+    /// Tuple is a fixed size group of expressions. Tuples are returned from functions
+    /// to give multiple results and are destructured into separate variables.
+    /// # Example:
+    ///     return (a / b, a % b);
+    ///            ^^^^^^^^^^^^^^ -> Tuple
+    TUPLE {
+        items: Vec<ASTNode>
+    },
+
     /// Unary operation is an expression operation with only one argument
     ///
     /// # Example:
@@ -121,16 +137,32 @@ pub enum ASTNode {
         expression: Box<ASTNode>
     },
 
-    /// Empty construct statement is a construct statement that does not provide any value to the cons
+    /// Destructure statement defines a variable for each item of a tuple.
     ///
     /// # Syntax:
-    ///     let <identifier> (: <datatype>)? = <expression>;
+    ///     let (<identifier>, <identifier>, ...) = <expression>;
     ///
     /// # Example:
-    ///     let x = 36;
-    ///     ^^^^^^^^^^^ -> Construction Statement
+    ///     let (q, r) = divmod(7, 2);
+    ///     ^^^^^^^^^^^^^^^^^^^^^^^^^^ -> Destructure Statement
     ///
-    ///     let y: u32 = 42;
+    DESTRUCTURE {
+        identifiers: Vec<ASTNode>,
+        qualifier: Box<ASTNode>,
+        expression: Box<ASTNode>
+    },
+
+    /// Empty construct statement is a construct statement that does not provide any value to the
+    /// constructed variable. Every value of the variable is initialised to zero, so pointers start as null.
+    ///
+    /// # Syntax:
+    ///     let <identifier> : <datatype>;
+    ///
+    /// # Example:
+    ///     let mut x: i64;
+    ///     ^^^^^^^^^^^^^^^ -> Empty Construction Statement
+    ///
+    ///     let mut p: *i64;
     ///
     EMPTY_CONSTRUCT {
         identifier: Box<ASTNode>,
@@ -138,6 +170,42 @@ pub enum ASTNode {
         qualifier: Box<ASTNode>
     },
 
+    /// Struct statement defines a struct datatype with named fields.
+    ///
+    /// # Syntax:
+    ///     struct <identifier> { <identifier>: <datatype>, ... }
+    ///
+    /// # Example:
+    ///     struct Point { x: f64, y: f64 }
+    ///     ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ -> Struct Statement
+    STRUCT {
+        identifier: Box<ASTNode>,
+        datatype: Box<ASTNode>
+    },
+
+    /// Enum statement defines named integer constants. Variants without an explicit
+    /// value are numbered one after the previous variant, starting from 0.
+    ///
+    /// # Syntax:
+    ///     enum <identifier> { <identifier> (= <integer>)?, ... }
+    ///
+    /// # Example:
+    ///     enum Color { Red, Green = 5, Blue }
+    ///     ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^ -> Enum Statement
+    ENUM {
+        identifier: Box<ASTNode>,
+        variants: Vec<(String, Option<u64>)>
+    },
+
+    /// Enum variant refers to a variant of an enum, it is replaced with its value during semantic analysis.
+    /// # Example:
+    ///     let color = Color::Red;
+    ///                 ^^^^^^^^^^ -> Enum Variant
+    ENUM_VARIANT {
+        identifier: Box<ASTNode>,
+        variant: Box<ASTNode>
+    },
+
     /// External statement defines a external variable for use in future statements in scope.
     EXTERN {
         identifier: Box<ASTNode>
@@ -171,10 +239,23 @@ pub enum ASTNode {
         expression: Box<ASTNode>
     },
 
+    /// Fence statement orders memory accesses. Extern reads and writes before the fence
+    /// complete before any that follow it.
+    ///
+    /// # Syntax:
+    ///     fence();
+    ///
+    /// # Example:
+    ///     data[0] = 1;
+    ///     fence();    -> data is written before ready
+    ///     ready[0] = 1;
+    FENCE,
+
     /// Return statement will return the result of an expression to function caller.
     ///
     /// # Syntax:
     ///     return <expression>;
+    ///     return;
     ///
     /// # Example:
     ///     fn my_function() {
@@ -182,8 +263,10 @@ pub enum ASTNode {
     ///     }   ^^^^^^^^^^ -> Return Statement
     ///
     ///     print my_function();    -> '30'
+    ///
+    /// Without an expression the function returns early without a value.
     RETURN {
-        expression: Box<ASTNode>
+        expression: Box<Option<ASTNode>>
     },
 
     /// Branch statement, also known as an if statement will conditionally run a section of code if
@@ -246,21 +329,22 @@ pub enum ASTNode {
         body: Box<ASTNode>
     },
 
-    /// Function parameters are defined when defining a function. They carry an identifier and
-    /// an optional datatype.
+    /// Function parameters are defined when defining a function. They carry an identifier,
+    /// an optional datatype and an optional default literal used when the argument is omitted.
     ///
     /// # Syntax:
-    ///     <identifier> (: <datatype>)?
+    ///     <identifier> (: <datatype>)? (= <literal>)?
     ///
     /// # Example:
-    ///     fn my_func(x: u32, y: u32) {
+    ///     fn my_func(x: u32, y: u32 = 5) {
     ///                ^^^^^^ -> Function Parameter
     ///         return x + y;
     ///     }
     PARAMETER {
         identifier: Box<ASTNode>,
         datatype: Box<Option<ASTNode>>,
-        qualifier: Box<ASTNode>
+        qualifier: Box<ASTNode>,
+        default: Box<Option<ASTNode>>
     },
 
     /// Functions are callable sections of code that have defined 0 or more function parameters and
@@ -305,6 +389,20 @@ pub enum ASTNode {
         func_call: Box<ASTNode>
     },
 
+    /// Spread argument expands an array into the positional arguments of a function call.
+    /// As array sizes are static the expansion is done at compile time.
+    ///
+    /// # Syntax:
+    ///     <identifier>( ...<expression> )
+    ///
+    /// # Example:
+    ///     let xs = [1, 2, 3];
+    ///     g(...xs);   -> g(xs[0], xs[1], xs[2])
+    ///       ^^^^^ -> Spread Argument
+    SPREAD {
+        expression: Box<ASTNode>
+    },
+
     /// Statement list is a collection of statements that should
     /// be run linearly.
     ///
@@ -317,6 +415,17 @@ pub enum ASTNode {
     ///     print x * y; -> Statement  |
     STATEMENT_LIST(Vec<ASTNode>),
 
+    /// Source line tags a statement with the line of source code it started on.
+    /// This is used to decorate generated code for debugging.
+    ///
+    /// # Example:
+    ///     let x = 10;  -> Source Line 1
+    ///     let y = 30;  -> Source Line 2
+    SOURCE_LINE {
+        line: usize,
+        statement: Box<ASTNode>
+    },
+
     /// Scope Block defines all nodes after inner as existing in the same scope.
     ///
     /// # Syntax:
@@ -356,12 +465,18 @@ impl ASTNode {
             ASTNode::DATATYPE(_) => {}
             ASTNode::QUALIFIER(_) => {}
             ASTNode::LITERAL(_) => {}
+            ASTNode::MEMBER(_) => {}
             ASTNode::ARRAY {items, qualifier} => {
                 for item in items {
                     output.push(item.borrow_mut());
                 }
                 output.push(qualifier.as_mut());
             }
+            ASTNode::TUPLE { items } => {
+                for item in items {
+                    output.push(item.borrow_mut());
+                }
+            }
             ASTNode::UNARY_OP { op: _, expression } => {
                 output.push(expression.as_mut());
             }
@@ -386,11 +501,29 @@ impl ASTNode {
                 output.push(qualifier.as_mut());
                 output.push(expression.as_mut());
             }
+            ASTNode::DESTRUCTURE { identifiers, qualifier, expression } => {
+                for identifier in identifiers {
+                    output.push(identifier);
+                }
+                output.push(qualifier.as_mut());
+                output.push(expression.as_mut());
+            }
             ASTNode::EMPTY_CONSTRUCT { identifier, datatype, qualifier } => {
                 output.push(identifier.as_mut());
                 output.push(datatype.as_mut());
                 output.push(qualifier.as_mut());
             }
+            ASTNode::STRUCT { identifier, datatype } => {
+                output.push(identifier.as_mut());
+                output.push(datatype.as_mut());
+            }
+            ASTNode::ENUM { identifier, .. } => {
+                output.push(identifier.as_mut());
+            }
+            ASTNode::ENUM_VARIANT { identifier, variant } => {
+                output.push(identifier.as_mut());
+                output.push(variant.as_mut());
+            }
             ASTNode::EXTERN {identifier} => {
                 output.push(identifier.as_mut());
             }
@@ -404,8 +537,11 @@ impl ASTNode {
             ASTNode::PRINT { expression } => {
                 output.push(expression.as_mut());
             }
+            ASTNode::FENCE => {}
             ASTNode::RETURN { expression } => {
-                output.push(expression.as_mut());
+                if expression.is_some() {
+                    output.push(expression.as_mut().as_mut().unwrap());
+                }
             }
             ASTNode::BRANCH { condition, if_branch, else_branch } => {
                 output.push(condition.as_mut());
@@ -424,12 +560,15 @@ impl ASTNode {
                 output.push(advancement.as_mut());
                 output.push(body.as_mut());
             }
-            ASTNode::PARAMETER { identifier, datatype, qualifier } => {
+            ASTNode::PARAMETER { identifier, datatype, qualifier, default } => {
                 output.push(identifier.as_mut());
                 output.push(qualifier.as_mut());
                 if datatype.is_some() {
                     output.push(datatype.as_mut().as_mut().unwrap());
                 }
+                if default.is_some() {
+                    output.push(default.as_mut().as_mut().unwrap());
+                }
             }
             ASTNode::FUNCTION { identifier, parameters, return_type, body } => {
                 output.push(identifier);
@@ -450,11 +589,17 @@ impl ASTNode {
             ASTNode::NAKED_FUNC_CALL { func_call } => {
                 output.push(func_call.as_mut());
             }
+            ASTNode::SPREAD { expression } => {
+                output.push(expression.as_mut());
+            }
             ASTNode::STATEMENT_LIST(statements) => {
                 for statement in statements {
                     output.push(statement.borrow_mut());
                 }
             }
+            ASTNode::SOURCE_LINE { statement, .. } => {
+                output.push(statement.as_mut());
+            }
             ASTNode::SCOPE_BLOCK { inner, scope: _ } => {
                 output.push(inner.as_mut());
             }
@@ -493,4 +638,50 @@ impl ASTNode {
         }
     }
 
+    /// Utility function for checking if a node is a literal that always evaluates to false
+    pub(crate) fn is_constant_false(&self) -> bool {
+        match self {
+            ASTNode::TYPED_NODE { inner, .. } => inner.is_constant_false(),
+            ASTNode::LITERAL(Literal::BOOL(value)) => !value,
+            ASTNode::LITERAL(Literal::INTEGER(value)) => *value == 0,
+            ASTNode::LITERAL(Literal::FLOAT(value)) => *value == 0.0,
+            _ => false
+        }
+    }
+
+    /// Utility function for checking if every path through a statement ends in a return.
+    /// Loops are assumed to possibly not execute so never count as returning.
+    pub(crate) fn always_returns(&self) -> bool {
+        match self {
+            ASTNode::RETURN { .. } => true,
+            ASTNode::STATEMENT_LIST(statements) => statements.iter().any(|statement| statement.always_returns()),
+            ASTNode::SOURCE_LINE { statement, .. } => statement.always_returns(),
+            ASTNode::SCOPE_BLOCK { inner, .. } => inner.always_returns(),
+            ASTNode::BRANCH { if_branch, else_branch, .. } => match else_branch.as_ref() {
+                Some(else_branch) => if_branch.always_returns() && else_branch.always_returns(),
+                None => false
+            },
+            _ => false
+        }
+    }
+
+    /// Utility function for checking if a statement assigns to the variable name anywhere within it.
+    /// Shadowing isn't taken into account so assignments to a shadowing variable are counted too.
+    pub(crate) fn assigns_to(&self, name: &str) -> bool {
+        match self {
+            ASTNode::ASSIGNMENT { identifier, .. } => identifier.identifier_name().as_deref() == Some(name),
+            ASTNode::STATEMENT_LIST(statements) => statements.iter().any(|statement| statement.assigns_to(name)),
+            ASTNode::SOURCE_LINE { statement, .. } => statement.assigns_to(name),
+            ASTNode::SCOPE_BLOCK { inner, .. } => inner.assigns_to(name),
+            ASTNode::BRANCH { if_branch, else_branch, .. } => {
+                if_branch.assigns_to(name) || else_branch.as_ref().as_ref().is_some_and(|branch| branch.assigns_to(name))
+            }
+            ASTNode::WHILE_LOOP { body, .. } => body.assigns_to(name),
+            ASTNode::FOR_LOOP { initialization, advancement, body, .. } => {
+                initialization.assigns_to(name) || advancement.assigns_to(name) || body.assigns_to(name)
+            }
+            _ => false
+        }
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+use std::fmt::Write;
+
+use super::ast_node::ASTNode;
+
+const INDENT: &str = "  ";
+
+/// Pretty printing of the AST for debugging the frontend.
+/// Each node is written on its own line, indented by its depth in the tree and labelled with
+/// the field of its parent it belongs to. Typed nodes are folded into the node they annotate
+/// so their datatype and qualifier are displayed next to it.
+///
+/// # Example:
+///     let mut a = 1 + 2;
+///
+///     STATEMENT_LIST
+///       SOURCE_LINE 1
+///         CONSTRUCT
+///           identifier: IDENTIFIER a <PRIMITIVE(F64), mut>
+///           datatype: DATATYPE PRIMITIVE(F64)
+///           qualifier: QUALIFIER mut
+///           expression: BINARY_OP ADD <PRIMITIVE(F64), mut>
+///             lhs: LITERAL FLOAT(1.0) <PRIMITIVE(F64), const>
+///             rhs: LITERAL FLOAT(2.0) <PRIMITIVE(F64), const>
+impl ASTNode {
+    pub(crate) fn pretty_print(&self) -> String {
+        let mut output = String::new();
+        self.write_tree(&mut output, None, 0);
+        output
+    }
+
+    fn write_tree(&self, output: &mut String, label: Option<&str>, depth: usize) {
+        // Typed nodes annotate their inner node rather than adding a level to the tree
+        let (node, annotation) = match self {
+            ASTNode::TYPED_NODE { datatype, qualifier, inner } => {
+                (inner.as_ref(), format!(" <{:?}, {}>", datatype, qualifier.to_str()))
+            }
+            _ => (self, String::new())
+        };
+
+        write!(output, "{}", INDENT.repeat(depth)).unwrap();
+        if let Some(label) = label {
+            write!(output, "{}: ", label).unwrap();
+        }
+        writeln!(output, "{}{}", node.header(), annotation).unwrap();
+
+        for (label, child) in node.labelled_children() {
+            child.write_tree(output, label, depth + 1);
+        }
+    }
+
+    /// Variant name of the node along with any values it holds that aren't child nodes
+    fn header(&self) -> String {
+        match self {
+            ASTNode::IDENTIFIER(name) => format!("IDENTIFIER {}", name),
+            ASTNode::REFERENCE(name) => format!("REFERENCE {}", name),
+            ASTNode::DATATYPE(datatype) => format!("DATATYPE {:?}", datatype),
+            ASTNode::QUALIFIER(qualifier) => format!("QUALIFIER {}", qualifier.to_str()),
+            ASTNode::LITERAL(literal) => format!("LITERAL {:?}", literal),
+            ASTNode::MEMBER(name) => format!("MEMBER {}", name),
+            ASTNode::ARRAY { .. } => String::from("ARRAY"),
+            ASTNode::TUPLE { .. } => String::from("TUPLE"),
+            ASTNode::UNARY_OP { op, .. } => format!("UNARY_OP {:?}", op),
+            ASTNode::BINARY_OP { op, .. } => format!("BINARY_OP {:?}", op),
+            ASTNode::TERNARY_OP { .. } => String::from("TERNARY_OP"),
+            ASTNode::ARRAY_INDEX { .. } => String::from("ARRAY_INDEX"),
+            ASTNode::CONSTRUCT { .. } => String::from("CONSTRUCT"),
+            ASTNode::DESTRUCTURE { .. } => String::from("DESTRUCTURE"),
+            ASTNode::EMPTY_CONSTRUCT { .. } => String::from("EMPTY_CONSTRUCT"),
+            ASTNode::STRUCT { .. } => String::from("STRUCT"),
+            ASTNode::ENUM { variants, .. } => format!("ENUM {:?}", variants),
+            ASTNode::ENUM_VARIANT { .. } => String::from("ENUM_VARIANT"),
+            ASTNode::EXTERN { .. } => String::from("EXTERN"),
+            ASTNode::ASSIGNMENT { pointer_level, .. } => format!("ASSIGNMENT pointer_level={}", pointer_level),
+            ASTNode::PRINT { .. } => String::from("PRINT"),
+            ASTNode::FENCE => String::from("FENCE"),
+            ASTNode::RETURN { .. } => String::from("RETURN"),
+            ASTNode::BRANCH { .. } => String::from("BRANCH"),
+            ASTNode::WHILE_LOOP { .. } => String::from("WHILE_LOOP"),
+            ASTNode::FOR_LOOP { .. } => String::from("FOR_LOOP"),
+            ASTNode::PARAMETER { .. } => String::from("PARAMETER"),
+            ASTNode::FUNCTION { .. } => String::from("FUNCTION"),
+            ASTNode::FUNC_CALL { .. } => String::from("FUNC_CALL"),
+            ASTNode::NAKED_FUNC_CALL { .. } => String::from("NAKED_FUNC_CALL"),
+            ASTNode::SPREAD { .. } => String::from("SPREAD"),
+            ASTNode::STATEMENT_LIST(_) => String::from("STATEMENT_LIST"),
+            ASTNode::SOURCE_LINE { line, .. } => format!("SOURCE_LINE {}", line),
+            ASTNode::SCOPE_BLOCK { scope, .. } => format!("SCOPE_BLOCK {:?}", scope),
+            ASTNode::TYPED_NODE { .. } => String::from("TYPED_NODE"),
+        }
+    }
+
+    /// Children of the node labelled with the field they belong to.
+    /// Unlike children() this includes every child, such as array indices.
+    fn labelled_children(&self) -> Vec<(Option<&'static str>, &ASTNode)> {
+        let mut output: Vec<(Option<&'static str>, &ASTNode)> = vec![];
+
+        match self {
+            ASTNode::IDENTIFIER(_) | ASTNode::REFERENCE(_) | ASTNode::DATATYPE(_)
+            | ASTNode::QUALIFIER(_) | ASTNode::LITERAL(_) | ASTNode::MEMBER(_) | ASTNode::FENCE => {}
+            ASTNode::ARRAY { items, qualifier } => {
+                output.extend(items.iter().map(|item| (None, item)));
+                output.push((Some("qualifier"), qualifier.as_ref()));
+            }
+            ASTNode::TUPLE { items } => {
+                output.extend(items.iter().map(|item| (None, item)));
+            }
+            ASTNode::UNARY_OP { expression, .. } => {
+                output.push((Some("expression"), expression.as_ref()));
+            }
+            ASTNode::BINARY_OP { lhs, rhs, .. } => {
+                output.push((Some("lhs"), lhs.as_ref()));
+                output.push((Some("rhs"), rhs.as_ref()));
+            }
+            ASTNode::TERNARY_OP { condition, true_branch, false_branch } => {
+                output.push((Some("condition"), condition.as_ref()));
+                output.push((Some("true_branch"), true_branch.as_ref()));
+                output.push((Some("false_branch"), false_branch.as_ref()));
+            }
+            ASTNode::ARRAY_INDEX { index, expression } => {
+                output.push((Some("index"), index.as_ref()));
+                output.push((Some("expression"), expression.as_ref()));
+            }
+            ASTNode::CONSTRUCT { identifier, datatype, qualifier, expression } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                if let Some(datatype) = datatype.as_ref() {
+                    output.push((Some("datatype"), datatype));
+                }
+                output.push((Some("qualifier"), qualifier.as_ref()));
+                output.push((Some("expression"), expression.as_ref()));
+            }
+            ASTNode::DESTRUCTURE { identifiers, qualifier, expression } => {
+                output.extend(identifiers.iter().map(|identifier| (Some("identifier"), identifier)));
+                output.push((Some("qualifier"), qualifier.as_ref()));
+                output.push((Some("expression"), expression.as_ref()));
+            }
+            ASTNode::EMPTY_CONSTRUCT { identifier, datatype, qualifier } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.push((Some("datatype"), datatype.as_ref()));
+                output.push((Some("qualifier"), qualifier.as_ref()));
+            }
+            ASTNode::STRUCT { identifier, datatype } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.push((Some("datatype"), datatype.as_ref()));
+            }
+            ASTNode::ENUM { identifier, .. } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+            }
+            ASTNode::ENUM_VARIANT { identifier, variant } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.push((Some("variant"), variant.as_ref()));
+            }
+            ASTNode::EXTERN { identifier } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+            }
+            ASTNode::ASSIGNMENT { identifier, array_index, expression, .. } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.extend(array_index.iter().map(|index| (Some("index"), index)));
+                output.push((Some("expression"), expression.as_ref()));
+            }
+            ASTNode::PRINT { expression } | ASTNode::SPREAD { expression } => {
+                output.push((Some("expression"), expression.as_ref()));
+            }
+            ASTNode::RETURN { expression } => {
+                if let Some(expression) = expression.as_ref() {
+                    output.push((Some("expression"), expression));
+                }
+            }
+            ASTNode::BRANCH { condition, if_branch, else_branch } => {
+                output.push((Some("condition"), condition.as_ref()));
+                output.push((Some("if_branch"), if_branch.as_ref()));
+                if let Some(else_branch) = else_branch.as_ref() {
+                    output.push((Some("else_branch"), else_branch));
+                }
+            }
+            ASTNode::WHILE_LOOP { condition, body } => {
+                output.push((Some("condition"), condition.as_ref()));
+                output.push((Some("body"), body.as_ref()));
+            }
+            ASTNode::FOR_LOOP { initialization, condition, advancement, body } => {
+                output.push((Some("initialization"), initialization.as_ref()));
+                output.push((Some("condition"), condition.as_ref()));
+                output.push((Some("advancement"), advancement.as_ref()));
+                output.push((Some("body"), body.as_ref()));
+            }
+            ASTNode::PARAMETER { identifier, datatype, qualifier, default } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.push((Some("qualifier"), qualifier.as_ref()));
+                if let Some(datatype) = datatype.as_ref() {
+                    output.push((Some("datatype"), datatype));
+                }
+                if let Some(default) = default.as_ref() {
+                    output.push((Some("default"), default));
+                }
+            }
+            ASTNode::FUNCTION { identifier, parameters, return_type, body } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.extend(parameters.iter().map(|parameter| (Some("parameter"), parameter)));
+                if let Some(return_type) = return_type.as_ref() {
+                    output.push((Some("return_type"), return_type));
+                }
+                output.push((Some("body"), body.as_ref()));
+            }
+            ASTNode::FUNC_CALL { identifier, arguments } => {
+                output.push((Some("identifier"), identifier.as_ref()));
+                output.extend(arguments.iter().map(|argument| (Some("argument"), argument)));
+            }
+            ASTNode::NAKED_FUNC_CALL { func_call } => {
+                output.push((None, func_call.as_ref()));
+            }
+            ASTNode::STATEMENT_LIST(statements) => {
+                output.extend(statements.iter().map(|statement| (None, statement)));
+            }
+            ASTNode::SOURCE_LINE { statement, .. } => {
+                output.push((None, statement.as_ref()));
+            }
+            ASTNode::SCOPE_BLOCK { inner, .. } | ASTNode::TYPED_NODE { inner, .. } => {
+                output.push((None, inner.as_ref()));
+            }
+        }
+
+        output
+    }
+}
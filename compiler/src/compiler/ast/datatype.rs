@@ -36,30 +36,39 @@ impl PrimitiveDataType {
             _ => {return None}
         })
     }
-    // Currently unused but may be needed in future?
-    //pub fn size(&self) -> usize {
-    //    match self {
-    //        PrimitiveDataType::F128 => 16,
-    //        PrimitiveDataType::F64 => 8,
-    //        PrimitiveDataType::F32 => 4,
-    //        PrimitiveDataType::F16 => 2,
-    //        PrimitiveDataType::F8 => 1,
-    //        PrimitiveDataType::I128 => 16,
-    //        PrimitiveDataType::I64 => 8,
-    //        PrimitiveDataType::I32 => 4,
-    //        PrimitiveDataType::I16 => 2,
-    //        PrimitiveDataType::I8 => 1,
-    //        PrimitiveDataType::Bool => 1,
-    //        PrimitiveDataType::String => 8
-    //    }
-    //}
+
+    /// Byte width of a value of this type
+    pub fn size(&self) -> usize {
+        match self {
+            PrimitiveDataType::F128 => 16,
+            PrimitiveDataType::F64 => 8,
+            PrimitiveDataType::F32 => 4,
+            PrimitiveDataType::F16 => 2,
+            PrimitiveDataType::F8 => 1,
+            PrimitiveDataType::I128 => 16,
+            PrimitiveDataType::I64 => 8,
+            PrimitiveDataType::I32 => 4,
+            PrimitiveDataType::I16 => 2,
+            PrimitiveDataType::I8 => 1,
+            PrimitiveDataType::Bool => 1,
+            PrimitiveDataType::String => 8
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum DataType {
     ENVIRONMENTVARIABLE(PrimitiveDataType),
     POINTER(Box<DataType>),
+    // Pointer returned by malloc into the VM heap, read and written with heap ops rather than stack ops
+    HEAPPOINTER(Box<DataType>),
     ARRAY(Box<DataType>, usize),
+    // Array sized by a named compile time constant, resolved to an ARRAY during semantic analysis
+    NAMEDARRAY(Box<DataType>, String),
+    // Struct name and its fields in order. Fields are laid out consecutively like array elements.
+    // Datatypes naming a struct are parsed without fields, these are filled in during semantic analysis.
+    STRUCT(String, Vec<(String, DataType)>),
+    TUPLE(Vec<DataType>),
     PRIMITIVE(PrimitiveDataType),
     NONE
 }
@@ -81,14 +90,73 @@ impl DataType {
         }
     }
 
+    /// Primitives compare equal regardless of their kind, so booleans must be matched explicitly
+    pub fn is_bool(&self) -> bool {
+        matches!(self, DataType::PRIMITIVE(PrimitiveDataType::Bool) | DataType::ENVIRONMENTVARIABLE(PrimitiveDataType::Bool))
+    }
+
+    /// Primitives compare equal regardless of their kind, so integers must be matched explicitly
+    pub fn is_integer(&self) -> bool {
+        matches!(self,
+            DataType::PRIMITIVE(PrimitiveDataType::I128 | PrimitiveDataType::I64 | PrimitiveDataType::I32 | PrimitiveDataType::I16 | PrimitiveDataType::I8) |
+            DataType::ENVIRONMENTVARIABLE(PrimitiveDataType::I128 | PrimitiveDataType::I64 | PrimitiveDataType::I32 | PrimitiveDataType::I16 | PrimitiveDataType::I8))
+    }
+
+    /// Primitives compare equal regardless of their kind, so floats must be matched explicitly
+    pub fn is_float(&self) -> bool {
+        matches!(self,
+            DataType::PRIMITIVE(PrimitiveDataType::F128 | PrimitiveDataType::F64 | PrimitiveDataType::F32 | PrimitiveDataType::F16 | PrimitiveDataType::F8) |
+            DataType::ENVIRONMENTVARIABLE(PrimitiveDataType::F128 | PrimitiveDataType::F64 | PrimitiveDataType::F32 | PrimitiveDataType::F16 | PrimitiveDataType::F8))
+    }
+
+    /// Primitives compare equal regardless of their kind, so numbers must be matched explicitly
+    pub fn is_numeric(&self) -> bool {
+        match self {
+            DataType::PRIMITIVE(primitive) | DataType::ENVIRONMENTVARIABLE(primitive) => {
+                !matches!(primitive, PrimitiveDataType::Bool | PrimitiveDataType::String)
+            }
+            _ => false
+        }
+    }
+
+    /// Byte width of a value of this type. Pointers are 8 bytes, arrays and structs are the widths
+    /// of their elements added together. Named structs and arrays must be resolved first.
+    pub fn size(&self) -> usize {
+        match self {
+            DataType::PRIMITIVE(primitive) | DataType::ENVIRONMENTVARIABLE(primitive) => primitive.size(),
+            DataType::POINTER(_) | DataType::HEAPPOINTER(_) => 8,
+            DataType::ARRAY(inner, size) => size * inner.size(),
+            DataType::STRUCT(_, fields) => fields.iter().map(|(_, datatype)| datatype.size()).sum(),
+            _ => panic!("Can't take the size of {:?}!", self)
+        }
+    }
+
     pub fn get_array_length(datatype: &Self) -> usize {
         match datatype {
             DataType::ARRAY(inner, size) => {
                 size * DataType::get_array_length(inner)
             }
+            DataType::STRUCT(_, fields) => {
+                fields.iter().map(|(_, datatype)| DataType::get_array_length(datatype)).sum()
+            }
             _ => 1
         }
     }
+
+    /// Finds a field of a struct by name.
+    /// @return: (offset of the field within the struct, datatype of the field)
+    pub fn get_field(&self, name: &str) -> Option<(usize, DataType)> {
+        if let DataType::STRUCT(_, fields) = self {
+            let mut offset = 0;
+            for (field_name, datatype) in fields {
+                if field_name == name {
+                    return Some((offset, datatype.clone()));
+                }
+                offset += DataType::get_array_length(datatype);
+            }
+        }
+        None
+    }
 }
 
 impl PartialEq for DataType {
@@ -101,9 +169,12 @@ impl PartialEq for DataType {
             | (DataType::PRIMITIVE(_), DataType::ENVIRONMENTVARIABLE(_))
             | (DataType::ENVIRONMENTVARIABLE(_), DataType::ENVIRONMENTVARIABLE(_)) => true,
             (DataType::POINTER(this_inner), DataType::POINTER(other_inner)) => this_inner == other_inner,
+            (DataType::HEAPPOINTER(this_inner), DataType::HEAPPOINTER(other_inner)) => this_inner == other_inner,
             (DataType::ARRAY(this_inner, this_size), DataType::ARRAY(other_inner, other_size)) => {
                 this_inner == other_inner && this_size == other_size
             },
+            (DataType::TUPLE(this_items), DataType::TUPLE(other_items)) => this_items == other_items,
+            (DataType::STRUCT(this_name, _), DataType::STRUCT(other_name, _)) => this_name == other_name,
             (DataType::NONE, DataType::NONE) => true,
             (_, _) => false,
         }
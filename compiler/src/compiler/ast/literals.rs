@@ -1,4 +1,7 @@
 
+/// Largest integer that f64 can represent exactly, along with every integer below it. (2^53)
+pub const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
 /// Literals are defined constants within a program. See ASTNode for more detail on their usage.
 /// They are divided by their representation in text.
 ///
@@ -6,10 +9,12 @@
 /// -32 <=> UNARY_OP{UnaryOperation::NEGATE, Literal::INTEGER(32)}
 #[derive(Debug, Clone)]
 pub enum Literal {
-    /// Form: %d.%d
+    /// Form: %d.%d | %de%d | 0x%x.%xp%d
     FLOAT(f64),
 
     /// Form: %d
+    /// Integers above MAX_EXACT_INTEGER can't be represented exactly as floats. They are only
+    /// kept in i64 typed contexts, where they are emitted by bit pattern rather than value.
     INTEGER(u64),
 
     /// Form: "%c*"
@@ -21,4 +26,8 @@ pub enum Literal {
     /// Form: %d.%d
     /// 8 characters packed into a single f64
     PACKEDSTRING(f64), 
+
+    /// Form: null
+    /// Pointer to address zero
+    NULL,
 }
\ No newline at end of file
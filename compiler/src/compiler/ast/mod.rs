@@ -1,6 +1,7 @@
 pub(super) mod literals;
 pub(super) mod operators;
 pub(super) mod ast_node;
+pub(super) mod ast_printer;
 pub(super) mod scope;
 pub(super) mod symbol_table;
 pub(super) mod datatype;
@@ -11,7 +12,7 @@ use std::collections::HashMap;
 
 pub(super) use self::{
     ast_node::ASTNode,
-    literals::Literal,
+    literals::{Literal, MAX_EXACT_INTEGER},
     scope::ScopeId,
     operators::{
         UnaryOperation,
@@ -27,6 +28,7 @@ pub use self::environment_symbol_context::EnvironmentSymbolContext;
 use self::symbol_table::SymbolTable;
 
 use super::semantic_analyser::function_tracker::FunctionTracker;
+use super::warnings::CompilerWarning;
 
 /// Intermediate Representation of the compiler model
 /// This model is represented as a tree using the ASTNode enum.
@@ -34,7 +36,8 @@ use super::semantic_analyser::function_tracker::FunctionTracker;
 /// involved in the construction of a program.
 pub struct AbstractSyntaxTree {
     root: ASTNode,
-    symbol_table: SymbolTable
+    symbol_table: SymbolTable,
+    warnings: Vec<CompilerWarning>
 }
 
 impl AbstractSyntaxTree {
@@ -44,10 +47,22 @@ impl AbstractSyntaxTree {
 
         Self {
             root,
-            symbol_table
+            symbol_table,
+            warnings: vec![]
         }
     }
 
+    /// Attach warnings found while analysing the tree
+    pub fn with_warnings(mut self, warnings: Vec<CompilerWarning>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Return copy of warnings
+    pub fn get_warnings(&self) -> Vec<CompilerWarning> {
+        self.warnings.clone()
+    }
+
     /// Return cloned copy of symbol table
     pub fn get_symbol_table(&self) -> SymbolTable {
         self.symbol_table.clone()
@@ -58,6 +73,11 @@ impl AbstractSyntaxTree {
         self.symbol_table.get_functions()
     }
 
+    /// Indented tree representation of the AST for debugging
+    pub fn pretty_print(&self) -> String {
+        self.root.pretty_print()
+    }
+
     /// Convert AST into ASTNode
     pub fn into_root(self) -> ASTNode {
         self.root
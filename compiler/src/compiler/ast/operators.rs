@@ -4,7 +4,9 @@
 pub enum UnaryOperation {
     NOT,        // ! <rhs>
     NEGATE,     // - <rhs>
+    PLUS,       // + <rhs>
     PTR_DEREF,  // * <rhs>
+    SIGN,       // sign(<rhs>), only produced by the sign builtin
 }
 
 /// Binary Operations are mathematical symbolic functions with two arguments.
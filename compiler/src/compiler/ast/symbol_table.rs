@@ -66,7 +66,7 @@ impl Symbol {
     pub fn is_array(&self) -> bool {
         match &self.symbol_type {
             SymbolType::Variable(datatype, _) => match datatype {
-                DataType::ARRAY(_,_) => true,
+                DataType::ARRAY(_,_) | DataType::STRUCT(_, _) => true,
                 _ => false
             },
             _ => false
@@ -76,7 +76,7 @@ impl Symbol {
     pub fn array_length(&self) -> usize {
         match &self.symbol_type {
             SymbolType::Variable(datatype, _) => match datatype {
-                DataType::ARRAY(_, _) => DataType::get_array_length(datatype),
+                DataType::ARRAY(_, _) | DataType::STRUCT(_, _) => DataType::get_array_length(datatype),
                 _ => 0
             },
             _ => 0
@@ -342,7 +342,7 @@ impl SymbolTable {
         let symbol_scope = self.scope_map.get_mut(&current_scope).unwrap();
 
         match node {
-            ASTNode::PARAMETER { identifier, datatype, qualifier } => {
+            ASTNode::PARAMETER { identifier, datatype, qualifier, .. } => {
                 match Self::process_parameter(identifier.as_ref(), datatype.as_ref(), qualifier.as_ref()) {
                     Some(symbol) => symbol_scope.add_symbol(symbol),
                     None => panic!("") // AST Malformed
@@ -358,6 +358,17 @@ impl SymbolTable {
                     None => panic!("") // AST Malformed
                 };
             }
+            ASTNode::DESTRUCTURE { identifiers, .. } => {
+                for identifier in identifiers {
+                    match identifier {
+                        ASTNode::TYPED_NODE { datatype, qualifier, inner } => {
+                            let name = inner.identifier_name().unwrap();
+                            symbol_scope.add_symbol(Symbol::new(name, SymbolType::Variable(datatype.clone(), qualifier.clone())));
+                        }
+                        _ => panic!("Malformed AST! Destructured identifiers should be typed!")
+                    }
+                }
+            }
             ASTNode::EMPTY_CONSTRUCT{ identifier, datatype, qualifier } => {
                 let identifier = match identifier.as_ref() {
                     ASTNode::TYPED_NODE { inner, .. } => inner,
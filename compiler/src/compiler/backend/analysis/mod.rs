@@ -1 +1,2 @@
-pub(crate) mod stack_estimator;
\ No newline at end of file
+pub(crate) mod stack_estimator;
+pub(crate) mod precision_analyser;
\ No newline at end of file
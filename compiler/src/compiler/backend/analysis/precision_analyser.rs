@@ -0,0 +1,47 @@
+use crate::compiler::ast::{ASTNode, Literal, MAX_EXACT_INTEGER};
+
+pub struct PrecisionAnalyser {
+    precision: usize,
+    rounded_values: usize
+}
+
+impl PrecisionAnalyser {
+
+    /// Visits every node of the AST counting numeric literals that can't be exactly represented
+    /// at the analyser precision. Packed strings and large integers are ignored as they are emitted
+    /// by bit pattern rather than rounded.
+    fn visit(&mut self, node: &mut ASTNode) {
+        if let ASTNode::LITERAL(literal) = node {
+            let value = match *literal {
+                Literal::FLOAT(value) => value,
+                Literal::INTEGER(value) if value > MAX_EXACT_INTEGER => return,
+                Literal::INTEGER(value) => value as f64,
+                Literal::BOOL(_) | Literal::PACKEDSTRING(_) | Literal::NULL => return
+            };
+            if self.precision == 32 && (value as f32) as f64 != value {
+                self.rounded_values += 1;
+            }
+            return;
+        }
+
+        for child in node.children() {
+            self.visit(child);
+        }
+    }
+
+    /// Counts the number of literal values in a program that will be rounded when
+    /// emitted at a given precision.
+    /// @root: Root node of the AST to analyse
+    /// @precision: Precision in bits of the values emitted, either 32 or 64
+    /// @return: count of literal values that lose precision
+    pub fn count_rounded_values(root: &mut ASTNode, precision: usize) -> usize {
+        let mut analyser = Self {
+            precision,
+            rounded_values: 0
+        };
+
+        analyser.visit(root);
+
+        analyser.rounded_values
+    }
+}
@@ -2,13 +2,46 @@ use std::cmp::max;
 use barracuda_common::BarracudaInstructions;
 use barracuda_common::ProgramCode;
 
+/// Result of estimating the stack size of a program.
+/// If either max_depth_reached or recursive is set the estimate is not complete and callers
+/// should fall back to a conservative stack size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackEstimate {
+    /// Max stack size found from following all execution paths
+    pub max_stacksize: usize,
+
+    /// Instruction about to be executed when the stack first reached max_stacksize
+    pub peak_instruction: usize,
+
+    /// Deepest branch depth followed during analysis
+    pub depth_reached: usize,
+
+    /// Set if analysis stopped early due to reaching the max branching depth
+    pub max_depth_reached: bool,
+
+    /// Set if a jump was found that re-enters code with a larger stack, i.e. recursion.
+    /// The stack usage of these programs is unbounded at compile time.
+    pub recursive: bool
+}
+
+impl StackEstimate {
+    /// Returns true if the estimate covers every execution path of the program.
+    pub fn is_bounded(&self) -> bool {
+        !(self.max_depth_reached || self.recursive)
+    }
+}
+
 pub struct StackEstimator {
 
     /// Note max depth is used as a precaution since stack estimator works of program code
     /// it is possible to define a endless recursive program using this representation. Its
     /// unlikely that the backend would generate a recursive program to this degree.
     max_depth: usize,
-    max_depth_reached: bool
+    max_depth_reached: bool,
+    depth_reached: usize,
+    recursive: bool,
+    peak_stacksize: usize,
+    peak_instruction: usize
 }
 
 impl StackEstimator {
@@ -21,8 +54,10 @@ impl StackEstimator {
     /// @stack_size: Stack size estimate entering this execution path
     /// @depth: Current recursive depth of following these statements. On reaching self.max_depth
     ///         stack size is returned and the flag self.max_depth_reached is set.
+    /// @jump_targets: Static jump targets entered on this execution path with the stack size at entry.
+    ///         Re-entering a target with a larger stack sets the flag self.recursive and stops the path.
     /// @return: max_expected_stack_size from following the execution path
-    fn follow_execution_path(&mut self, code: &ProgramCode, initial_pc: usize, initial_stack_size: usize, depth: usize) -> usize {
+    fn follow_execution_path(&mut self, code: &ProgramCode, initial_pc: usize, initial_stack_size: usize, depth: usize, mut jump_targets: Vec<(usize, usize)>) -> usize {
         let mut pc = initial_pc;
         let mut stack_size = initial_stack_size;
         let mut max_stack_size = 0;
@@ -31,6 +66,7 @@ impl StackEstimator {
         let mut last_value = None;
 
         // Check recursive depth
+        self.depth_reached = max(self.depth_reached, depth);
         if depth >= self.max_depth {
             self.max_depth_reached = true;
             return stack_size;
@@ -38,6 +74,7 @@ impl StackEstimator {
         // Follow Execution
         while let Some(instruction) = code.instructions.get(pc) {
             max_stack_size = max(max_stack_size, stack_size);
+            self.record_stack_size(pc, stack_size);
             match instruction {
                 BarracudaInstructions::OP => {
                     if let Some(op) = code.operations.get(pc) {
@@ -60,6 +97,9 @@ impl StackEstimator {
                     // as this is the only context for this action at present
                     if let Some(address) = last_value {
                         pc = usize::from_be_bytes((*address).to_be_bytes());
+                        if !self.enter_jump_target(&mut jump_targets, pc, stack_size) {
+                            break;
+                        }
                     } else {
                         break;
                     }
@@ -73,11 +113,14 @@ impl StackEstimator {
                         // Follow true path if not matching current execution path
                         // as this implies a loop iteration with no expected change.
                         if stack_size != initial_stack_size && pc + 1 != initial_pc {
-                            max_stack_size = max(max_stack_size,self.follow_execution_path(code, pc + 1, stack_size, depth + 1));
+                            max_stack_size = max(max_stack_size,self.follow_execution_path(code, pc + 1, stack_size, depth + 1, jump_targets.clone()));
                         }
 
                         // Follow false path
                         pc = false_pc;
+                        if !self.enter_jump_target(&mut jump_targets, pc, stack_size) {
+                            break;
+                        }
                     } else {
                         break;
                     }
@@ -97,27 +140,59 @@ impl StackEstimator {
         };
 
         max_stack_size = max(max_stack_size, stack_size);
+        self.record_stack_size(pc, stack_size);
         return max_stack_size;
     }
 
+    /// Records where the largest stack size across all execution paths occurs
+    /// @pc: Instruction about to be executed
+    /// @stack_size: Stack size estimate before executing the instruction
+    fn record_stack_size(&mut self, pc: usize, stack_size: usize) {
+        if stack_size > self.peak_stacksize {
+            self.peak_stacksize = stack_size;
+            self.peak_instruction = pc;
+        }
+    }
+
+    /// Records entering a static jump target on the current execution path.
+    /// A target entered again with a larger stack can only be reached through recursion, as
+    /// every loop iteration leaves the stack as it found it, so following it would never end.
+    /// @jump_targets: Static jump targets entered on this execution path
+    /// @target: Address being jumped to
+    /// @stack_size: Stack size estimate when jumping
+    /// @return: true if the execution path should continue to be followed
+    fn enter_jump_target(&mut self, jump_targets: &mut Vec<(usize, usize)>, target: usize, stack_size: usize) -> bool {
+        if jump_targets.iter().any(|(entered, entered_stack_size)| *entered == target && *entered_stack_size < stack_size) {
+            self.recursive = true;
+            return false;
+        }
+        jump_targets.push((target, stack_size));
+        true
+    }
+
     /// Estimates the max stack size from executing of a program.
-    /// If max_depth_reached is true then the estimate is not complete.
+    /// If the estimate is not bounded (see StackEstimate::is_bounded) then it is not complete.
     /// @code: ProgramCode to follow the execution of
     /// @max_depth: Max recursive depth to follow when doing branch analysis
-    /// @return (max_stacksize: usize, max_depth_reached: bool)
-    pub fn estimate_max_stacksize(code: &ProgramCode, max_depth: usize) -> (usize, bool) {
+    /// @return StackEstimate describing the max stack size found and how complete it is
+    pub fn estimate_max_stacksize(code: &ProgramCode, max_depth: usize) -> StackEstimate {
         let mut estimator = Self {
             max_depth,
-            max_depth_reached: false
+            max_depth_reached: false,
+            depth_reached: 0,
+            recursive: false,
+            peak_stacksize: 0,
+            peak_instruction: 0
         };
 
-        let max_stacksize = estimator.follow_execution_path(code, 0, 0, 0);
+        let max_stacksize = estimator.follow_execution_path(code, 0, 0, 0, vec![]);
 
-        if estimator.max_depth_reached {
-            println!("MAX DEPTH REACHED!");
-        }
-
-
-        return (max_stacksize, estimator.max_depth_reached);
+        return StackEstimate {
+            max_stacksize,
+            peak_instruction: estimator.peak_instruction,
+            depth_reached: estimator.depth_reached,
+            max_depth_reached: estimator.max_depth_reached,
+            recursive: estimator.recursive
+        };
     }
 }
@@ -4,6 +4,7 @@ use super::super::ast::{
     AbstractSyntaxTree,
     ASTNode,
     Literal,
+    MAX_EXACT_INTEGER,
     BinaryOperation,
     UnaryOperation
 };
@@ -11,6 +12,7 @@ use super::builtin_functions::BARRACUDA_BUILT_IN_FUNCTIONS;
 
 use barracuda_common::{
     ProgramCode,
+    SymbolLocation,
     BarracudaInstructions as INSTRUCTION,
     FixedBarracudaOperators as OP,
 };
@@ -23,11 +25,14 @@ use crate::compiler::ast::{
     symbol_table::SymbolType
 };
 use crate::compiler::backend::analysis::stack_estimator::StackEstimator;
+use crate::compiler::backend::analysis::precision_analyser::PrecisionAnalyser;
 use crate::compiler::backend::program_code_builder::BarracudaProgramCodeBuilder;
 use crate::compiler::semantic_analyser::function_tracker::{FunctionTracker, FunctionImplementation};
 
 use crate::compiler::PrimitiveDataType;
 use crate::compiler::Qualifier;
+use crate::compiler::CompilerWarning;
+use crate::compiler::utils::pack_string_to_f64_array;
 
 /// BarracudaByteCodeGenerator is a Backend for Barracuda
 /// It generates program code from an Abstract Syntax Tree
@@ -48,9 +53,25 @@ pub struct BarracudaByteCodeGenerator {
     function_labels: HashMap<String, Vec<u64>>,
     functions: HashMap<String, FunctionTracker>,
 
+    // Parameter count of the function implementation being generated
+    // tuple return values are stored in the caller's frame below the parameters
+    current_parameter_count: usize,
+
     // Max analysis branching depth
     // used for estimating the stack depth of a program
-    max_analysis_branch_depth: usize
+    max_analysis_branch_depth: usize,
+
+    // Level 0 generates every statement as written
+    // Level 1 elides loops that can never execute
+    optimisation_level: usize,
+
+    // Emits runtime checks for array accesses with dynamic indices
+    // Out of range accesses jump to a fault handler at the end of the program
+    bounds_checking: bool,
+    bounds_fault_label: Option<u64>,
+
+    // Rounds numeric literals to the output precision before emitting them
+    quantise_literals: bool
 }
 
 impl BackEndGenerator for BarracudaByteCodeGenerator {
@@ -61,7 +82,12 @@ impl BackEndGenerator for BarracudaByteCodeGenerator {
             symbol_tracker: ScopeTracker::default(),
             function_labels: HashMap::default(),
             functions: HashMap::default(),
+            current_parameter_count: 0,
             max_analysis_branch_depth: 512,
+            optimisation_level: 0,
+            bounds_checking: false,
+            bounds_fault_label: None,
+            quantise_literals: false,
         }
     }
 
@@ -70,14 +96,17 @@ impl BackEndGenerator for BarracudaByteCodeGenerator {
         // Create symbol tracker
         self.symbol_tracker = ScopeTracker::new(tree.get_symbol_table());
         self.functions = tree.get_functions();
+        let mut warnings = tree.get_warnings();
 
-        // Generate built-in functions
-        self.generate_builtin_functions();
+        // Count values that will be rounded at the output precision
+        let mut tree_root_node = tree.into_root();
+        let precision = self.builder.get_precision();
+        let rounded_values = PrecisionAnalyser::count_rounded_values(&mut tree_root_node, precision);
 
         // Generate program
-        let tree_root_node = tree.into_root();
         self.builder.comment(String::from("PROGRAM START"));
         self.generate_node( &tree_root_node);
+        self.generate_bounds_fault_handler();
 
         // Finalise and attach variable header
         let header: Vec<f64> = vec![
@@ -93,13 +122,31 @@ impl BackEndGenerator for BarracudaByteCodeGenerator {
         let mut code = self.builder.finalize_with_header(header);
 
         // Estimate stack size
-        let (stacksize, max_depth_reached) = StackEstimator::estimate_max_stacksize(&code, self.max_analysis_branch_depth);
-        code.max_stack_size = if max_depth_reached {
-            stacksize + Self::default_max_stacksize()
+        // Recursive or too deeply branching programs can't be fully estimated so are given extra room
+        let estimate = StackEstimator::estimate_max_stacksize(&code, self.max_analysis_branch_depth);
+        code.max_stack_size = if estimate.is_bounded() {
+            estimate.max_stacksize
         } else {
-            stacksize
+            estimate.max_stacksize + Self::default_max_stacksize()
         };
-
+        // Shown in decorated output to find which part of a program needs the most stack
+        code.push_comment_at(estimate.peak_instruction, format!("PEAK STACK SIZE {}", estimate.max_stacksize));
+        code.function_count = self.functions.values()
+            .map(|function| function.get_implementations().len())
+            .sum();
+        // Only called built-ins are registered in the function labels
+        code.builtin_functions = self.function_labels.iter()
+            .filter(|(_, labels)| labels[1] == 1)
+            .map(|(name, _)| name.clone())
+            .collect();
+        code.builtin_functions.sort();
+
+        if rounded_values > 0 {
+            warnings.push(CompilerWarning::PrecisionLoss { rounded_values, precision });
+        }
+        for warning in warnings {
+            code.push_warning(warning.to_string());
+        }
 
         return code;
     }
@@ -111,6 +158,18 @@ impl BackEndGenerator for BarracudaByteCodeGenerator {
     fn set_precision(&mut self, precision: usize) {
         self.builder.set_precision(precision);
     }
+
+    fn set_optimisation_level(&mut self, optimisation_level: usize) {
+        self.optimisation_level = optimisation_level;
+    }
+
+    fn set_bounds_checking(&mut self, bounds_checking: bool) {
+        self.bounds_checking = bounds_checking;
+    }
+
+    fn set_quantise_literals(&mut self, quantise_literals: bool) {
+        self.quantise_literals = quantise_literals;
+    }
 }
 
 /// # Description
@@ -207,6 +266,23 @@ impl BarracudaByteCodeGenerator {
     /// This is done to ensure that only backend processed symbols are considered in scope.
     fn add_symbol(&mut self, name: String) {
         self.symbol_tracker.add_symbol(name.clone());
+
+        // Record where variables are stored for the symbol map
+        let location = match self.symbol_tracker.find_symbol(&name).unwrap().symbol_type() {
+            SymbolType::Variable(_, qualifier) => {
+                let frame_offset = self.symbol_tracker.get_local_id(&name).unwrap() as i64 + 1;
+                match (self.symbol_tracker.get_array_id(&name), qualifier) {
+                    (Some(address), Qualifier::MUTABLE) => SymbolLocation::MutableArray { frame_offset, address },
+                    (Some(address), Qualifier::CONSTANT) => SymbolLocation::ConstantArray { frame_offset, address },
+                    (None, _) => SymbolLocation::Stack(frame_offset)
+                }
+            }
+            SymbolType::Parameter(_, _) => {
+                SymbolLocation::Stack(-(self.symbol_tracker.get_param_id(&name).unwrap() as i64 + 2))
+            }
+            _ => return
+        };
+        self.builder.symbol(name, location);
     }
 }
 
@@ -238,6 +314,9 @@ impl BarracudaByteCodeGenerator {
                 ASTNode::ARRAY { .. } => {
                     panic!("Arrays literals can only be used for direct assignment!");
                 }
+                ASTNode::TUPLE { items } => {
+                    self.generate_tuple(items)
+                }
                 ASTNode::UNARY_OP { op, expression } => {
                     self.generate_unary_op(op, expression)
                 }
@@ -251,13 +330,16 @@ impl BarracudaByteCodeGenerator {
                     self.generate_array_index(index, expression, datatype, qualifier)
                 }
                 ASTNode::FUNC_CALL { identifier, arguments } => {
-                    self.generate_function_call(identifier, arguments)
+                    self.generate_function_call(identifier, arguments, datatype)
                 }
                 _ => panic!("Malformed AST! Node {:?} should not be inside a typed node.", node)
             }
             ASTNode::CONSTRUCT { identifier, expression, .. } => {
                 self.generate_construct_statement(identifier, expression);
             }
+            ASTNode::DESTRUCTURE { identifiers, expression, .. } => {
+                self.generate_destructure_statement(identifiers, expression);
+            }
             ASTNode::EMPTY_CONSTRUCT { identifier, .. } => {
                 self.generate_empty_construct_statement(identifier);
             }
@@ -270,6 +352,9 @@ impl BarracudaByteCodeGenerator {
             ASTNode::PRINT { expression } => {
                 self.generate_print_statement(expression)
             }
+            ASTNode::FENCE => {
+                self.generate_fence_statement()
+            }
             ASTNode::RETURN { expression } => {
                 self.generate_return_statement(expression)
             }
@@ -291,6 +376,9 @@ impl BarracudaByteCodeGenerator {
             ASTNode::STATEMENT_LIST(statement_list) => {
                 self.generate_statement_list(statement_list)
             }
+            ASTNode::SOURCE_LINE { line, statement } => {
+                self.generate_source_line(*line, statement)
+            }
             ASTNode::SCOPE_BLOCK { inner, scope } => {
                 self.generate_scope_block(inner, scope);
             }
@@ -319,30 +407,17 @@ impl BarracudaByteCodeGenerator {
                 self.builder.emit_op(OP::LDNX);
                 for _n in 0..ptr_depth {
                     if _n == ptr_depth - 1 {
-                        match datatype {
-                            DataType::PRIMITIVE(primitive) | DataType::ENVIRONMENTVARIABLE(primitive) => {
-                                match primitive {
-                                    PrimitiveDataType::F128 => panic!("F128 not currently supported in environment variables"),
-                                    PrimitiveDataType::F64 => self.builder.emit_op(OP::READ_F64),
-                                    PrimitiveDataType::F32 => self.builder.emit_op(OP::READ_F32),
-                                    PrimitiveDataType::F16 => panic!("F16 not currently supported in environment variables"),
-                                    PrimitiveDataType::F8 => panic!("F8 not currently supported in environment variables"),
-                                    PrimitiveDataType::I128 => panic!("I128 not currently supported in environment variables"),
-                                    PrimitiveDataType::I64 => self.builder.emit_op(OP::READ_I64),
-                                    PrimitiveDataType::I32 => self.builder.emit_op(OP::READ_I32),
-                                    PrimitiveDataType::I16 => panic!("I16 not currently supported in environment variables"),
-                                    PrimitiveDataType::I8 => panic!("I8 not currently supported in environment variables"),
-                                    PrimitiveDataType::Bool => self.builder.emit_op(OP::READ_F64),
-                                    PrimitiveDataType::String => self.builder.emit_op(OP::READ_F64),
-                                }
-                            }
-                            _ => panic!("Datatype {:?} must be a primitive!", datatype)
-                        }
+                        self.generate_typed_read(&datatype);
                     }
                     else {
                         self.builder.emit_op(OP::PTR_DEREF);
                     }
                 }
+                // Environment variables can hold any value so booleans are normalised to 1 or 0
+                if datatype.is_bool() {
+                    self.builder.emit_value(0.0);
+                    self.builder.emit_op(OP::NEQ);
+                }
             }
             SymbolType::Parameter(_datatype,_qualifier) => {
                 let param_id = self.symbol_tracker.get_param_id(name).unwrap();
@@ -353,6 +428,44 @@ impl BarracudaByteCodeGenerator {
         }
     }
 
+    /// Reads a value of the given type from host memory at the address on top of the stack
+    fn generate_typed_read(&mut self, datatype: &DataType) {
+        match datatype {
+            DataType::PRIMITIVE(primitive) | DataType::ENVIRONMENTVARIABLE(primitive) => {
+                match primitive {
+                    PrimitiveDataType::F128 => panic!("F128 not currently supported in environment variables"),
+                    PrimitiveDataType::F64 => self.builder.emit_op(OP::READ_F64),
+                    PrimitiveDataType::F32 => self.builder.emit_op(OP::READ_F32),
+                    PrimitiveDataType::F16 => panic!("F16 not currently supported in environment variables"),
+                    PrimitiveDataType::F8 => panic!("F8 not currently supported in environment variables"),
+                    PrimitiveDataType::I128 => panic!("I128 not currently supported in environment variables"),
+                    PrimitiveDataType::I64 => self.builder.emit_op(OP::READ_I64),
+                    PrimitiveDataType::I32 => self.builder.emit_op(OP::READ_I32),
+                    PrimitiveDataType::I16 => panic!("I16 not currently supported in environment variables"),
+                    PrimitiveDataType::I8 => panic!("I8 not currently supported in environment variables"),
+                    PrimitiveDataType::Bool => self.builder.emit_op(OP::READ_F64),
+                    PrimitiveDataType::String => self.builder.emit_op(OP::READ_F64),
+                }
+            }
+            _ => panic!("Datatype {:?} must be a primitive!", datatype)
+        }
+    }
+
+    /// Writes the value on top of the stack to host memory at the address below it.
+    /// The VM only has typed writes for floats.
+    fn generate_typed_write(&mut self, datatype: &DataType) {
+        match datatype {
+            DataType::PRIMITIVE(primitive) | DataType::ENVIRONMENTVARIABLE(primitive) => {
+                match primitive {
+                    PrimitiveDataType::F64 | PrimitiveDataType::Bool => self.builder.emit_op(OP::WRITE_F64),
+                    PrimitiveDataType::F32 => self.builder.emit_op(OP::WRITE_F32),
+                    _ => panic!("{:?} can't currently be written through pointers to environment variables", primitive)
+                }
+            }
+            _ => panic!("Datatype {:?} must be a primitive!", datatype)
+        }
+    }
+
     fn generate_reference(&mut self, name: &String) {
         let symbol_result = self.symbol_tracker.find_symbol(name).unwrap();
 
@@ -361,6 +474,15 @@ impl BarracudaByteCodeGenerator {
                 let localvar_id = self.symbol_tracker.get_local_id(name).unwrap();
                 self.generate_local_var_address(localvar_id);
             }
+            // Environment variables held through pointers are referenced by their address in host memory
+            SymbolType::EnvironmentVariable(global_id, _datatype, _qualifier, ptr_levels) if ptr_levels.contains('*') => {
+                let ptr_depth = ptr_levels.matches("*").count();
+                self.builder.emit_value(f64::from_be_bytes(global_id.to_be_bytes()));
+                self.builder.emit_op(OP::LDNX);
+                for _ in 1..ptr_depth {
+                    self.builder.emit_op(OP::PTR_DEREF);
+                }
+            }
             SymbolType::Parameter(_datatype,_qualifier) => {
                 let param_id = self.symbol_tracker.get_param_id(name).unwrap();
                 self.generate_parameter_address(param_id);
@@ -371,15 +493,36 @@ impl BarracudaByteCodeGenerator {
 
     fn generate_literal(&mut self, literal: &Literal) {
         let literal_value = match *literal {
-            Literal::FLOAT(value) => { value }
-            Literal::INTEGER(value) => { value as f64 }
+            Literal::FLOAT(value) => { self.quantise(value) }
+            Literal::INTEGER(value) if value > MAX_EXACT_INTEGER => {
+                if self.builder.get_precision() != 64 {
+                    panic!("Integer literal {} is larger than 2^53 so needs 64 bit precision to keep its exact value!", value)
+                }
+                f64::from_bits(value)
+            }
+            Literal::INTEGER(value) => { self.quantise(value as f64) }
             Literal::BOOL(value) => { value as i64 as f64 }
             Literal::PACKEDSTRING(value) => { value }
+            Literal::NULL => { f64::from_bits(0) }
         };
 
         self.builder.emit_value(literal_value);
     }
 
+    /// Rounds a numeric value to the output precision when literal quantisation is enabled, so
+    /// emitted constants are exactly the values a VM of that precision will store.
+    /// Packed strings are bit patterns so aren't rounded.
+    fn quantise(&self, value: f64) -> f64 {
+        if !self.quantise_literals || self.builder.get_precision() != 32 {
+            return value;
+        }
+        let quantised = value as f32 as f64;
+        if quantised.is_infinite() && value.is_finite() {
+            panic!("Literal {:e} overflows f32!", value)
+        }
+        quantised
+    }
+
     fn generate_preallocated_array(&mut self, qualifier: &Box<ASTNode>, values: Vec<f64>, address: usize) {
         let qualifier = match qualifier.as_ref() {
             ASTNode::QUALIFIER(qualifier) => qualifier,
@@ -441,16 +584,71 @@ impl BarracudaByteCodeGenerator {
         position + 1
     }
 
+    /// Tuples are generated as consecutive values on the stack
+    fn generate_tuple(&mut self, items: &Vec<ASTNode>) {
+        for item in items {
+            self.generate_node(item);
+        }
+    }
+
     fn generate_unary_op(&mut self, op: &UnaryOperation, expression: &Box<ASTNode>) {
         self.generate_node(expression);
         match op {
             UnaryOperation::NOT => { self.builder.emit_op(OP::NOT) }
             UnaryOperation::NEGATE => { self.builder.emit_op(OP::NEGATE) }
-            UnaryOperation::PTR_DEREF => { self.builder.emit_op(OP::STK_READ) }
+            UnaryOperation::PLUS => {} // Leaves the value unchanged
+            // (x > 0) - (x < 0) with x duplicated rather than evaluated twice
+            UnaryOperation::SIGN => {
+                self.builder.emit_op(OP::DUP);
+                self.builder.emit_value(0.0);
+                self.builder.emit_op(OP::GT);
+                self.builder.emit_op(OP::SWAP);
+                self.builder.emit_value(0.0);
+                self.builder.emit_op(OP::LT);
+                self.builder.emit_op(OP::SUB);
+            }
+            // Pointers to environment variables point into host memory so are read with the width of their type
+            UnaryOperation::PTR_DEREF => match expression.get_type() {
+                DataType::POINTER(inner) if matches!(*inner, DataType::ENVIRONMENTVARIABLE(_)) => {
+                    self.generate_typed_read(&inner);
+                    // Booleans in host memory can hold any value so are normalised like environment variables
+                    if inner.is_bool() {
+                        self.builder.emit_value(0.0);
+                        self.builder.emit_op(OP::NEQ);
+                    }
+                }
+                // Heap values are held at the VM's precision so need no width
+                DataType::HEAPPOINTER(_) => self.builder.emit_op(OP::READ),
+                _ => self.builder.emit_op(OP::STK_READ)
+            }
         };
     }
 
     fn generate_binary_op(&mut self, op: &BinaryOperation, lhs: &Box<ASTNode>, rhs: &Box<ASTNode>) {
+        if let (BinaryOperation::POW, Some(exponent)) = (op, self.constant_index(rhs)) {
+            if lhs.get_type().is_integer() && rhs.get_type().is_integer() && exponent > 0 {
+                self.generate_integer_pow(lhs, exponent);
+                return;
+            }
+        }
+        if let (BinaryOperation::EQUAL | BinaryOperation::NOT_EQUAL, DataType::ARRAY(_, _)) = (op, lhs.get_type()) {
+            self.generate_array_comparison(op, lhs, rhs);
+            return;
+        }
+        // Pointers are compared by address. Their bit patterns aren't meaningful as floats
+        // so the difference is converted to a number before comparing it with 0.
+        if let (BinaryOperation::EQUAL | BinaryOperation::NOT_EQUAL, DataType::POINTER(_) | DataType::HEAPPOINTER(_)) = (op, lhs.get_type()) {
+            self.generate_node(lhs);
+            self.generate_node(rhs);
+            self.builder.emit_op(OP::SUB_PTR);
+            self.builder.emit_op(OP::LONGLONGTODOUBLE);
+            self.builder.emit_value(0.0);
+            match op {
+                BinaryOperation::EQUAL => self.builder.emit_op(OP::EQ),
+                _ => self.builder.emit_op(OP::NEQ)
+            }
+            return;
+        }
         self.generate_node(lhs);
         self.generate_node(rhs);
         match op {
@@ -460,6 +658,8 @@ impl BarracudaByteCodeGenerator {
             BinaryOperation::MUL   => { self.builder.emit_op(OP::MUL); }
             BinaryOperation::MOD   => { self.builder.emit_op(OP::FMOD); }
             BinaryOperation::POW   => { self.builder.emit_op(OP::POW); }
+            // Comparison and logical ops produce exactly 1.0 or 0.0 on the VM, so their results need
+            // no normalisation. Booleans from other sources are normalised where they're produced.
             BinaryOperation::EQUAL => { self.builder.emit_op(OP::EQ); }
             BinaryOperation::NOT_EQUAL => { self.builder.emit_op(OP::NEQ); }
             BinaryOperation::GREATER_THAN  => { self.builder.emit_op(OP::GT); }
@@ -469,10 +669,118 @@ impl BarracudaByteCodeGenerator {
             BinaryOperation::AND => { self.builder.emit_op(OP::AND); }
             BinaryOperation::OR  => { self.builder.emit_op(OP::OR); }
             BinaryOperation::LSHIFT => { self.builder.emit_op(OP::LSHIFT); }
+            // Signed integers shift arithmetically, copying the sign bit in so negatives stay negative,
+            // while unsigned integers shift logically, filling with zeros. There are no unsigned types
+            // and the VM has no logical shift op, so RSHIFT is used for every operand type.
             BinaryOperation::RSHIFT => { self.builder.emit_op(OP::RSHIFT); }
         };
     }
 
+    /// Compares two arrays of the same length element by element.
+    /// Array lengths are known at compile time so the comparisons are unrolled, jumping out at the
+    /// first mismatch. Array literals are compared item by item, other arrays keep their pointers
+    /// on the stack while their elements are loaded.
+    fn generate_array_comparison(&mut self, op: &BinaryOperation, lhs: &Box<ASTNode>, rhs: &Box<ASTNode>) {
+        let lhs_items = Self::array_literal_items(lhs);
+        let rhs_items = Self::array_literal_items(rhs);
+        let (equal, not_equal) = match op {
+            BinaryOperation::EQUAL => (1.0, 0.0),
+            _ => (0.0, 1.0)
+        };
+        let mismatch = self.builder.create_label();
+        let comparison_end = self.builder.create_label();
+
+        self.builder.comment(String::from("ARRAY COMPARISON"));
+        if lhs_items.is_none() {
+            self.generate_node(lhs);
+        }
+        if rhs_items.is_none() {
+            self.generate_node(rhs);
+        }
+        for position in 0..DataType::get_array_length(&lhs.get_type()) {
+            match &lhs_items {
+                Some(items) => self.generate_node(items[position]),
+                None => {
+                    // The lhs pointer is beneath the rhs pointer if both are on the stack
+                    match rhs_items {
+                        Some(_) => self.builder.emit_op(OP::DUP),
+                        None => self.builder.emit_op(OP::OVER)
+                    }
+                    self.generate_array_element_at(position, lhs.get_qualifier());
+                }
+            }
+            match &rhs_items {
+                Some(items) => self.generate_node(items[position]),
+                None => {
+                    self.builder.emit_op(OP::OVER);
+                    self.generate_array_element_at(position, rhs.get_qualifier());
+                }
+            }
+            self.builder.emit_op(OP::EQ);
+            self.builder.reference(mismatch);
+            self.builder.emit_instruction(INSTRUCTION::GOTO_IF);
+        }
+
+        let pointer_count = lhs_items.is_none() as usize + rhs_items.is_none() as usize;
+        for _ in 0..pointer_count {
+            self.builder.emit_op(OP::DROP);
+        }
+        self.builder.emit_value(equal);
+        self.builder.reference(comparison_end);
+        self.builder.emit_instruction(INSTRUCTION::GOTO);
+
+        self.builder.set_label(mismatch);
+        for _ in 0..pointer_count {
+            self.builder.emit_op(OP::DROP);
+        }
+        self.builder.emit_value(not_equal);
+        self.builder.set_label(comparison_end);
+    }
+
+    /// Loads the element at a constant position of the array pointed to by the top of the stack
+    fn generate_array_element_at(&mut self, position: usize, array_qualifier: Qualifier) {
+        self.builder.emit_value(f64::from_be_bytes(position.to_be_bytes()));
+        self.builder.emit_op(OP::ADD_PTR);
+        self.generate_array_element_load(array_qualifier);
+    }
+
+    /// Returns the items of an array literal flattened in memory order, or None if the node isn't
+    /// an array literal.
+    fn array_literal_items(node: &ASTNode) -> Option<Vec<&ASTNode>> {
+        match node {
+            ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
+                ASTNode::ARRAY { items, .. } => Some(items.iter().flat_map(|item| {
+                    Self::array_literal_items(item).unwrap_or_else(|| vec![item])
+                }).collect()),
+                _ => None
+            }
+            _ => None
+        }
+    }
+
+    /// Raises an integer to a constant positive power by repeated squaring, so the result is exact
+    /// rather than depending on the VM's floating point POW.
+    /// The base stays beneath the accumulator on the stack until the end.
+    fn generate_integer_pow(&mut self, base: &Box<ASTNode>, exponent: usize) {
+        self.generate_node(base);
+        if exponent == 1 {
+            return;
+        }
+        self.builder.comment(format!("INTEGER POW {}", exponent));
+        self.builder.emit_op(OP::DUP);
+        let bits = usize::BITS - exponent.leading_zeros();
+        for bit in (0..bits - 1).rev() {
+            self.builder.emit_op(OP::DUP);
+            self.builder.emit_op(OP::MUL);
+            if exponent & (1 << bit) != 0 {
+                self.builder.emit_op(OP::OVER);
+                self.builder.emit_op(OP::MUL);
+            }
+        }
+        self.builder.emit_op(OP::SWAP);
+        self.builder.emit_op(OP::DROP);
+    }
+
     fn generate_ternary_op(&mut self, condition: &Box<ASTNode>, true_branch: &Box<ASTNode>, false_branch: &Box<ASTNode>) {
         self.generate_node(condition);
         self.generate_node(true_branch);
@@ -481,34 +789,145 @@ impl BarracudaByteCodeGenerator {
     }
 
     fn generate_array_index(&mut self, index: &Box<ASTNode>, expression: &Box<ASTNode>, datatype: &DataType, _qualifier: &Qualifier) {   
-        // Generate code to determine the index.
+        // Fold multidimensional accesses with only constant indices into a single offset.
+        if let ASTNode::TYPED_NODE { inner, .. } = expression.as_ref() {
+            if let ASTNode::ARRAY_INDEX { .. } = inner.as_ref() {
+                if let Some((offset, array)) = self.constant_index_chain_offset(index, expression, datatype) {
+                    self.generate_node(array);
+                    self.builder.emit_value(f64::from_be_bytes(offset.to_be_bytes()));
+                    self.builder.emit_op(OP::ADD_PTR);
+                    if !matches!(datatype, DataType::ARRAY(_, _) | DataType::STRUCT(_, _)) {
+                        self.generate_array_element_load(expression.get_qualifier());
+                    }
+                    return;
+                }
+            }
+        }
+
+        // Nested arrays and structs are left as addresses, single values are loaded
         self.generate_node(expression);
+        self.generate_element_offset(index, &expression.get_type());
+        if !matches!(datatype, DataType::ARRAY(_, _) | DataType::STRUCT(_, _)) {
+            self.generate_array_element_load(expression.get_qualifier());
+        }
+    }
+
+    /// Offsets the address of an array, struct or environment variable on top of the stack to one
+    /// of its elements. Reads and assignments both address elements through this, one index or
+    /// member at a time from left to right, so chains like a[i].x are computed consistently.
+    /// Array indices are scaled by the length of the elements. Members are replaced with their
+    /// offset during analysis so are added directly.
+    fn generate_element_offset(&mut self, index: &ASTNode, datatype: &DataType) {
         self.generate_node(index);
-    
-        // Then, perform pointer arithmetic based on the datatype.
         match datatype {
-            DataType::ARRAY(_, _) => {
-                let array_length = DataType::get_array_length(&datatype);
-                self.builder.emit_value(array_length as f64);
-                self.builder.emit_op(OP::MUL_PTR);
-                self.builder.emit_op(OP::DOUBLETOLONGLONG);
-                self.builder.emit_op(OP::ADD_PTR);
+            DataType::ARRAY(inner, size) => {
+                self.generate_bounds_check(index, *size);
+                let array_length = DataType::get_array_length(inner);
+                if array_length > 1 {
+                    self.builder.emit_value(array_length as f64); // avoid unneccessary double to longlong conversion
+                    self.builder.emit_op(OP::MUL_PTR);
+                }
+            }
+            DataType::STRUCT(_, _) | DataType::ENVIRONMENTVARIABLE(_) => {}
+            _ => panic!("Datatype {:?} should be an array!", datatype)
+        }
+        self.builder.emit_op(OP::DOUBLETOLONGLONG);
+        self.builder.emit_op(OP::ADD_PTR);
+    }
+
+    /// Returns the datatype of the element of an array, struct or environment variable at an index.
+    fn element_datatype(&self, datatype: DataType, index: &ASTNode) -> DataType {
+        match datatype {
+            DataType::ARRAY(inner, _) => *inner,
+            DataType::STRUCT(_, mut fields) => fields.swap_remove(self.constant_index(index).unwrap()).1,
+            DataType::ENVIRONMENTVARIABLE(primitive) => DataType::PRIMITIVE(primitive),
+            _ => panic!("Datatype {:?} should be an array!", datatype)
+        }
+    }
+
+    /// Checks the index on top of the stack is within [0, size) when bounds checking is enabled.
+    /// Constant indices are checked at compile time so are skipped.
+    fn generate_bounds_check(&mut self, index: &ASTNode, size: usize) {
+        if !self.bounds_checking || self.constant_index(index).is_some() {
+            return;
+        }
+        let fault_label = match self.bounds_fault_label {
+            Some(label) => label,
+            None => {
+                let label = self.builder.create_label();
+                self.bounds_fault_label = Some(label);
+                label
+            }
+        };
+
+        self.builder.comment(String::from("BOUNDS CHECK"));
+        self.builder.emit_op(OP::DUP);
+        self.builder.emit_value(0.0);
+        self.builder.emit_op(OP::GTEQ);
+        self.builder.emit_op(OP::OVER);
+        self.builder.emit_value(size as f64);
+        self.builder.emit_op(OP::LT);
+        self.builder.emit_op(OP::AND);
+        self.builder.reference(fault_label);
+        self.builder.emit_instruction(INSTRUCTION::GOTO_IF);
+    }
+
+    /// Generates the handler that failed bounds checks jump to.
+    /// Prints an error and halts by running off the end of the program.
+    fn generate_bounds_fault_handler(&mut self) {
+        if let Some(fault_label) = self.bounds_fault_label {
+            let program_end = self.builder.create_label();
+            self.builder.reference(program_end);
+            self.builder.emit_instruction(INSTRUCTION::GOTO);
+
+            self.builder.comment(String::from("BOUNDS FAULT"));
+            self.builder.set_label(fault_label);
+            for packed_chars in pack_string_to_f64_array("Array index out of bounds!\n", 64) {
+                self.builder.emit_value(packed_chars);
+                self.builder.emit_op(OP::PRINTC);
+            }
+            self.builder.set_label(program_end);
+        }
+    }
+
+    /// Loads the array element at the address on top of the stack
+    /// from user space depending on the qualifier of the array.
+    fn generate_array_element_load(&mut self, array_qualifier: Qualifier) {
+        match array_qualifier {
+            Qualifier::CONSTANT => {
+                self.builder.emit_op(OP::LDCUX);
             },
-            _ => {
-                self.builder.emit_op(OP::DOUBLETOLONGLONG);
-                self.builder.emit_op(OP::ADD_PTR);
-                let array_qualifier = expression.get_qualifier();
-                match array_qualifier {
-                    Qualifier::CONSTANT => {
-                        self.builder.emit_op(OP::LDCUX);
-                    },
-                    Qualifier::MUTABLE => {
-                        self.builder.emit_op(OP::LDNXPTR);
-                        self.builder.emit_op(OP::READ_F64);
-                    }
+            Qualifier::MUTABLE => {
+                self.builder.emit_op(OP::LDNXPTR);
+                self.builder.emit_op(OP::READ_F64);
+            }
+        }
+    }
+
+    /// Computes the element offset of an array index chain where every index is a compile time constant.
+    /// @return: (offset, indexed array expression) or None if any index in the chain isn't constant
+    fn constant_index_chain_offset<'a>(&self, index: &Box<ASTNode>, expression: &'a Box<ASTNode>, datatype: &DataType) -> Option<(usize, &'a Box<ASTNode>)> {
+        let offset = self.constant_index(index)? * DataType::get_array_length(datatype);
+        if let ASTNode::TYPED_NODE { datatype: inner_datatype, inner, .. } = expression.as_ref() {
+            if let ASTNode::ARRAY_INDEX { index, expression } = inner.as_ref() {
+                let (inner_offset, array) = self.constant_index_chain_offset(index, expression, inner_datatype)?;
+                return Some((offset + inner_offset, array));
+            }
+        }
+        Some((offset, expression))
+    }
+
+    /// Returns the value of an index if it is a non-negative whole literal.
+    fn constant_index(&self, index: &ASTNode) -> Option<usize> {
+        if let ASTNode::TYPED_NODE { inner, .. } = index {
+            if let ASTNode::LITERAL(literal) = inner.as_ref() {
+                let value = self.extract_literal_value(literal);
+                if value >= 0.0 && value.fract() == 0.0 {
+                    return Some(value as usize);
                 }
             }
         }
+        None
     }
     
 
@@ -542,13 +961,21 @@ impl BarracudaByteCodeGenerator {
         }
     }
 
+    /// Each tuple value is left on the stack in order so they become the destructured local variables
+    fn generate_destructure_statement(&mut self, identifiers: &Vec<ASTNode>, expression: &Box<ASTNode>) {
+        for identifier in identifiers {
+            self.add_symbol(identifier.identifier_name().unwrap());
+        }
+        self.generate_node(expression);
+    }
+
     fn generate_empty_construct_statement(&mut self, identifier: &Box<ASTNode>) {
         let identifier_name = identifier.identifier_name().unwrap();
         self.add_symbol(identifier_name.clone());
     
         let datatype = identifier.get_type();
         match datatype {
-            DataType::ARRAY(_, _) => {
+            DataType::ARRAY(_, _) | DataType::STRUCT(_, _) => {
                 let array_size = DataType::get_array_length(&datatype); // Fetch array size
                 let address = self.symbol_tracker.get_array_id(&identifier_name).unwrap();
                 
@@ -556,6 +983,7 @@ impl BarracudaByteCodeGenerator {
                 let qualifier = identifier.get_qualifier();
                 self.generate_preallocated_array(&Box::new(ASTNode::QUALIFIER(qualifier)), vec![0.0; array_size], address);
             },
+            // Scalars start as zero, which for pointers is null
             _ => {
                 self.builder.emit_value(0.0);
             }
@@ -577,7 +1005,7 @@ impl BarracudaByteCodeGenerator {
                     let local_var_id = self.symbol_tracker.get_local_id(&identifier_name).unwrap();
                     self.generate_local_var_address(local_var_id);
                     match datatype {
-                        DataType::ARRAY(_,_) => {
+                        DataType::ARRAY(_,_) | DataType::STRUCT(_, _) => {
                             self.builder.emit_op(OP::STK_READ);
                             self.generate_array_assignment_statement(array_index, expression, datatype)
                         },
@@ -642,22 +1070,17 @@ impl BarracudaByteCodeGenerator {
 
     fn generate_array_assignment_statement(&mut self, array_index: &Vec<ASTNode>, expression: &ASTNode, mut datatype: DataType) {
         //we have pointer as usize on the stack
-        for index in array_index {
-            datatype = match datatype {
-                DataType::ARRAY(inner, _) => {
-                    self.generate_node(index);
-                    let array_length = DataType::get_array_length(&inner);
-                    if array_length > 1 {
-                        self.builder.emit_value(array_length as f64); // avoid unneccessary double to longlong conversion
-                        self.builder.emit_op(OP::MUL_PTR);
-                    }
-                    self.builder.emit_op(OP::DOUBLETOLONGLONG);
-                    self.builder.emit_op(OP::ADD_PTR);
-                    *inner
-                },
-                _ => panic!("Datatype {:?} should be an array!", datatype)
+        if let DataType::ARRAY(inner, _) = &datatype {
+            if matches!(**inner, DataType::PRIMITIVE(PrimitiveDataType::String)) && array_index.len() == 1 {
+                self.generate_string_character_assignment(&array_index[0], expression);
+                return;
             }
         }
+        for index in array_index {
+            self.generate_element_offset(index, &datatype);
+            // Fields are single values so a member's offset is also its position in the struct
+            datatype = self.element_datatype(datatype, index);
+        }
 
         match datatype {
             DataType::ARRAY(_, _) => match expression {
@@ -675,6 +1098,21 @@ impl BarracudaByteCodeGenerator {
         }
     }
 
+    /// Writes a single character of a mutable string.
+    /// The index is the byte offset of the character from the start of the string, as characters
+    /// are packed several to a value.
+    fn generate_string_character_assignment(&mut self, index: &ASTNode, expression: &ASTNode) {
+        let offset = self.constant_index(index).unwrap();
+        let value_size = 8;
+        self.builder.emit_value(f64::from_be_bytes((offset / value_size).to_be_bytes()));
+        self.builder.emit_op(OP::ADD_PTR);
+        self.builder.emit_op(OP::LDNXPTR);
+        self.builder.emit_value(f64::from_be_bytes((offset % value_size).to_be_bytes()));
+        self.builder.emit_op(OP::ADD_PTR);
+        self.generate_node(expression);
+        self.builder.emit_op(OP::WRITE_CHAR);
+    }
+
     fn generate_array_assignment(&mut self, items: &Vec<ASTNode>, mut position: usize) -> usize {
         for item in items {
             match item {
@@ -695,17 +1133,30 @@ impl BarracudaByteCodeGenerator {
     }
 
     fn generate_regular_assignment_statement(&mut self, expression: &ASTNode, array_index: &Vec<ASTNode>, mut datatype: DataType, pointer_level: usize) {
+        // Each pointer is read from where the previous one pointed, which is the heap after a heap pointer
+        let mut on_heap = false;
         for _ in 0..pointer_level {
-            datatype = match datatype {
-                DataType::POINTER(inner) => *inner,
+            self.builder.emit_op(if on_heap { OP::READ } else { OP::STK_READ });
+            (datatype, on_heap) = match datatype {
+                DataType::POINTER(inner) => (*inner, false),
+                DataType::HEAPPOINTER(inner) => (*inner, true),
                 _ => panic!("Datatype {:?} should be a pointer!", datatype)
             };
-            self.builder.emit_op(OP::STK_READ);
         }
         match datatype {
+            // The address is of the slot holding the array, the array itself is reached through it
             DataType::ARRAY(_, _) => {
+                self.builder.emit_op(OP::STK_READ);
                 self.generate_array_assignment_statement(array_index, expression, datatype);
             }
+            DataType::ENVIRONMENTVARIABLE(_) if pointer_level > 0 => {
+                self.generate_node(expression);
+                self.generate_typed_write(&datatype);
+            }
+            _ if on_heap => {
+                self.generate_node(expression);
+                self.builder.emit_op(OP::WRITE);
+            }
             _ => {
                 self.generate_node(expression);
                 self.builder.emit_op(OP::STK_WRITE);
@@ -724,7 +1175,8 @@ impl BarracudaByteCodeGenerator {
                         match **sub_datatype {
                             DataType::PRIMITIVE(primitive) => {
                                 match primitive {
-                                    PrimitiveDataType::F8 | PrimitiveDataType::F16 | PrimitiveDataType::F32 | PrimitiveDataType::F64 | PrimitiveDataType::F128 => {
+                                    PrimitiveDataType::F8 | PrimitiveDataType::F16 | PrimitiveDataType::F32 | PrimitiveDataType::F64 | PrimitiveDataType::F128
+                                  | PrimitiveDataType::I8 | PrimitiveDataType::I16 | PrimitiveDataType::I32 | PrimitiveDataType::I64 | PrimitiveDataType::I128 => {
                                         // Iterate over array and print each element
                                         for i in 0..*size {
                                             self.builder.emit_op(OP::DUP); // Duplicate the position of start of the array.
@@ -778,6 +1230,10 @@ impl BarracudaByteCodeGenerator {
                             PrimitiveDataType::F8 | PrimitiveDataType::F16 | PrimitiveDataType::F32 | PrimitiveDataType::F64 | PrimitiveDataType::F128 => {
                                 self.builder.emit_op(OP::PRINTFF);
                             }
+                            // Integers have their own print op so the VM formats them as integers rather than floats
+                            PrimitiveDataType::I8 | PrimitiveDataType::I16 | PrimitiveDataType::I32 | PrimitiveDataType::I64 | PrimitiveDataType::I128 => {
+                                self.builder.emit_op(OP::PRINTI);
+                            }
                             PrimitiveDataType::String => {
                                 self.builder.emit_op(OP::PRINTC);
                             }
@@ -797,9 +1253,30 @@ impl BarracudaByteCodeGenerator {
         }
     }
 
-    fn generate_return_statement(&mut self, expression: &Box<ASTNode>) {
-        // Store return result in register
-        self.generate_set_return_store(expression);
+    fn generate_fence_statement(&mut self) {
+        self.builder.comment(String::from("FENCE"));
+        self.builder.emit_op(OP::FENCE);
+    }
+
+    fn generate_return_statement(&mut self, expression: &Box<Option<ASTNode>>) {
+        // Returns without a value leave the return store untouched
+        let expression = match expression.as_ref() {
+            Some(expression) => expression,
+            None => return self.generate_return_handler()
+        };
+        match expression.get_type() {
+            // Store each tuple value in the slots the caller reserved below the parameters
+            DataType::TUPLE(items) => {
+                self.generate_node(expression);
+                for i in (0..items.len()).rev() {
+                    self.generate_parameter_address(self.current_parameter_count + items.len() - 1 - i);
+                    self.builder.emit_op(OP::SWAP);
+                    self.builder.emit_op(OP::STK_WRITE);
+                }
+            }
+            // Store return result in register
+            _ => self.generate_set_return_store(expression)
+        }
         self.generate_return_handler();
     }
 
@@ -822,6 +1299,16 @@ impl BarracudaByteCodeGenerator {
     }
 
     fn generate_branch_statement(&mut self, condition: &Box<ASTNode>, if_branch: &Box<ASTNode>, else_branch: &Box<Option<ASTNode>>) {
+        let branch_end = self.builder.create_label();
+        self.generate_branch_arm(condition, if_branch, else_branch, branch_end);
+        self.builder.set_label(branch_end);
+        self.builder.comment(String::from("IF END"));
+    }
+
+    /// Generates a single arm of an if/else if/else chain.
+    /// An else branch that is itself a branch is generated as the next arm of the chain so every
+    /// arm skips straight to the shared end label, rather than each level having its own.
+    fn generate_branch_arm(&mut self, condition: &Box<ASTNode>, if_branch: &Box<ASTNode>, else_branch: &Box<Option<ASTNode>>, branch_end: u64) {
         let if_end = self.builder.create_label();
 
         // Conditional Jump
@@ -841,23 +1328,33 @@ impl BarracudaByteCodeGenerator {
                 self.builder.set_label(if_end);
             },
             Some(else_branch) => {
-                let else_end = self.builder.create_label();
-
-                // Skip else block if encountered after running if block
-                self.builder.reference(else_end);
+                // Skip the rest of the chain if encountered after running if block
+                self.builder.reference(branch_end);
                 self.builder.emit_instruction(INSTRUCTION::GOTO);
                 self.builder.set_label(if_end);
 
-                // Generate else block
-                self.builder.comment(String::from("ELSE BRANCH"));
-                self.generate_node(else_branch);
-                self.builder.set_label(else_end);
+                match else_branch {
+                    ASTNode::BRANCH { condition, if_branch, else_branch } => {
+                        self.builder.comment(String::from("ELSE IF BRANCH"));
+                        self.generate_branch_arm(condition, if_branch, else_branch, branch_end);
+                    }
+                    _ => {
+                        // Generate else block
+                        self.builder.comment(String::from("ELSE BRANCH"));
+                        self.generate_node(else_branch);
+                    }
+                }
             }
         }
-        self.builder.comment(String::from("IF END"));
     }
 
     fn generate_while_statement(&mut self, condition: &Box<ASTNode>, body: &Box<ASTNode>) {
+        // Loop never executes so nothing needs to be generated
+        if self.optimisation_level >= 1 && condition.is_constant_false() {
+            self.builder.comment(String::from("WHILE ELIDED"));
+            return;
+        }
+
         let while_start = self.builder.create_label();
         let while_exit = self.builder.create_label();
 
@@ -896,6 +1393,20 @@ impl BarracudaByteCodeGenerator {
                 // Start
                 self.builder.comment(String::from("FOR INIT"));
                 self.generate_node(initialization);
+
+                // Only an initialization declaring a loop variable leaves a value to drop
+                let declares_variable = matches!(initialization.as_ref(), ASTNode::CONSTRUCT { .. });
+
+                // Loop never executes so only the initialisation is kept
+                if self.optimisation_level >= 1 && condition.is_constant_false() {
+                    self.builder.comment(String::from("FOR ELIDED"));
+                    if declares_variable {
+                        self.builder.emit_op(OP::DROP);
+                    }
+                    self.symbol_tracker.exit_scope();
+                    return;
+                }
+
                 self.builder.set_label(for_start);
 
                 // Condition
@@ -919,7 +1430,9 @@ impl BarracudaByteCodeGenerator {
                 self.builder.set_label(for_exit);
                 self.builder.comment(String::from("FOR END"));
 
-                self.builder.emit_op(OP::DROP);
+                if declares_variable {
+                    self.builder.emit_op(OP::DROP);
+                }
 
                 self.symbol_tracker.exit_scope();
             }
@@ -937,8 +1450,8 @@ impl BarracudaByteCodeGenerator {
     
     fn generate_function_implementation(&mut self, implementation: FunctionImplementation) {
         let identifier_name = implementation.get_name();
-        // Create labels and assign them
-        let function_def_start = self.builder.create_label();
+        // Create labels and assign them. Functions called before their definition already have a start label
+        let function_def_start = self.function_label(&identifier_name);
         let function_def_end = self.builder.create_label();
 
         // Jump over function definition approaching from the top
@@ -961,7 +1474,10 @@ impl BarracudaByteCodeGenerator {
                 }
 
                 // Generate function body
+                let outer_parameter_count = self.current_parameter_count;
+                self.current_parameter_count = parameter_names.len();
                 self.generate_node(&inner);
+                self.current_parameter_count = outer_parameter_count;
 
                 self.symbol_tracker.exit_scope();
             }
@@ -973,29 +1489,45 @@ impl BarracudaByteCodeGenerator {
         self.builder.set_label(function_def_end);
         self.builder.comment(format!("FN {} END", &identifier_name));
 
-        // Add function symbol (Done after function to disallow recursion as it doesn't work at the moment)
+        // Add function symbol after its body. Calls jump to its label so recursive calls don't need the symbol
         if self.symbol_tracker.find_symbol(&identifier_name).is_some() {
             panic!("Identifier `{}` can't be assigned to function as it already exists!", identifier_name);
         }
         self.add_symbol(identifier_name.clone());
-        self.function_labels.insert(identifier_name.clone(), vec![function_def_start, 0]);
 
     }
 
+    /// Returns the start label of a function implementation, creating it if the implementation
+    /// hasn't been referenced before.
+    fn function_label(&mut self, identifier_name: &String) -> u64 {
+        if let Some(labels) = self.function_labels.get(identifier_name) {
+            return labels[0];
+        }
+        let label = self.builder.create_label();
+        self.function_labels.insert(identifier_name.clone(), vec![label, 0]);
+        label
+    }
+
     fn generate_parameter(&mut self, identifier: String) {
         self.add_symbol(identifier);
     }
 
-    fn generate_builtin_functions(&mut self)
-    {
-        for func in BARRACUDA_BUILT_IN_FUNCTIONS {
-            self.function_labels.insert(String::from(format!("__{}", func.to_string().to_lowercase())), vec![func.as_u32() as u64, 1]);
+    /// Registers the built-in function with the given name the first time it is referenced.
+    /// Only referenced built-ins are added to the function labels, in order of first use.
+    fn generate_builtin_function(&mut self, identifier_name: &String) {
+        if self.function_labels.contains_key(identifier_name) {
+            return;
+        }
+        if let Some(func) = BARRACUDA_BUILT_IN_FUNCTIONS.iter()
+            .find(|func| &format!("__{}", func.to_string().to_lowercase()) == identifier_name) {
+            self.function_labels.insert(identifier_name.clone(), vec![func.as_u32() as u64, 1]);
         }
     }
 
-    fn generate_function_call(&mut self, identifier: &Box<ASTNode>, arguments: &Vec<ASTNode>) {
+    fn generate_function_call(&mut self, identifier: &Box<ASTNode>, arguments: &Vec<ASTNode>, datatype: &DataType) {
         let identifier_name = identifier.identifier_name().unwrap();
-        let function_def_label = self.function_labels.get(&identifier_name).unwrap().clone()[0];
+        self.generate_builtin_function(&identifier_name);
+        let function_def_label = self.function_label(&identifier_name);
         let function_builtin_label = self.function_labels.get(&identifier_name).unwrap().clone()[1];
         let function_call_end = self.builder.create_label();
 
@@ -1021,6 +1553,14 @@ impl BarracudaByteCodeGenerator {
         // Generate Call Stack
         self.builder.comment(format!("FN CALL {} START", &identifier_name));
 
+        // Reserve slots for tuple return values
+        if let DataType::TUPLE(items) = datatype {
+            self.builder.comment(String::from("RESERVE RETURN VALUES"));
+            for _ in items {
+                self.builder.emit_value(0.0);
+            }
+        }
+
         // Push arguments onto the stack in reverse order
         for (i, arg) in arguments.iter().enumerate().rev() {
             self.builder.comment(format!("FN ARG {}", i));
@@ -1054,13 +1594,31 @@ impl BarracudaByteCodeGenerator {
 
         self.builder.comment(format!("FN CALL {} END", &identifier_name));
 
-        // Push return onto stack
-        self.generate_get_return_store();
+        // Push return onto stack, tuple return values are already in place
+        if !matches!(datatype, DataType::TUPLE(_)) {
+            self.generate_get_return_store();
+        }
     }
 
     fn generate_naked_function_call(&mut self, func_call: &Box<ASTNode>) {
         self.generate_node(func_call);
-        self.builder.emit_op(OP::DROP);
+        let builtin = match func_call.as_ref() {
+            ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
+                ASTNode::FUNC_CALL { identifier, .. } => BARRACUDA_BUILT_IN_FUNCTIONS.iter()
+                    .find(|func| Some(format!("__{}", func.to_string().to_lowercase())) == identifier.identifier_name()),
+                _ => None
+            },
+            _ => None
+        };
+        // Built in functions such as __free don't necessarily leave a value to drop
+        let return_values = match (builtin, func_call.get_type()) {
+            (Some(func), _) => func.produce() as usize,
+            (None, DataType::TUPLE(items)) => items.len(),
+            (None, _) => 1
+        };
+        for _ in 0..return_values {
+            self.builder.emit_op(OP::DROP);
+        }
     }
 
     fn generate_statement_list(&mut self, statements: &Vec<ASTNode>) {
@@ -1069,6 +1627,11 @@ impl BarracudaByteCodeGenerator {
         }
     }
 
+    fn generate_source_line(&mut self, line: usize, statement: &Box<ASTNode>) {
+        self.builder.comment(format!("line {}", line));
+        self.generate_node(statement);
+    }
+
     fn generate_scope_block(&mut self, inner: &Box<ASTNode>, scope: &ScopeId) {
         self.symbol_tracker.enter_scope(scope.clone());
         self.generate_node(inner);
@@ -1082,25 +1645,19 @@ impl BarracudaByteCodeGenerator {
 
 
     fn is_static_array(&self, items: &Vec<ASTNode>) -> bool {
-        // Check if all items in the array are compile-time constants (literals)
+        // Check if all items in the array are compile-time constants
         for item in items {
             match item {
                 ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
-                    ASTNode::LITERAL(_) => continue,
-                    ASTNode::UNARY_OP { expression, .. } => {
-                        if self.is_literal_expression(expression) {
-                            continue;
-                        } else {
-                            return false;
-                        }
-                    },
                     ASTNode::ARRAY{items, ..} => {
                         // Recursively check if inner arrays are static
                         if !self.is_static_array(items) {
                             return false;
                         }
                     }
-                    _ => return false,
+                    _ => if self.evaluate_constant_expression(item).is_none() {
+                        return false;
+                    }
                 }
                 _ => return false,
             }
@@ -1113,19 +1670,14 @@ impl BarracudaByteCodeGenerator {
         for item in items {
             match item {
                 ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
-                    ASTNode::LITERAL(literal) => values.push(self.extract_literal_value(literal)),
-                    ASTNode::UNARY_OP { op, expression } => {
-                        if let Some(value) = self.evaluate_unary_operation(op, expression) {
-                            values.push(value);
-                        } else {
-                            panic!("Non-literal value in static array!");
-                        }
-                    },
                     ASTNode::ARRAY{items, ..} => {
                         let mut sub_values = self.get_array_values(items);
                         values.append(&mut sub_values);
                     }
-                    _ => panic!("Non-literal value in static array!"),
+                    _ => match self.evaluate_constant_expression(item) {
+                        Some(value) => values.push(value),
+                        None => panic!("Non-literal value in static array!"),
+                    }
                 }
                 _ => panic!("Non-literal value in static array!"),
             }
@@ -1136,37 +1688,54 @@ impl BarracudaByteCodeGenerator {
     
     fn extract_literal_value(&self, literal: &Literal) -> f64 {
         match *literal {
-            Literal::FLOAT(value) => value,
-            Literal::INTEGER(value) => value as f64,
+            Literal::FLOAT(value) => self.quantise(value),
+            Literal::INTEGER(value) => self.quantise(value as f64),
             Literal::BOOL(value) => value as i64 as f64,
             Literal::PACKEDSTRING(value) => value,  // If you have packed strings as floats
+            Literal::NULL => f64::from_bits(0),
         }
     }
 
-    fn is_literal_expression(&self, expression: &Box<ASTNode>) -> bool {
-        match expression.as_ref() {
-            ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
-                ASTNode::LITERAL(_) => true,
-                _ => false,
-            },
-            _ => false,
-        }
-    }
-
-    fn evaluate_unary_operation(&self, op: &UnaryOperation, expression: &Box<ASTNode>) -> Option<f64> {
-        if let ASTNode::TYPED_NODE { inner, .. } = expression.as_ref() {
-            if let ASTNode::LITERAL(literal) = inner.as_ref() {
-                let value = self.extract_literal_value(literal);
+    /// Evaluates an expression of literals at compile time, giving the same value the VM would compute.
+    /// @return: the value or None if the expression isn't made only of literals and arithmetic,
+    ///          comparison or unary operators on numbers
+    fn evaluate_constant_expression(&self, node: &ASTNode) -> Option<f64> {
+        let ASTNode::TYPED_NODE { inner, .. } = node else {
+            return None
+        };
+        match inner.as_ref() {
+            ASTNode::LITERAL(literal) => Some(self.extract_literal_value(literal)),
+            ASTNode::UNARY_OP { op, expression } => {
+                let value = self.evaluate_constant_expression(expression)?;
                 match op {
                     UnaryOperation::NEGATE => Some(-value),
+                    UnaryOperation::PLUS => Some(value),
                     UnaryOperation::NOT => Some((value == 0.0) as i64 as f64),
                     _ => None,
                 }
-            } else {
-                None
-            }
-        } else {
-            None
+            },
+            // Pointers are compared by address so aren't folded
+            ASTNode::BINARY_OP { op, lhs, rhs } if matches!(lhs.get_type(), DataType::PRIMITIVE(_)) => {
+                let lhs = self.evaluate_constant_expression(lhs)?;
+                let rhs = self.evaluate_constant_expression(rhs)?;
+                let boolean = |value: bool| Some(value as i64 as f64);
+                match op {
+                    BinaryOperation::ADD => Some(lhs + rhs),
+                    BinaryOperation::SUB => Some(lhs - rhs),
+                    BinaryOperation::MUL => Some(lhs * rhs),
+                    BinaryOperation::DIV => Some(lhs / rhs),
+                    BinaryOperation::MOD => Some(lhs % rhs),
+                    BinaryOperation::POW => Some(lhs.powf(rhs)),
+                    BinaryOperation::EQUAL => boolean(lhs == rhs),
+                    BinaryOperation::NOT_EQUAL => boolean(lhs != rhs),
+                    BinaryOperation::GREATER_THAN => boolean(lhs > rhs),
+                    BinaryOperation::LESS_THAN => boolean(lhs < rhs),
+                    BinaryOperation::GREATER_EQUAL => boolean(lhs >= rhs),
+                    BinaryOperation::LESS_EQUAL => boolean(lhs <= rhs),
+                    _ => None,
+                }
+            },
+            _ => None,
         }
     }
     
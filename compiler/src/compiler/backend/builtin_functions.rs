@@ -96,9 +96,59 @@ pub static BARRACUDA_BUILT_IN_FUNCTIONS: &[OP] = &[
     OP::FREE,
     OP::MEMCPY,
     OP::MEMSET,
+    // Random numbers are generated by the VM, hardware VMs must provide their own generator.
+    OP::RAND,
+    OP::RAND_SEED,
     // These instructions could potentially be dangerous to be exposed to the user (could lead to deadlocks if used incorrectly [e.g., used inside an if statement where one thread doesn't reach the sync]),
     // eventually we need the compiler to recognise when these are required and insert them automatically, but for now expose them to the user...
     OP::SYNCWARP,
     OP::SYNCBLOCK,
     OP::SYNCGRID,
-];
\ No newline at end of file
+];
+
+/// Lists every built in function as (name, argument count), with names in the __name form used in source.
+#[allow(dead_code)] // Used in library but not the binary
+pub fn builtin_function_signatures() -> Vec<(String, i8)> {
+    BARRACUDA_BUILT_IN_FUNCTIONS.iter()
+        .map(|function| (format!("__{}", function.to_string().to_lowercase()), function.consume()))
+        .collect()
+}
+
+/// Evaluates a built in math function at compile time.
+/// @return: Result of the function or None if the function can't be evaluated at compile time
+pub fn evaluate_builtin_function(function: OP, arguments: &[f64]) -> Option<f64> {
+    match (function, arguments) {
+        (OP::ACOS, [x]) => Some(x.acos()),
+        (OP::ACOSH, [x]) => Some(x.acosh()),
+        (OP::ASIN, [x]) => Some(x.asin()),
+        (OP::ASINH, [x]) => Some(x.asinh()),
+        (OP::ATAN, [x]) => Some(x.atan()),
+        (OP::ATANH, [x]) => Some(x.atanh()),
+        (OP::CBRT, [x]) => Some(x.cbrt()),
+        (OP::CEIL, [x]) => Some(x.ceil()),
+        (OP::COS, [x]) => Some(x.cos()),
+        (OP::COSH, [x]) => Some(x.cosh()),
+        (OP::COSPI, [x]) => Some((x * std::f64::consts::PI).cos()),
+        (OP::EXP, [x]) => Some(x.exp()),
+        (OP::EXP10, [x]) => Some(10f64.powf(*x)),
+        (OP::EXP2, [x]) => Some(x.exp2()),
+        (OP::EXPM1, [x]) => Some(x.exp_m1()),
+        (OP::FABS, [x]) => Some(x.abs()),
+        (OP::FLOOR, [x]) => Some(x.floor()),
+        (OP::LOG, [x]) => Some(x.ln()),
+        (OP::LOG10, [x]) => Some(x.log10()),
+        (OP::LOG1P, [x]) => Some(x.ln_1p()),
+        (OP::LOG2, [x]) => Some(x.log2()),
+        (OP::RCBRT, [x]) => Some(1.0 / x.cbrt()),
+        (OP::ROUND, [x]) => Some(x.round()),
+        (OP::RSQRT, [x]) => Some(1.0 / x.sqrt()),
+        (OP::SIN, [x]) => Some(x.sin()),
+        (OP::SINH, [x]) => Some(x.sinh()),
+        (OP::SINPI, [x]) => Some((x * std::f64::consts::PI).sin()),
+        (OP::SQRT, [x]) => Some(x.sqrt()),
+        (OP::TAN, [x]) => Some(x.tan()),
+        (OP::TANH, [x]) => Some(x.tanh()),
+        (OP::TRUNC, [x]) => Some(x.trunc()),
+        _ => None
+    }
+}
@@ -19,6 +19,16 @@ pub trait BackEndGenerator {
     fn add_environment_variable(&mut self);
 
     fn set_precision(&mut self, precision: usize);
+
+    /// Set the optimisation level. Level 0 generates code for every statement as written.
+    fn set_optimisation_level(&mut self, optimisation_level: usize);
+
+    /// Enable runtime bounds checks on array accesses with dynamic indices.
+    fn set_bounds_checking(&mut self, bounds_checking: bool);
+
+    /// Round numeric literals to the output precision, so a 32 bit program emits the f32 value
+    /// of each constant rather than the f64 it's rounded from by the VM.
+    fn set_quantise_literals(&mut self, quantise_literals: bool);
 }
 
 // Concrete Definition Export
@@ -1,5 +1,6 @@
 use barracuda_common::{
     ProgramCode,
+    SymbolLocation,
     BarracudaInstructions as INSTRUCTION,
     BarracudaOperators as OP,
     FixedBarracudaOperators as FIXED_OP,
@@ -33,7 +34,11 @@ enum BarracudaIR {
 
     /// Comments are purely decorative and allow for instructions to be annotated these are stored
     /// with ProgramCodeDecorations after finalisation
-    Comment(String)
+    Comment(String),
+
+    /// Symbols record where a variable declared at the next instruction is stored, for debugging.
+    /// Like arrays, mutable array addresses are only exact once the environment variables are known.
+    Symbol(String, SymbolLocation)
 }
 
 /// BarracudaProgramCodeBuilder is a Builder utility class that builds ProgramCode linearly.
@@ -89,6 +94,12 @@ impl BarracudaProgramCodeBuilder {
         self.program_out.push(BarracudaIR::Comment(comment));
     }
 
+    /// Symbol records where a variable declared at the next instruction is stored.
+    /// Symbols have no functional usage, they let debuggers label values with variable names.
+    pub fn symbol(&mut self, identifier: String, location: SymbolLocation) {
+        self.program_out.push(BarracudaIR::Symbol(identifier, location));
+    }
+
     /// Create label generates a new unique label to address specific instruction indices in the code.
     /// This is useful for jump statements where you do not know the generated code size yet.
     /// ## Typical Usage
@@ -134,9 +145,9 @@ impl BarracudaProgramCodeBuilder {
         self.precision = precision;
     }
 
-    //pub fn get_precision(&self) -> usize {
-    //    self.precision
-    //}
+    pub fn get_precision(&self) -> usize {
+        self.precision
+    }
 
     /// Resolves all BarracudaIR items into ProgramCode, consumes self in the process.
     pub fn finalize(self) -> ProgramCode {
@@ -176,6 +187,7 @@ impl BarracudaProgramCodeBuilder {
                     locations[*id as usize] = current_line;
                 }
                 BarracudaIR::Comment(_) => {}
+                BarracudaIR::Symbol(_, _) => {}
                 BarracudaIR::Userspace(_, _) => {} // Userspace should NOT take up instruction slots.
 
                 // Everything else should take up a instruction slot
@@ -240,6 +252,15 @@ impl BarracudaProgramCodeBuilder {
                 BarracudaIR::Comment(comment) => {
                     output_program.push_comment(comment.clone());
                 }
+                BarracudaIR::Symbol(identifier, location) => {
+                    let location = match location {
+                        SymbolLocation::MutableArray { frame_offset, address } => {
+                            SymbolLocation::MutableArray { frame_offset: *frame_offset, address: address + self.env_var_count }
+                        }
+                        _ => location.clone()
+                    };
+                    output_program.push_symbol(identifier.clone(), location);
+                }
             };
         }
 
@@ -0,0 +1,46 @@
+use std::any::Any;
+use std::cell::Cell;
+
+/// Diagnostic describes an error found while checking a program.
+/// Line and column are 1-based, 0 means the location is unknown. Semantic errors are located
+/// by the line of the statement being analysed so they don't carry a column.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)] // Diagnostics are only read through the library interface
+pub struct Diagnostic {
+    pub message: String,
+    pub line: usize,
+    pub column: usize
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic from the payload of a compiler panic, located at the last recorded
+    /// source location.
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            String::from("Unknown compiler error")
+        };
+        let (line, column) = location();
+
+        Self { message, line, column }
+    }
+}
+
+thread_local! {
+    static LOCATION: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+/// Records the source location currently being processed.
+/// Compiler errors are reported through panics so the last recorded location is used to
+/// locate them.
+pub(crate) fn set_location(line: usize, column: usize) {
+    LOCATION.with(|location| location.set((line, column)));
+}
+
+/// Returns the last recorded source location as (line, column)
+pub(crate) fn location() -> (usize, usize) {
+    LOCATION.with(|location| location.get())
+}
@@ -1,15 +1,20 @@
 mod ast;
 pub mod backend;
+pub mod diagnostics;
 pub mod parser;
+pub mod preprocessor;
 pub mod semantic_analyser;
 pub mod utils;
+pub mod warnings;
 use barracuda_common;
 
+use std::collections::HashSet;
 use std::path::Path;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
 
 // Interface Definitions
 use self::parser::AstParser;
@@ -24,6 +29,8 @@ pub use self::semantic_analyser::BarracudaSemanticAnalyser;
 pub use self::ast::EnvironmentSymbolContext;
 pub use self::ast::datatype::PrimitiveDataType;
 pub use self::ast::qualifiers::Qualifier;
+pub use self::warnings::CompilerWarning;
+pub use self::diagnostics::Diagnostic;
 
 
 /// Compiler is a simple class that holds the configuration of a compilation configuration.
@@ -39,7 +46,8 @@ pub struct Compiler<P: AstParser, A: SemanticAnalyser, G: BackEndGenerator> {
     semantic_analyser: A,
     generator: G,
     env_vars: EnvironmentSymbolContext,
-    precision: usize
+    precision: usize,
+    defines: HashSet<String>
 }
 
 #[allow(dead_code)] // Many of the functions on compiler act as a library interface and are not used
@@ -53,7 +61,8 @@ impl<P: AstParser, A: SemanticAnalyser, G: BackEndGenerator> Compiler<P, A, G> {
             semantic_analyser: A::default(),
             generator: G::default(),
             env_vars: EnvironmentSymbolContext::new(),
-            precision: 32
+            precision: 32,
+            defines: HashSet::new()
         }
     }
 
@@ -65,6 +74,7 @@ impl<P: AstParser, A: SemanticAnalyser, G: BackEndGenerator> Compiler<P, A, G> {
             generator,
             env_vars,
             precision,
+            defines: HashSet::new()
         }
     }
 
@@ -86,15 +96,88 @@ impl<P: AstParser, A: SemanticAnalyser, G: BackEndGenerator> Compiler<P, A, G> {
         return self
     }
 
+    /// Sets the word size in bits of the VM the program will run on, either 32 or 64.
+    pub fn set_target_width(mut self, target_width: usize) -> Self {
+        self.semantic_analyser.set_target_width(target_width);
+        return self
+    }
+
+    pub fn set_optimisation_level(mut self, optimisation_level: usize) -> Self {
+        self.generator.set_optimisation_level(optimisation_level);
+        return self
+    }
+
+    pub fn set_bounds_checking(mut self, bounds_checking: bool) -> Self {
+        self.generator.set_bounds_checking(bounds_checking);
+        return self
+    }
+
+    pub fn set_quantise_literals(mut self, quantise_literals: bool) -> Self {
+        self.generator.set_quantise_literals(quantise_literals);
+        return self
+    }
+
+    pub fn set_max_function_count(mut self, max_function_count: usize) -> Self {
+        self.semantic_analyser.set_max_function_count(max_function_count);
+        return self
+    }
+
+    pub fn set_warn_shadowing(mut self, warn_shadowing: bool) -> Self {
+        self.semantic_analyser.set_warn_shadowing(warn_shadowing);
+        return self
+    }
+
+    /// Defines names for conditional compilation, enabling #if blocks that test them.
+    pub fn set_defines(mut self, defines: Vec<String>) -> Self {
+        self.defines = defines.into_iter().collect();
+        return self
+    }
+
     /// Compiles a string representing an interpretable language by the parser into program code.
     pub fn compile_str(self, source: &str) -> ProgramCode {
-        let ast = self.parser.parse(source, self.precision);
+        let source = preprocessor::preprocess(source, &self.defines);
+        let ast = self.parser.parse(&source, self.precision);
         let annotated_ast = self.semantic_analyser.analyse(ast, self.env_vars);
         let program_code = self.generator.generate(annotated_ast);
 
         return program_code
     }
 
+    /// Compiles a source string into program code, returning the first error found instead of
+    /// panicking. Like compile_str this doesn't touch the filesystem or write any output so is
+    /// the entry point to use when embedding the compiler.
+    pub fn try_compile_str(self, source: &str) -> Result<ProgramCode, Diagnostic> {
+        diagnostics::set_location(0, 0);
+        panic::catch_unwind(AssertUnwindSafe(|| self.compile_str(source)))
+            .map_err(Diagnostic::from_panic)
+    }
+
+    /// Parses and analyses a source string without generating code, returning the annotated AST
+    /// pretty printed as an indented tree. Useful for debugging the parser and semantic analyser.
+    pub fn dump_ast(self, source: &str) -> String {
+        let source = preprocessor::preprocess(source, &self.defines);
+        let ast = self.parser.parse(&source, self.precision);
+        let annotated_ast = self.semantic_analyser.analyse(ast, self.env_vars);
+
+        return annotated_ast.pretty_print()
+    }
+
+    /// Parses and analyses a source string without generating code, returning the errors found.
+    /// Compilation stops at the first error so at most one diagnostic is returned.
+    pub fn check_str(self, source: &str) -> Vec<Diagnostic> {
+        diagnostics::set_location(0, 0);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let source = preprocessor::preprocess(source, &self.defines);
+            let ast = self.parser.parse(&source, self.precision);
+            self.semantic_analyser.analyse(ast, self.env_vars);
+        }));
+
+        match result {
+            Ok(_) => vec![],
+            Err(payload) => vec![Diagnostic::from_panic(payload)]
+        }
+    }
+
     /// Compiles a program file containing an interpretable language by the parser into program code.
     /// @return: ProgramCode if Ok. Otherwise IO Error from a failed read.
     pub fn compile(self, source_filename: &Path) -> Result<ProgramCode, Box<dyn Error>> {
@@ -107,25 +190,71 @@ impl<P: AstParser, A: SemanticAnalyser, G: BackEndGenerator> Compiler<P, A, G> {
     /// path.
     /// @return: ProgramCode if Ok. Otherwise IO Error from a failed read/write.
     pub fn compile_and_save(self, source_filename: &Path, dest_filename: &Path, decorated: bool) -> Result<(), Box<dyn Error>> {
-        let mut compiled_program = self.compile(source_filename)?;
+        let source_str = fs::read_to_string(source_filename)?;
+
+        self.compile_str_and_save(source_str.as_str(), dest_filename, decorated)
+    }
+
+    /// Compiles a source string and writes program code encoded as string into the destination
+    /// file path. Decorated programs also write a symbol map of variable locations alongside it
+    /// with the extension .sym
+    /// @return: Ok if the program was written. Otherwise IO Error from a failed write.
+    pub fn compile_str_and_save(self, source: &str, dest_filename: &Path, decorated: bool) -> Result<(), Box<dyn Error>> {
+        let compiled_program = self.compile_str(source);
+        Self::save_program_code(compiled_program, dest_filename, decorated)
+    }
+
+    /// Compiles a source string and writes program code encoded as string to any writer, such as
+    /// an in memory buffer. Unlike saving to a file no symbol map is written and warnings aren't
+    /// reported, use compile_str to inspect them.
+    /// @return: Ok if the program was written. Otherwise IO Error from a failed write.
+    pub fn compile_to_writer<W: Write>(self, source: &str, writer: &mut W, decorated: bool) -> Result<(), Box<dyn Error>> {
+        let mut compiled_program = self.compile_str(source);
         if decorated {
             compiled_program = compiled_program.decorated();
         }
+        Self::write_program_code(&compiled_program, writer)
+    }
 
-        let program_str = format!("{}", compiled_program);
+    fn write_program_code<W: Write>(compiled_program: &ProgramCode, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        writer.write_all(format!("{}", compiled_program).as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes compiled program code to dest_filename, along with a symbol map when decorated.
+    /// Warnings of the program and the files written are reported to stderr.
+    /// @return: Ok if the files were written. Otherwise IO Error naming the file that failed.
+    pub fn save_program_code(mut compiled_program: ProgramCode, dest_filename: &Path, decorated: bool) -> Result<(), Box<dyn Error>> {
+        if decorated {
+            compiled_program = compiled_program.decorated();
+        }
 
-        let display_dest = dest_filename.display();
+        for warning in &compiled_program.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+
+        Self::save_to_file(dest_filename, |file| Self::write_program_code(&compiled_program, file))?;
 
-        let mut file = match File::create(&dest_filename) {
-            Err(why) => panic!("Couldn't create {}: {}", display_dest, why),
-            Ok(file) => file,
-        };
+        if decorated {
+            let symbol_filename = dest_filename.with_extension("sym");
+            Self::save_to_file(&symbol_filename, |file| {
+                file.write_all(compiled_program.symbol_map().as_bytes())?;
+                Ok(())
+            })?;
+        }
 
-        match file.write_all(program_str.as_bytes()) {
-            Err(why) => panic!("Couldn't write to {}: {}", display_dest, why),
-            Ok(_) => println!("Successfully wrote to {}", display_dest),
-        };
+        Ok(())
+    }
 
+    /// Creates a file and writes to it, reporting the file once written.
+    fn save_to_file<F>(filename: &Path, write: F) -> Result<(), Box<dyn Error>>
+        where F: FnOnce(&mut File) -> Result<(), Box<dyn Error>> {
+        let display = filename.display();
+        let mut file = File::create(filename)
+            .map_err(|why| format!("Couldn't create {}: {}", display, why))?;
+        write(&mut file).map_err(|why| format!("Couldn't write to {}: {}", display, why))?;
+        eprintln!("Successfully wrote to {}", display);
         Ok(())
     }
 }
\ No newline at end of file
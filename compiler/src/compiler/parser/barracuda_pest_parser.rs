@@ -11,6 +11,8 @@ use super::super::ast::{
 };
 
 use crate::compiler::utils::pack_string_to_f64_array;
+use crate::compiler::diagnostics::set_location;
+use pest::error::LineColLocation;
 
 /// Pest Barracuda Parser parses a string into a series of tokens.
 /// These tokens are defined as a Context-Free-Grammar in the src/barracuda.pest file.
@@ -43,6 +45,11 @@ impl PestBarracudaParser {
                 }
             },
             Err(error) => {
+                let (line, column) = match error.line_col {
+                    LineColLocation::Pos(position) => position,
+                    LineColLocation::Span(start, _) => start
+                };
+                set_location(line, column);
                 panic!("Syntax Error: {}", error)
             }
         }
@@ -57,12 +64,19 @@ impl PestBarracudaParser {
             Rule::primitive_datatype => { self.parse_pair_primitive_datatype(pair) },
             Rule::pointer_datatype =>   { self.parse_pair_pointer_datatype(pair) },
             Rule::array_datatype =>     { self.parse_pair_array_datatype(pair) },
+            Rule::tuple_datatype =>     { self.parse_pair_tuple_datatype(pair) },
+            Rule::struct_datatype =>    { self.parse_pair_struct_datatype(pair) },
+            Rule::member =>             { self.parse_pair_member(pair) },
             Rule::qualifier =>          { self.parse_pair_qualifier(pair) },
             Rule::integer |
             Rule::decimal |
-            Rule::boolean =>            { self.parse_pair_literal(pair) },
+            Rule::hex_float |
+            Rule::boolean |
+            Rule::null =>               { self.parse_pair_literal(pair) },
             Rule::string =>             { self.parse_pair_string(pair) },
+            Rule::raw_string =>         { self.parse_pair_raw_string(pair) },
             Rule::array =>              { self.parse_pair_array(pair) },
+            Rule::tuple =>              { self.parse_pair_tuple(pair) },
             Rule::equality |
             Rule::comparison |
             Rule::term |
@@ -76,30 +90,77 @@ impl PestBarracudaParser {
             Rule::index =>              { self.parse_pair_array_index(pair) },
             Rule::global_statement_list |
             Rule::statement_list =>     { self.parse_pair_statement_list(pair) },
+            Rule::destructure_statement => { self.parse_pair_destructure_statement(pair) },
             Rule::full_qualified_construct_statement => { self.parse_pair_full_qualified_construct_statement(pair) },
             Rule::full_construct_statement => { self.parse_pair_full_construct_statement(pair) },
             Rule::inferred_qualified_construct_statement => { self.parse_pair_inferred_qualified_construct_statement(pair) },
             Rule::inferred_construct_statement => { self.parse_pair_inferred_construct_statement(pair) },
             Rule::empty_qualified_construct_statement => { self.parse_pair_empty_qualified_construct_statement(pair) },
             Rule::empty_construct_statement => { self.parse_pair_empty_construct_statement(pair) },
+            Rule::struct_statement =>   { self.parse_pair_struct_statement(pair) },
+            Rule::enum_statement =>     { self.parse_pair_enum_statement(pair) },
+            Rule::enum_variant =>       { self.parse_pair_enum_variant(pair) },
             Rule::external_statement => { self.parse_pair_external_statement(pair) },
             Rule::assign_statement =>   { self.parse_pair_assignment_statement(pair) },
+            Rule::invalid_assign_statement => { self.parse_pair_invalid_assignment_statement(pair) },
             Rule::if_statement =>       { self.parse_pair_if_statement(pair) },
             Rule::for_statement =>      { self.parse_pair_for_statement(pair) },
             Rule::while_statement =>    { self.parse_pair_while_statement(pair) },
             Rule::print_statement =>    { self.parse_pair_print_statement(pair) },
+            Rule::fence_statement =>    { ASTNode::FENCE },
             Rule::func_statement =>     { self.parse_pair_function(pair) },
             Rule::func_param =>         { self.parse_pair_function_parameter(pair) },
             Rule::return_statement =>   { self.parse_pair_return_statement(pair) },
             Rule::func_call =>          { self.parse_pair_function_call(pair) },
             Rule::naked_func_call =>    { self.parse_pair_naked_function_call(pair) },
             Rule::func_arg =>           { self.parse_pair_function_argument(pair) },
+            Rule::spread_arg =>         { self.parse_pair_spread_argument(pair) },
             Rule::global_scope_block |
             Rule::scope_block =>        { self.parse_pair_scope_block(pair) },
             _ => { panic!("Whoops! Unprocessed pest rule: {:?}", pair.as_rule()) }
         }
     }
 
+    /// Converts a hexadecimal float such as 0x1.8p3 into the value mantissa * 2^exponent.
+    /// Up to 64 bits of the mantissa are kept, any further non zero digits only affect rounding.
+    fn parse_hex_float(text: &str) -> f64 {
+        let (significand, exponent) = text[2..].split_once(['p', 'P']).unwrap();
+        let mut exponent: i64 = exponent.parse()
+            .unwrap_or_else(|_| panic!("Literal {} overflows f64!", text));
+        let (whole, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+
+        let mut mantissa: u64 = 0;
+        let mut inexact = false;
+        for (position, digit) in whole.chars().chain(fraction.chars()).enumerate() {
+            let digit = digit.to_digit(16).unwrap() as u64;
+            let is_fraction = position >= whole.len();
+            if mantissa >> 60 == 0 {
+                mantissa = mantissa << 4 | digit;
+                if is_fraction {
+                    exponent -= 4;
+                }
+            } else {
+                inexact |= digit != 0;
+                if !is_fraction {
+                    exponent += 4;
+                }
+            }
+        }
+        // Dropped digits lie below the lowest bit kept, so setting it rounds ties correctly
+        if inexact {
+            mantissa |= 1;
+        }
+
+        // Scaling in steps keeps the power of two itself from overflowing
+        let mut value = mantissa as f64;
+        while exponent != 0 && value != 0.0 && value.is_finite() {
+            let step = exponent.clamp(-1000, 1000);
+            value *= 2f64.powi(step as i32);
+            exponent -= step;
+        }
+        value
+    }
+
     /// Parses a pest token pair into an AST literal
     fn parse_pair_literal(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         match pair.as_rule() {
@@ -107,18 +168,50 @@ impl PestBarracudaParser {
                 ASTNode::LITERAL(Literal::INTEGER(pair.as_str().parse().unwrap()))
             },
             Rule::decimal => {
-                ASTNode::LITERAL(Literal::FLOAT(pair.as_str().parse().unwrap()))
+                let text = pair.as_str();
+                // Literals without a fractional part or exponent are integers, so no precision is lost before analysis
+                if text.chars().all(|c| c.is_ascii_digit()) {
+                    let value: u64 = text.parse()
+                        .unwrap_or_else(|_| panic!("Integer literal {} is too large! (Max {})", text, u64::MAX));
+                    return ASTNode::LITERAL(Literal::INTEGER(value))
+                }
+                let value: f64 = text.parse().unwrap();
+                if value.is_infinite() {
+                    panic!("Literal {} overflows f64!", text)
+                }
+                ASTNode::LITERAL(Literal::FLOAT(value))
+            },
+            Rule::hex_float => {
+                let value = Self::parse_hex_float(pair.as_str());
+                if value.is_infinite() {
+                    panic!("Literal {} overflows f64!", pair.as_str())
+                }
+                ASTNode::LITERAL(Literal::FLOAT(value))
             },
             Rule::boolean => {
                 ASTNode::LITERAL(Literal::BOOL(pair.as_str().parse().unwrap()))
             },
+            Rule::null => {
+                ASTNode::LITERAL(Literal::NULL)
+            },
             _ => { panic!("Whoops! Unprocessed literal rule: {:?}", pair.as_rule()) }
         }
     }
 
     fn parse_pair_string(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         let string = pair.as_str();
-        let string = &string[1..string.len() - 1];
+        self.parse_string_contents(&string[1..string.len() - 1])
+    }
+
+    /// Parses a raw string r#"..."#, whose contents are taken as written between the delimiters
+    fn parse_pair_raw_string(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let string = pair.as_str();
+        let delimiter_length = string[1..].find('"').unwrap();
+        self.parse_string_contents(&string[delimiter_length + 2..string.len() - delimiter_length - 1])
+    }
+
+    /// Packs string contents into user space as character codes
+    fn parse_string_contents(&self, string: &str) -> ASTNode {
         let string = pack_string_to_f64_array(string, 64);
         ASTNode::ARRAY{
             items: string.into_iter().map(|x| ASTNode::LITERAL(Literal::PACKEDSTRING(x))).collect(),
@@ -126,6 +219,12 @@ impl PestBarracudaParser {
         }
     }
 
+    fn parse_pair_tuple(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        ASTNode::TUPLE {
+            items: pair.into_inner().map(|p| self.parse_pair_node(p)).collect()
+        }
+    }
+
     fn parse_pair_array(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         //ASTNode::ARRAY(pair.into_inner().map(|p| self.parse_pair_node(p)).collect())
 
@@ -174,8 +273,28 @@ impl PestBarracudaParser {
             _ => panic!("Datatype not found in array (ASTNode: {:?})", sub_datatype),
         };
         let sub_datatype = Box::new(sub_datatype);
-        let size = pair.as_str().parse().unwrap();
-        ASTNode::DATATYPE(DataType::ARRAY(sub_datatype, size))
+        let size = pair.next().unwrap();
+        match size.as_rule() {
+            Rule::identifier => ASTNode::DATATYPE(DataType::NAMEDARRAY(sub_datatype, size.as_str().to_string())),
+            _ => ASTNode::DATATYPE(DataType::ARRAY(sub_datatype, size.as_str().parse().unwrap()))
+        }
+    }
+
+    fn parse_pair_tuple_datatype(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let items = pair.into_inner().map(|p| match self.parse_pair_node(p) {
+            ASTNode::DATATYPE(datatype) => datatype,
+            item => panic!("Datatype not found in tuple (ASTNode: {:?})", item),
+        }).collect();
+        ASTNode::DATATYPE(DataType::TUPLE(items))
+    }
+
+    /// Structs named in datatypes are resolved to their fields by the semantic analyser
+    fn parse_pair_struct_datatype(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        ASTNode::DATATYPE(DataType::STRUCT(pair.as_str().to_string(), vec![]))
+    }
+
+    fn parse_pair_member(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        ASTNode::MEMBER(String::from(pair.into_inner().next().unwrap().as_str()))
     }
 
     /// Parses a pest token pair into an AST binary expression
@@ -247,8 +366,15 @@ impl PestBarracudaParser {
     }
 
     /// Parses a pest token pair into an AST statement list
+    /// Each statement is tagged with the source line it starts on
     fn parse_pair_statement_list(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
-        ASTNode::STATEMENT_LIST(pair.into_inner().map(|p| self.parse_pair_node(p)).collect())
+        ASTNode::STATEMENT_LIST(pair.into_inner().map(|p| {
+            let (line, _) = p.as_span().start_pos().line_col();
+            ASTNode::SOURCE_LINE {
+                line,
+                statement: Box::new(self.parse_pair_node(p))
+            }
+        }).collect())
     }
 
     /// Parses a pest token pair into an AST construct statement, with datatype
@@ -296,6 +422,23 @@ impl PestBarracudaParser {
         }
     }
 
+    /// Parses a pest token pair into an AST destructure statement, with an optional qualifier
+    fn parse_pair_destructure_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let mut pair = pair.into_inner().peekable();
+        let qualifier = match pair.peek().unwrap().as_rule() {
+            Rule::qualifier => self.parse_pair_node(pair.next().unwrap()),
+            _ => ASTNode::QUALIFIER(Qualifier::CONSTANT)
+        };
+        let mut nodes: Vec<ASTNode> = pair.map(|p| self.parse_pair_node(p)).collect();
+        let expression = nodes.pop().unwrap();
+
+        ASTNode::DESTRUCTURE {
+            identifiers: nodes,
+            qualifier: Box::new(qualifier),
+            expression: Box::new(expression),
+        }
+    }
+
     /// Parses a pest token pair into an AST construct statement, without datatype
     fn parse_pair_inferred_construct_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         let mut pair = pair.into_inner();
@@ -346,6 +489,61 @@ impl PestBarracudaParser {
         }
     }
 
+    /// Parses a pest token pair into an AST struct statement
+    fn parse_pair_struct_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let mut pair = pair.into_inner();
+        let name = pair.next().unwrap().as_str().to_string();
+        let fields = pair.map(|field| {
+            let mut field = field.into_inner();
+            let field_name = field.next().unwrap().as_str().to_string();
+            let datatype = DataType::from(&self.parse_pair_node(field.next().unwrap()));
+            (field_name, datatype)
+        }).collect();
+
+        ASTNode::STRUCT {
+            identifier: Box::new(ASTNode::IDENTIFIER(name.clone())),
+            datatype: Box::new(ASTNode::DATATYPE(DataType::STRUCT(name, fields))),
+        }
+    }
+
+    /// Parses a pest token pair into an AST enum statement
+    fn parse_pair_enum_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let mut pair = pair.into_inner();
+        let identifier = self.parse_pair_node(pair.next().unwrap());
+        let variants = pair.map(|variant| {
+            let mut variant = variant.into_inner();
+            let name = variant.next().unwrap().as_str().to_string();
+            let value = variant.next().map(|value| value.as_str().parse()
+                .unwrap_or_else(|_| panic!("Value of enum variant {} is too large!", name)));
+            (name, value)
+        }).collect();
+
+        ASTNode::ENUM {
+            identifier: Box::new(identifier),
+            variants,
+        }
+    }
+
+    fn parse_pair_enum_variant(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let mut pair = pair.into_inner();
+        let identifier = self.parse_pair_node(pair.next().unwrap());
+        let variant = self.parse_pair_node(pair.next().unwrap());
+
+        ASTNode::ENUM_VARIANT {
+            identifier: Box::new(identifier),
+            variant: Box::new(variant),
+        }
+    }
+
+    /// Assignments can only be made to variables, optionally dereferenced, indexed or through
+    /// members. Other targets are parsed as invalid assignments so they can be reported here.
+    fn parse_pair_invalid_assignment_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let (line, column) = pair.as_span().start_pos().line_col();
+        set_location(line, column);
+        let target = pair.into_inner().next().unwrap();
+        panic!("Invalid assignment target {}! Only variables and pointer variables dereferenced with *, along with their array elements and struct members, can be assigned to.", target.as_str().trim())
+    }
+
     /// Parses a pest token pair into an AST assignment statement
     fn parse_pair_assignment_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         let mut pair = pair.into_inner();
@@ -385,11 +583,14 @@ impl PestBarracudaParser {
     }
 
     /// Parses a pest token pair into an AST for statement
+    /// Each section of the loop header can be omitted. An omitted condition is always true and
+    /// omitted initialization or advancement statements are empty statement lists.
     fn parse_pair_for_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         let mut pair = pair.into_inner();
-        let initialization = self.parse_pair_node(pair.next().unwrap());
-        let condition = self.parse_pair_node(pair.next().unwrap());
-        let advancement = self.parse_pair_node(pair.next().unwrap());
+        let mut section = || pair.next().unwrap().into_inner().next().map(|inner| self.parse_pair_node(inner));
+        let initialization = section().unwrap_or(ASTNode::STATEMENT_LIST(vec![]));
+        let condition = section().unwrap_or(ASTNode::LITERAL(Literal::BOOL(true)));
+        let advancement = section().unwrap_or(ASTNode::STATEMENT_LIST(vec![]));
         let body = self.parse_pair_node(pair.next().unwrap());
 
         ASTNode::FOR_LOOP {
@@ -468,22 +669,26 @@ impl PestBarracudaParser {
 
         let identifier = self.parse_pair_node(pair.next().unwrap());
 
-        let datatype = match pair.next() {
-            Some(datatype_pair) => Some(self.parse_pair_node(datatype_pair)),
-            None => None,
+        let datatype = match pair.peek() {
+            Some(datatype_pair) if datatype_pair.as_rule() != Rule::param_default => Some(self.parse_pair_node(pair.next().unwrap())),
+            _ => None,
         };
 
+        let default = pair.next()
+            .map(|default_pair| self.parse_pair_node(default_pair.into_inner().next().unwrap()));
+
         ASTNode::PARAMETER {
             identifier: Box::new(identifier),
             datatype: Box::new(datatype),
             qualifier: Box::new(qualifier),
+            default: Box::new(default),
         }
     }
 
     /// Parses a pest token pair into an AST return statement
     fn parse_pair_return_statement(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         let mut pair = pair.into_inner();
-        let expression = self.parse_pair_node(pair.next().unwrap());
+        let expression = pair.next().map(|expression| self.parse_pair_node(expression));
 
         ASTNode::RETURN {
             expression: Box::new(expression),
@@ -523,6 +728,17 @@ impl PestBarracudaParser {
         self.parse_pair_node(pair.next().unwrap())
     }
 
+    /// Parse a pest token pair into an AST spread argument.
+    /// Spread arguments expand an array into positional function arguments
+    fn parse_pair_spread_argument(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
+        let mut pair = pair.into_inner();
+        let expression = self.parse_pair_node(pair.next().unwrap());
+
+        ASTNode::SPREAD {
+            expression: Box::new(expression),
+        }
+    }
+
     /// Parses a pest token pair into an AST statement list
     fn parse_pair_scope_block(&self, pair: pest::iterators::Pair<Rule>) -> ASTNode {
         let mut pair = pair.into_inner();
@@ -539,6 +755,7 @@ impl PestBarracudaParser {
         match pair.as_rule() {
             Rule::unary_not => Some(UnaryOperation::NOT),
             Rule::unary_neg => Some(UnaryOperation::NEGATE),
+            Rule::unary_plus => Some(UnaryOperation::PLUS),
             Rule::dereference => Some(UnaryOperation::PTR_DEREF),
             _ => None,
         }
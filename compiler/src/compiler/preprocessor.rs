@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+use super::diagnostics;
+
+/// Resolves conditional compilation directives in source before it is parsed.
+/// Directives take up a whole line starting with #:
+///     #define NAME    defines NAME for the rest of the source
+///     #if NAME        keeps the following lines only if NAME is defined
+///     #else           keeps the following lines only if the matching #if didn't
+///     #endif          ends the block started by the matching #if
+///
+/// Blocks can be nested. Directive lines and removed lines are left empty so the remaining code
+/// keeps its line numbers for error messages. Other lines starting with #, such as headings in
+/// comments or strings, are passed through unchanged.
+///
+/// # Example:
+///     #if FAST
+///     let mut steps = 10;
+///     #else
+///     let mut steps = 1000;
+///     #endif
+pub fn preprocess(source: &str, defines: &HashSet<String>) -> String {
+    let mut defines = defines.clone();
+    let mut blocks: Vec<ConditionalBlock> = vec![];
+
+    let lines: Vec<&str> = source.split('\n').enumerate().map(|(index, line)| {
+        let active = blocks.last().is_none_or(|block| block.is_active());
+        let directive = line.trim();
+        let words: Vec<&str> = directive.strip_prefix('#').map_or(vec![], |text| text.split_whitespace().collect());
+        if !matches!(words.first(), Some(&"define") | Some(&"if") | Some(&"else") | Some(&"endif")) {
+            return if active { line } else { "" };
+        }
+
+        diagnostics::set_location(index + 1, 0);
+        match words.as_slice() {
+            ["define", name] => {
+                if active {
+                    defines.insert(name.to_string());
+                }
+            }
+            ["if", name] => blocks.push(ConditionalBlock {
+                enclosing_active: active,
+                condition: defines.contains(*name),
+                in_else: false,
+                line: index + 1
+            }),
+            ["else"] => match blocks.last_mut() {
+                Some(block) if !block.in_else => block.in_else = true,
+                Some(_) => panic!("#if can only have one #else!"),
+                None => panic!("#else without a matching #if!")
+            },
+            ["endif"] => {
+                if blocks.pop().is_none() {
+                    panic!("#endif without a matching #if!")
+                }
+            }
+            _ => panic!("Invalid preprocessor directive {}!", directive)
+        }
+        ""
+    }).collect();
+
+    if let Some(block) = blocks.last() {
+        diagnostics::set_location(block.line, 0);
+        panic!("#if without a matching #endif!")
+    }
+
+    lines.join("\n")
+}
+
+/// An #if block that hasn't been closed by #endif yet
+struct ConditionalBlock {
+    // Lines are only kept if the enclosing block keeps them too
+    enclosing_active: bool,
+    condition: bool,
+    in_else: bool,
+    line: usize
+}
+
+impl ConditionalBlock {
+    fn is_active(&self) -> bool {
+        self.enclosing_active && (self.condition != self.in_else)
+    }
+}
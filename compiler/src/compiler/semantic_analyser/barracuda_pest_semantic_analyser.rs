@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::compiler::PrimitiveDataType;
 use crate::compiler::ast::qualifiers::Qualifier;
 use crate::compiler::ast::scope::ScopeIdGenerator;
 use crate::compiler::ast::symbol_table::SymbolType;
-use crate::compiler::ast::{Literal, UnaryOperation, BinaryOperation};
+use crate::compiler::ast::{Literal, MAX_EXACT_INTEGER, UnaryOperation, BinaryOperation};
 use crate::compiler::ast::datatype::DataType;
-use crate::compiler::backend::builtin_functions::BARRACUDA_BUILT_IN_FUNCTIONS;
+use crate::compiler::backend::builtin_functions::{BARRACUDA_BUILT_IN_FUNCTIONS, evaluate_builtin_function};
+use crate::compiler::warnings::CompilerWarning;
+use crate::compiler::utils::unpack_f64_array_to_string;
+use crate::compiler::diagnostics::{location, set_location};
 
 use super::function_tracker::FunctionTracker;
 use super::scope_tracker::ScopeTracker;
@@ -23,7 +26,31 @@ pub struct BarracudaSemanticAnalyser {
     symbol_tracker: ScopeTracker,
     scope_counter: ScopeIdGenerator,
     env_vars: HashMap<String, (usize, PrimitiveDataType, Qualifier, String)>,
-    functions: HashMap<String, FunctionTracker>
+    functions: HashMap<String, FunctionTracker>,
+    structs: HashMap<String, DataType>,
+    enums: HashMap<String, HashMap<String, u64>>,
+
+    // Identifiers that are referenced anywhere in the program
+    // constants with these names need storage so are not propagated
+    referenced_identifiers: HashSet<String>,
+
+    // Function implementations whose bodies are being analysed
+    // calls to these are recursive so their return type must be declared
+    implementations_in_progress: HashSet<String>,
+
+    // Max number of functions a program can define
+    // protects against unbounded growth of function tables
+    max_function_count: usize,
+
+    // Warnings found during analysis
+    warnings: Vec<CompilerWarning>,
+
+    // Warn when a declaration shadows a symbol of an enclosing scope
+    warn_shadowing: bool,
+
+    // Word size in bits of the VM the program targets
+    // strings are packed 8 characters to a 64 bit value so need a 64 bit target
+    target_width: usize
 }
 
 impl BarracudaSemanticAnalyser {
@@ -43,12 +70,22 @@ impl BarracudaSemanticAnalyser {
             ASTNode::QUALIFIER(_) => {
                 panic!("Malformed AST! Qualifiers should not be directly analysed during type checking.");
             }
+            ASTNode::MEMBER(name) => {
+                panic!("Malformed AST! Member {} should only be analysed as an index.", name);
+            }
+            // Untyped whole numbers in source are floats, integers are only kept apart to check declarations
+            ASTNode::LITERAL(Literal::INTEGER(value)) if *value <= MAX_EXACT_INTEGER => {
+                self.analyse_literal(&Literal::FLOAT(*value as f64))
+            }
             ASTNode::LITERAL(literal) => {
                 self.analyse_literal(literal)
             }
             ASTNode::ARRAY {items, qualifier } => {
                 self.analyse_array(items, qualifier)
             }
+            ASTNode::TUPLE { items } => {
+                self.analyse_tuple(items)
+            }
             ASTNode::UNARY_OP { op, expression } => {
                 self.analyse_unary_op(op, expression)
             }
@@ -64,9 +101,21 @@ impl BarracudaSemanticAnalyser {
             ASTNode::CONSTRUCT { identifier, datatype, qualifier, expression } => {
                 self.analyse_construct_statement(identifier, datatype, qualifier, expression)
             }
+            ASTNode::DESTRUCTURE { identifiers, qualifier, expression } => {
+                self.analyse_destructure_statement(identifiers, qualifier, expression)
+            }
             ASTNode::EMPTY_CONSTRUCT { identifier, datatype, qualifier } => {
                 self.analyse_empty_construct_statement(identifier, datatype, qualifier)
             }
+            ASTNode::STRUCT { identifier, datatype } => {
+                self.analyse_struct_statement(identifier, datatype)
+            }
+            ASTNode::ENUM { identifier, variants } => {
+                self.analyse_enum_statement(identifier, variants)
+            }
+            ASTNode::ENUM_VARIANT { identifier, variant } => {
+                self.analyse_enum_variant(identifier, variant)
+            }
             ASTNode::EXTERN { identifier } => {
                 self.analyse_extern_statement(identifier)
             }
@@ -76,6 +125,9 @@ impl BarracudaSemanticAnalyser {
             ASTNode::PRINT { expression } => {
                 self.analyse_print_statement(expression)
             },
+            ASTNode::FENCE => {
+                ASTNode::FENCE
+            }
             ASTNode::RETURN { expression } => {
                 self.analyse_return_statement(expression)
             }
@@ -100,9 +152,15 @@ impl BarracudaSemanticAnalyser {
             ASTNode::NAKED_FUNC_CALL { func_call } => {
                 self.analyse_naked_function_call(func_call)
             }
+            ASTNode::SPREAD { .. } => {
+                panic!("Malformed AST! Spread arguments should only be analysed within function calls.")
+            }
             ASTNode::STATEMENT_LIST(statement_list) => {
                 self.analyse_statement_list(statement_list)
             }
+            ASTNode::SOURCE_LINE { line, statement } => {
+                self.analyse_source_line(*line, statement)
+            }
             ASTNode::SCOPE_BLOCK { inner, .. } => {
                 self.analyse_scope_block(inner)
             }
@@ -112,7 +170,45 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
+    /// Collects the names of all identifiers that have a reference taken anywhere in the program.
+    fn find_referenced_identifiers(&mut self, node: &mut ASTNode) {
+        if let ASTNode::REFERENCE(name) = node {
+            self.referenced_identifiers.insert(name.clone());
+        }
+        for child in node.children() {
+            self.find_referenced_identifiers(child);
+        }
+    }
+
+    /// Registers every function before any statements are analysed so functions can be called
+    /// before their definition.
+    fn declare_functions(&mut self, node: &mut ASTNode) {
+        if let ASTNode::FUNCTION { identifier, parameters, return_type, body } = node {
+            if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
+                if self.functions.contains_key(name) {
+                    panic!("Function {} already exisits!", name)
+                }
+                if self.functions.len() >= self.max_function_count {
+                    panic!("Too many functions! Defining {} exceeds the limit of {} functions", name, self.max_function_count)
+                }
+                self.functions.insert(name.clone(), FunctionTracker::new(
+                    parameters.clone(),
+                    return_type.as_ref().clone(),
+                    body.as_ref().clone()
+                ));
+            } else {
+                panic!("Malformed AST! Function names should be identifiers!")
+            }
+        }
+        for child in node.children() {
+            self.declare_functions(child);
+        }
+    }
+
     fn mark_identifier(&mut self, name: &String, datatype: SymbolType) {
+        if self.warn_shadowing && self.symbol_tracker.shadows_symbol(name) {
+            self.warnings.push(CompilerWarning::Shadowing(name.clone(), location().0));
+        }
         self.symbol_tracker.add_symbol(name, datatype);
     }
 
@@ -143,6 +239,11 @@ impl BarracudaSemanticAnalyser {
     fn analyse_identifier(&mut self, name: &String) -> ASTNode {
         let datatype = self.type_from_identifier(name);
         let qualifier = self.qualifier_from_identifier(name);
+
+        // Known constants are replaced with their value
+        if let Some(literal) = self.symbol_tracker.find_constant(name) {
+            return ASTNode::TYPED_NODE { datatype, qualifier, inner: Box::new(ASTNode::LITERAL(literal.clone())) }
+        }
         ASTNode::TYPED_NODE { datatype, qualifier, inner: Box::new(ASTNode::IDENTIFIER(name.clone())) }
     }
 
@@ -156,12 +257,40 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
+    /// References to constants keep the constant qualifier. Storing one in a mutable variable would
+    /// allow the constant to be written through it, so these must be held by constants.
+    fn check_constant_not_referenced(expression: &ASTNode) {
+        if let ASTNode::TYPED_NODE { inner, qualifier, .. } = expression {
+            match inner.as_ref() {
+                ASTNode::REFERENCE(name) if *qualifier == Qualifier::CONSTANT => {
+                    panic!("Can't take a mutable reference to constant {}!", name)
+                }
+                ASTNode::TERNARY_OP { true_branch, false_branch, .. } => {
+                    Self::check_constant_not_referenced(true_branch);
+                    Self::check_constant_not_referenced(false_branch);
+                }
+                _ => {}
+            }
+        }
+    }
+
     fn analyse_literal(&mut self, literal: &Literal) -> ASTNode {
+        // Outside of i64 typed contexts large integers are rounded to floats
+        if let Literal::INTEGER(value) = *literal {
+            if value > MAX_EXACT_INTEGER {
+                self.warnings.push(CompilerWarning::IntegerPrecisionLoss(value, value as f64));
+                return self.analyse_literal(&Literal::FLOAT(value as f64));
+            }
+        }
+        if matches!(literal, Literal::PACKEDSTRING(_)) && self.target_width != 64 {
+            panic!("String literals need a 64 bit target as they are packed 8 characters to a value! (Target is {} bit)", self.target_width)
+        }
         let datatype = match *literal {
             Literal::FLOAT(_) => DataType::PRIMITIVE(PrimitiveDataType::F64),
             Literal::INTEGER(_) => DataType::PRIMITIVE(PrimitiveDataType::I64),
             Literal::BOOL(_) => DataType::PRIMITIVE(PrimitiveDataType::Bool),
             Literal::PACKEDSTRING(_) => DataType::PRIMITIVE(PrimitiveDataType::String),
+            Literal::NULL => DataType::POINTER(Box::new(DataType::NONE)),
         };
 
         ASTNode::TYPED_NODE { 
@@ -198,20 +327,45 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
+    fn analyse_tuple(&mut self, items: &Vec<ASTNode>) -> ASTNode {
+        let typed_items: Vec<ASTNode> = items.iter().map(|item| self.analyse_node(item)).collect();
+        let datatypes = typed_items.iter().map(|item| match item.get_type() {
+            DataType::PRIMITIVE(primitive) | DataType::ENVIRONMENTVARIABLE(primitive) => DataType::PRIMITIVE(primitive),
+            datatype => panic!("Tuples can only contain primitive values! (Found {:?})", datatype)
+        }).collect();
+        ASTNode::TYPED_NODE {
+            datatype: DataType::TUPLE(datatypes),
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::TUPLE { items: typed_items })
+        }
+    }
+
     fn analyse_unary_op(&mut self, op: &UnaryOperation, expression: &Box<ASTNode>) -> ASTNode {
         let expression = self.analyse_node(expression);
         let datatype = expression.get_type();
         let qualifier = expression.get_qualifier();
         let datatype = match op {
-            UnaryOperation::NOT | UnaryOperation::NEGATE => { 
+            UnaryOperation::NOT => {
+                if !datatype.is_bool() {
+                    panic!("Cannot use operation {:?} on non boolean type {:?}", op, datatype)
+                }
+                datatype
+            }
+            UnaryOperation::NEGATE => { 
                 match datatype {
                     DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => datatype,
                     _ => panic!("Cannot use operation {:?} on type {:?}", op, datatype)
                 }
             }
+            UnaryOperation::PLUS | UnaryOperation::SIGN => {
+                if !datatype.is_numeric() {
+                    panic!("Cannot use operation {:?} on non numeric type {:?}", op, datatype)
+                }
+                datatype
+            }
             UnaryOperation::PTR_DEREF => { 
                 match datatype {
-                    DataType::POINTER(inner_datatype) => inner_datatype.as_ref().clone(),
+                    DataType::POINTER(inner_datatype) | DataType::HEAPPOINTER(inner_datatype) => inner_datatype.as_ref().clone(),
                     _ => panic!("Cannot use operation {:?} on type {:?}", op, datatype)
                 }
             }
@@ -231,7 +385,9 @@ impl BarracudaSemanticAnalyser {
         let rhs = self.analyse_node(rhs);
         let lhs_datatype = lhs.get_type();
         let rhs_datatype = rhs.get_type();
-        if lhs_datatype != rhs_datatype {
+        let null_comparison = matches!(op, BinaryOperation::EQUAL | BinaryOperation::NOT_EQUAL)
+            && Self::null_compatible(&lhs_datatype, &rhs_datatype);
+        if lhs_datatype != rhs_datatype && !null_comparison {
             panic!("Cannot perform operation {:?} with mismatched types! ({:?} vs {:?})", op, lhs_datatype, rhs_datatype)
         }
         let datatype = lhs_datatype;
@@ -245,16 +401,26 @@ impl BarracudaSemanticAnalyser {
                     _ => panic!("Cannot use operation {:?} on type {:?}", op, datatype)
                 }
             }
+            BinaryOperation::AND | BinaryOperation::OR => {
+                if !datatype.is_bool() || !rhs_datatype.is_bool() {
+                    panic!("Cannot use operation {:?} on non boolean types! ({:?} vs {:?})", op, datatype, rhs_datatype)
+                }
+                DataType::PRIMITIVE(PrimitiveDataType::Bool)
+            }
+            // Primitives are ordered by their numeric value. Booleans are always 1 or 0 so false < true,
+            // and characters read from strings are their character codes.
             BinaryOperation::GREATER_THAN | BinaryOperation::LESS_THAN 
-          | BinaryOperation::GREATER_EQUAL | BinaryOperation::LESS_EQUAL
-          | BinaryOperation::AND | BinaryOperation::OR => { 
+          | BinaryOperation::GREATER_EQUAL | BinaryOperation::LESS_EQUAL => { 
                 match datatype {
                     DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => DataType::PRIMITIVE(PrimitiveDataType::Bool),
                     _ => panic!("Cannot use operation {:?} on type {:?}", op, datatype)
                 }
             }
             BinaryOperation::EQUAL | BinaryOperation::NOT_EQUAL => { 
-                DataType::PRIMITIVE(PrimitiveDataType::Bool)
+                match datatype {
+                    DataType::TUPLE(_) | DataType::STRUCT(_, _) => panic!("Cannot use operation {:?} on type {:?}", op, datatype),
+                    _ => DataType::PRIMITIVE(PrimitiveDataType::Bool)
+                }
             }
         };
         ASTNode::TYPED_NODE { 
@@ -281,6 +447,11 @@ impl BarracudaSemanticAnalyser {
         if true_branch_datatype != false_branch_datatype {
             panic!("Branches of ternary operator must be the same type! ({:?} vs {:?})", true_branch_datatype, false_branch_datatype)
         }
+        // TERNARY selects between two stack values so only single value types can be selected.
+        // Pointers to arrays can be selected instead.
+        if matches!(true_branch_datatype, DataType::ARRAY(_, _) | DataType::STRUCT(_, _) | DataType::TUPLE(_) | DataType::NONE) {
+            panic!("Branches of ternary operator must be single values, not {:?}! Select pointers to them instead.", true_branch_datatype)
+        }
         let datatype = true_branch_datatype;
 
         ASTNode::TYPED_NODE { 
@@ -296,14 +467,26 @@ impl BarracudaSemanticAnalyser {
 
     fn analyse_array_index(&mut self, index: &Box<ASTNode>, expression: &Box<ASTNode>) -> ASTNode {
         let expression = Box::new(self.analyse_node(expression));
+        if let ASTNode::MEMBER(name) = index.as_ref() {
+            let (index, datatype) = self.analyse_member(name, &expression.get_type());
+            return ASTNode::TYPED_NODE {
+                datatype,
+                qualifier: expression.get_qualifier(),
+                inner: Box::new(ASTNode::ARRAY_INDEX { index: Box::new(index), expression })
+            }
+        }
         let index = Box::new(self.analyse_node(index));
         let expression_datatype = expression.get_type();
         let index_datatype = index.get_type();
         // check index is a literal and expression is an array/environmentvariable. Return array innards
         match expression_datatype {
-            DataType::ARRAY(inner_type, _size) => {
+            DataType::ARRAY(inner_type, _) if matches!(*inner_type, DataType::PRIMITIVE(PrimitiveDataType::String)) => {
+                self.analyse_string_index(&index, &expression)
+            }
+            DataType::ARRAY(inner_type, size) => {
                 match index_datatype {
                     DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => {
+                        Self::check_literal_index_bounds(&index, Some(size));
                         ASTNode::TYPED_NODE { 
                             datatype: inner_type.as_ref().clone(), 
                             qualifier: expression.get_qualifier(),
@@ -316,6 +499,7 @@ impl BarracudaSemanticAnalyser {
             DataType::ENVIRONMENTVARIABLE(inner_type) => {
                 match index_datatype {
                     DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => {
+                        Self::check_literal_index_bounds(&index, None);
                         ASTNode::TYPED_NODE { 
                             datatype: DataType::PRIMITIVE(inner_type), 
                             qualifier: expression.get_qualifier(),
@@ -329,6 +513,102 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
+    /// Member accesses are replaced with the offset of the field within the struct.
+    /// @return: (offset literal, datatype of the field)
+    fn analyse_member(&mut self, name: &String, datatype: &DataType) -> (ASTNode, DataType) {
+        if !matches!(datatype, DataType::STRUCT(_, _)) {
+            panic!("Can't access member {} of a non-struct! ({:?})", name, datatype)
+        }
+        // Structs reached through pointer members are only named, so their fields are looked up
+        let datatype = &self.resolve_datatype(datatype);
+        match datatype.get_field(name) {
+            Some((offset, field_datatype)) => (self.analyse_literal(&Literal::INTEGER(offset as u64)), field_datatype),
+            None => panic!("{:?} has no member {}!", datatype, name)
+        }
+    }
+
+    /// Strings are bit packed so characters are read at compile time, giving the character code.
+    fn analyse_string_index(&mut self, index: &ASTNode, expression: &ASTNode) -> ASTNode {
+        let contents = self.string_contents(expression)
+            .filter(|_| !Self::is_mutable_variable(expression))
+            .unwrap_or_else(|| panic!("Can only index string literals or constant strings!"));
+        let index = Self::string_character_index(index, &contents);
+        self.analyse_literal(&Literal::INTEGER(contents.as_bytes()[index] as u64))
+    }
+
+    /// Characters of mutable strings are written to their byte within user space.
+    /// Strings are packed eight characters to a value with the final value padded at its start,
+    /// so the character index is replaced with the byte offset of the character from the string.
+    fn analyse_string_character_assignment(&mut self, index: &ASTNode, identifier: &ASTNode) -> ASTNode {
+        let contents = self.string_contents(identifier)
+            .unwrap_or_else(|| panic!("Can only assign characters of string variables!"));
+        let index = Self::string_character_index(&self.analyse_node(index), &contents);
+        let last_value = (contents.len() - 1) / 8;
+        let padding = if index / 8 == last_value { 8 * (last_value + 1) - contents.len() } else { 0 };
+        self.analyse_literal(&Literal::INTEGER((index + padding) as u64))
+    }
+
+    /// Checks a string index is an integer literal within the string.
+    /// @return: the index of the character
+    fn string_character_index(index: &ASTNode, contents: &String) -> usize {
+        let index = Self::constant_value(index)
+            .filter(|index| index.fract() == 0.0)
+            .unwrap_or_else(|| panic!("Strings can only be indexed with integer literals!"));
+        if index < 0.0 || index >= contents.len() as f64 {
+            panic!("String index {} is out of bounds for string of length {}!", index, contents.len())
+        }
+        index as usize
+    }
+
+    fn is_mutable_variable(node: &ASTNode) -> bool {
+        match node {
+            ASTNode::TYPED_NODE { inner, qualifier, .. } => {
+                *qualifier == Qualifier::MUTABLE && matches!(inner.as_ref(), ASTNode::IDENTIFIER(_))
+            }
+            _ => false
+        }
+    }
+
+    /// Returns the contents of a string literal or of an identifier bound to a constant string.
+    fn string_contents(&self, node: &ASTNode) -> Option<String> {
+        match node {
+            ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
+                ASTNode::ARRAY { items, .. } => {
+                    let packed: Option<Vec<f64>> = items.iter().map(|item| match item {
+                        ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
+                            ASTNode::LITERAL(Literal::PACKEDSTRING(value)) => Some(*value),
+                            _ => None
+                        }
+                        _ => None
+                    }).collect();
+                    packed.map(|packed| unpack_f64_array_to_string(&packed))
+                }
+                ASTNode::IDENTIFIER(name) => self.symbol_tracker.find_string(name).cloned(),
+                _ => None
+            }
+            _ => None
+        }
+    }
+
+    /// Integer literal indices are known at compile time so can be checked against the array size.
+    /// Whole numbers are parsed as decimals so integral float literals are treated as integers too.
+    /// Negative literal indices are never valid so are rejected even when the size isn't known,
+    /// as with environment variables. Dynamic indices are left unchecked.
+    fn check_literal_index_bounds(index: &ASTNode, size: Option<usize>) {
+        let value = match Self::constant_value(index) {
+            Some(value) if value.fract() == 0.0 => value,
+            _ => return
+        };
+        if value < 0.0 {
+            panic!("Array index {} is negative!", value)
+        }
+        if let Some(size) = size {
+            if value >= size as f64 {
+                panic!("Array index {} is out of bounds for array of size {}!", value, size)
+            }
+        }
+    }
+
     fn analyse_construct_statement(
         &mut self,
         identifier: &Box<ASTNode>,
@@ -336,19 +616,78 @@ impl BarracudaSemanticAnalyser {
         qualifier: &Box<ASTNode>,
         expression: &Box<ASTNode>
     ) -> ASTNode {
+        let datatype = &Box::new(datatype.as_ref().as_ref().map(|datatype| ASTNode::DATATYPE(self.resolve_datatype(&DataType::from(datatype)))));
+
+        // Float literals would be truncated by integer declarations, while integer literals are widened by float declarations
+        if let Some(ASTNode::DATATYPE(declared_datatype)) = datatype.as_ref() {
+            match Self::signed_literal(expression) {
+                Some((sign, Literal::FLOAT(value))) if declared_datatype.is_integer() => {
+                    panic!("Can't initialise {:?} with float literal {:?}! Use an integer literal instead.", declared_datatype, sign * value)
+                }
+                // Widening is implicit, so only integers the float can't hold exactly are worth flagging.
+                // Those beyond f64 are already flagged as they lose precision in every context.
+                Some((sign, Literal::INTEGER(value)))
+                    if declared_datatype.is_float() && *value <= MAX_EXACT_INTEGER && !Self::exact_in_float(*value, declared_datatype) => {
+                    let name = identifier.identifier_name().unwrap_or_default();
+                    self.warnings.push(CompilerWarning::IntegerWidening(name, sign * *value as f64));
+                }
+                _ => {}
+            }
+        }
+
         // First, analyze the expression and get its type.
-        let mut analyzed_expr = self.analyse_node(expression);
+        // Integer literals declared as i64 keep their exact value, even if they're too large to be floats.
+        let mut analyzed_expr = match (datatype.as_ref(), expression.as_ref()) {
+            (Some(ASTNode::DATATYPE(DataType::PRIMITIVE(PrimitiveDataType::I64))), ASTNode::LITERAL(literal @ Literal::INTEGER(_))) => {
+                ASTNode::TYPED_NODE {
+                    datatype: DataType::PRIMITIVE(PrimitiveDataType::I64),
+                    qualifier: Qualifier::CONSTANT,
+                    inner: Box::new(ASTNode::LITERAL(literal.clone()))
+                }
+            }
+            _ => self.analyse_node(expression)
+        };
+        if let Some(ASTNode::DATATYPE(declared_datatype)) = datatype.as_ref() {
+            if declared_datatype.is_bool() {
+                analyzed_expr = self.normalise_bool(analyzed_expr);
+            }
+        }
         let expression_datatype = analyzed_expr.get_type();
+        if let DataType::TUPLE(_) = expression_datatype {
+            panic!("Tuples must be destructured into variables! e.g. let (a, b) = <expression>;")
+        }
+        if let DataType::NONE = expression_datatype {
+            panic!("Can't bind an expression with no value to a variable! {:?}", identifier)
+        }
     
         // Extract the declared qualifier from the construct.
         let declared_qualifier = match **qualifier {
             ASTNode::QUALIFIER(ref q) => q.clone(),
             _ => panic!("Malformed AST! Expected a qualifier node"),
         };
+        if declared_qualifier == Qualifier::MUTABLE {
+            Self::check_constant_not_referenced(&analyzed_expr);
+        }
     
         // Register the new variable using the expression's type and the declared qualifier.
+        // Primitives keep their declared type so integer declarations are known to be integers.
+        let symbol_datatype = match datatype.as_ref() {
+            Some(ASTNode::DATATYPE(declared_datatype @ DataType::PRIMITIVE(_))) if matches!(expression_datatype, DataType::PRIMITIVE(_)) => {
+                declared_datatype.clone()
+            }
+            // Pointers initialised with null keep their declared type so they can be dereferenced
+            Some(ASTNode::DATATYPE(declared_datatype)) if Self::null_compatible(declared_datatype, &expression_datatype) => {
+                declared_datatype.clone()
+            }
+            _ => expression_datatype.clone()
+        };
         if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
-            self.mark_identifier(name, SymbolType::Variable(expression_datatype.clone(), declared_qualifier.clone()));
+            self.mark_identifier(name, SymbolType::Variable(symbol_datatype, declared_qualifier.clone()));
+            // Strings don't change length so their contents locate characters at compile time.
+            // Only constant strings can have their characters read at compile time.
+            if let Some(contents) = self.string_contents(&analyzed_expr) {
+                self.symbol_tracker.add_string(name, contents);
+            }
         }
     
         // Override the qualifier in the array literal (if the expression is an array)
@@ -385,9 +724,12 @@ impl BarracudaSemanticAnalyser {
                 ASTNode::DATATYPE(dt) => dt,
                 _ => panic!("Malformed AST! Node {:?} should have been a datatype but wasn't!", datatype_node),
             };
-            if declared_datatype != expression_datatype {
+            if declared_datatype != expression_datatype && !Self::null_compatible(&declared_datatype, &expression_datatype) {
                 panic!("Provided data doesn't match given datatype in construct statement! {:?} vs {:?}", declared_datatype, expression_datatype);
             }
+            if self.propagate_constant(identifier, &declared_qualifier, &analyzed_expr) {
+                return ASTNode::STATEMENT_LIST(vec![]);
+            }
             let datatype_box: Box<Option<ASTNode>> = Box::new(Some(ASTNode::DATATYPE(declared_datatype)));
             ASTNode::CONSTRUCT { 
                 identifier: identifier_node, 
@@ -396,6 +738,9 @@ impl BarracudaSemanticAnalyser {
                 expression: Box::new(analyzed_expr)
             }
         } else {
+            if self.propagate_constant(identifier, &declared_qualifier, &analyzed_expr) {
+                return ASTNode::STATEMENT_LIST(vec![]);
+            }
             let datatype_box: Box<Option<ASTNode>> = Box::new(Some(ASTNode::DATATYPE(expression_datatype)));
             ASTNode::CONSTRUCT { 
                 identifier: identifier_node, 
@@ -406,12 +751,121 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
+    /// Normalises a non boolean value stored as a boolean to exactly 1 or 0 by comparing it with 0.
+    /// Constants are converted directly so they can still be propagated.
+    fn normalise_bool(&mut self, expression: ASTNode) -> ASTNode {
+        let expression_datatype = expression.get_type();
+        if !matches!(expression_datatype, DataType::PRIMITIVE(_)) || expression_datatype.is_bool() {
+            return expression
+        }
+        match Self::constant_value(&expression) {
+            Some(value) => self.analyse_literal(&Literal::BOOL(value != 0.0)),
+            None => ASTNode::TYPED_NODE {
+                datatype: DataType::PRIMITIVE(PrimitiveDataType::Bool),
+                qualifier: Qualifier::CONSTANT,
+                inner: Box::new(ASTNode::BINARY_OP {
+                    op: BinaryOperation::NOT_EQUAL,
+                    lhs: Box::new(expression),
+                    rhs: Box::new(self.analyse_literal(&Literal::FLOAT(0.0)))
+                })
+            }
+        }
+    }
+
+    /// Whether one of two types is null and the other a pointer. null is a pointer to none so it can be
+    /// stored in and compared with any pointer, which is checked here as a PartialEq match would not be transitive.
+    fn null_compatible(this: &DataType, other: &DataType) -> bool {
+        let is_null = |datatype: &DataType| matches!(datatype, DataType::POINTER(inner) if **inner == DataType::NONE);
+        let is_pointer = |datatype: &DataType| matches!(datatype, DataType::POINTER(_) | DataType::HEAPPOINTER(_));
+        (is_null(this) && is_pointer(other)) || (is_pointer(this) && is_null(other))
+    }
+
+    /// Whether an integer can be held exactly by a float type, which is when its significant bits fit in the mantissa.
+    fn exact_in_float(value: u64, datatype: &DataType) -> bool {
+        let mantissa_bits = match datatype {
+            DataType::PRIMITIVE(PrimitiveDataType::F8) => 4,
+            DataType::PRIMITIVE(PrimitiveDataType::F16) => 11,
+            DataType::PRIMITIVE(PrimitiveDataType::F32) => 24,
+            DataType::PRIMITIVE(PrimitiveDataType::F64) => 53,
+            _ => 113
+        };
+        let dropped_bits = (u64::BITS - value.leading_zeros()).saturating_sub(mantissa_bits);
+        value.trailing_zeros() >= dropped_bits
+    }
+
+    /// Returns a literal written directly in source before analysis, along with its sign as 1 or -1.
+    fn signed_literal(node: &ASTNode) -> Option<(f64, &Literal)> {
+        match node {
+            ASTNode::LITERAL(literal) => Some((1.0, literal)),
+            ASTNode::UNARY_OP { op: UnaryOperation::NEGATE, expression } => Self::signed_literal(expression).map(|(sign, literal)| (-sign, literal)),
+            ASTNode::UNARY_OP { op: UnaryOperation::PLUS, expression } => Self::signed_literal(expression),
+            _ => None
+        }
+    }
+
+    /// Records a constant scalar initialised with a literal so uses of it can be replaced with the literal.
+    /// Constants that are referenced still need storage and are not propagated.
+    /// @return: true if the constant was recorded and the construct statement can be removed
+    fn propagate_constant(&mut self, identifier: &Box<ASTNode>, qualifier: &Qualifier, expression: &ASTNode) -> bool {
+        let name = match identifier.as_ref() {
+            ASTNode::IDENTIFIER(name) => name,
+            _ => return false
+        };
+        if *qualifier != Qualifier::CONSTANT || self.referenced_identifiers.contains(name) {
+            return false;
+        }
+        if let ASTNode::TYPED_NODE { datatype: DataType::PRIMITIVE(_), inner, .. } = expression {
+            if let ASTNode::LITERAL(literal) = inner.as_ref() {
+                if let Literal::PACKEDSTRING(_) = literal {
+                    return false;
+                }
+                self.symbol_tracker.add_constant(name, literal.clone());
+                return true;
+            }
+        }
+        false
+    }
+
+    fn analyse_destructure_statement(&mut self, identifiers: &Vec<ASTNode>, qualifier: &Box<ASTNode>, expression: &Box<ASTNode>) -> ASTNode {
+        let expression = self.analyse_node(expression);
+        let datatypes = match expression.get_type() {
+            DataType::TUPLE(datatypes) => datatypes,
+            datatype => panic!("Only tuples can be destructured! (Found {:?})", datatype)
+        };
+        if datatypes.len() != identifiers.len() {
+            panic!("Can't destructure a tuple of {} values into {} variables!", datatypes.len(), identifiers.len())
+        }
+        let declared_qualifier = match qualifier.as_ref() {
+            ASTNode::QUALIFIER(q) => q.clone(),
+            _ => panic!("Malformed AST! Expected a qualifier node"),
+        };
+
+        // Each item is bound like a construct statement
+        let mut typed_identifiers = vec![];
+        for (identifier, datatype) in identifiers.iter().zip(datatypes) {
+            if let ASTNode::IDENTIFIER(name) = identifier {
+                self.mark_identifier(name, SymbolType::Variable(datatype, declared_qualifier.clone()));
+            }
+            typed_identifiers.push(self.analyse_node(identifier));
+        }
+        ASTNode::DESTRUCTURE {
+            identifiers: typed_identifiers,
+            qualifier: qualifier.clone(),
+            expression: Box::new(expression)
+        }
+    }
+
     fn analyse_empty_construct_statement(&mut self, identifier: &Box<ASTNode>, datatype: &Box<ASTNode>, qualifier: &Box<ASTNode>) -> ASTNode {
         if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
             let datatype = match datatype.as_ref() {
-                ASTNode::DATATYPE(datatype) => datatype,
+                ASTNode::DATATYPE(datatype) => &self.resolve_datatype(datatype),
                 _ => panic!("Malformed AST! Node {:?} should have been a datatype but wasn't!", datatype)
             };
+            match datatype {
+                DataType::TUPLE(_) => panic!("Tuples must be destructured into variables! e.g. let (a, b) = <expression>;"),
+                DataType::NONE => panic!("Can't declare variable {} with no value!", name),
+                _ => {}
+            }
             let qualifier = match qualifier.as_ref() {
                 ASTNode::QUALIFIER(ref q) => q.clone(),
                 _ => panic!("Malformed AST! Expected a qualifier node"),
@@ -426,6 +880,91 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
+    /// Records the fields of a struct so datatypes can refer to it by name.
+    /// Structs currently only hold primitives and pointers, which may point to the struct itself.
+    fn analyse_struct_statement(&mut self, identifier: &Box<ASTNode>, datatype: &Box<ASTNode>) -> ASTNode {
+        let name = identifier.identifier_name().unwrap();
+        let datatype = DataType::from(datatype);
+        self.datatype_size(&DataType::STRUCT(name.clone(), vec![]), &datatype, &mut vec![]);
+        if let DataType::STRUCT(_, fields) = &datatype {
+            for (index, (field_name, field_datatype)) in fields.iter().enumerate() {
+                if !matches!(field_datatype, DataType::PRIMITIVE(_) | DataType::POINTER(_) | DataType::HEAPPOINTER(_)) {
+                    panic!("Member {} of struct {} must be a primitive or a pointer! Found {:?}", field_name, name, field_datatype)
+                }
+                if fields[..index].iter().any(|(other_name, _)| other_name == field_name) {
+                    panic!("Struct {} has more than one member named {}!", name, field_name)
+                }
+            }
+        }
+        if self.structs.insert(name.clone(), datatype).is_some() {
+            panic!("Struct {} is already defined!", name)
+        }
+        ASTNode::STATEMENT_LIST(vec![])
+    }
+
+    /// Computes the number of values a datatype takes up, following structs by name. Pointers take
+    /// up a single value whatever they point to. Structs containing themselves by value would be
+    /// infinitely sized so are rejected.
+    /// @definition: the struct being defined, which isn't recorded yet
+    /// @enclosing: names of the structs the datatype is nested within
+    fn datatype_size(&self, datatype: &DataType, definition: &DataType, enclosing: &mut Vec<String>) -> usize {
+        match datatype {
+            DataType::ARRAY(inner, size) => size * self.datatype_size(inner, definition, enclosing),
+            DataType::TUPLE(items) => items.iter().map(|item| self.datatype_size(item, definition, enclosing)).sum(),
+            DataType::STRUCT(name, _) => {
+                if enclosing.contains(name) {
+                    panic!("Struct {} contains itself so would be infinitely sized! Use a pointer to it instead.", name)
+                }
+                let fields = match definition {
+                    DataType::STRUCT(definition_name, fields) if definition_name == name => fields,
+                    _ => match self.structs.get(name) {
+                        Some(DataType::STRUCT(_, fields)) => fields,
+                        _ => panic!("Unknown datatype {}!", name)
+                    }
+                };
+                enclosing.push(name.clone());
+                let size = fields.iter().map(|(_, field)| self.datatype_size(field, definition, enclosing)).sum();
+                enclosing.pop();
+                size
+            }
+            _ => 1
+        }
+    }
+
+    /// Records the values of an enum's variants so they can be replaced with integer literals.
+    fn analyse_enum_statement(&mut self, identifier: &Box<ASTNode>, variants: &Vec<(String, Option<u64>)>) -> ASTNode {
+        let name = identifier.identifier_name().unwrap();
+        let mut values = HashMap::new();
+        let mut next_value = Some(0);
+        for (variant, value) in variants {
+            let value = match value {
+                Some(value) => *value,
+                None => next_value.unwrap_or_else(|| panic!("Value of enum variant {}::{} overflows!", name, variant))
+            };
+            if values.insert(variant.clone(), value).is_some() {
+                panic!("Enum {} has more than one variant named {}!", name, variant)
+            }
+            next_value = value.checked_add(1);
+        }
+        if self.enums.insert(name.clone(), values).is_some() {
+            panic!("Enum {} is already defined!", name)
+        }
+        ASTNode::STATEMENT_LIST(vec![])
+    }
+
+    fn analyse_enum_variant(&mut self, identifier: &Box<ASTNode>, variant: &Box<ASTNode>) -> ASTNode {
+        let name = identifier.identifier_name().unwrap();
+        let variant = variant.identifier_name().unwrap();
+        let value = match self.enums.get(&name) {
+            Some(values) => match values.get(&variant) {
+                Some(value) => *value,
+                None => panic!("Enum {} has no variant {}!", name, variant)
+            },
+            None => panic!("Enum {} doesn't exist!", name)
+        };
+        self.analyse_literal(&Literal::INTEGER(value))
+    }
+
     fn analyse_extern_statement(&mut self, identifier: &Box<ASTNode>) -> ASTNode {
         if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
             match self.env_vars.get(name) {
@@ -442,48 +981,83 @@ impl BarracudaSemanticAnalyser {
     }
 
     fn analyse_assignment_statement(&mut self, identifier: &Box<ASTNode>, pointer_level: usize, array_index: &Vec<ASTNode>, expression: &Box<ASTNode>) -> ASTNode {
+        if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
+            if self.symbol_tracker.find_symbol(name).is_none() && self.functions.contains_key(name) {
+                panic!("Invalid assignment target {}! Functions can't be assigned to.", name)
+            }
+        }
         let identifier = Box::new(self.analyse_node(identifier));
         let mut identifier_datatype = identifier.get_type();
         let identifier_qualifier = identifier.get_qualifier();
 
         for _ in 0..pointer_level {
             identifier_datatype = match identifier_datatype {
-                DataType::POINTER(datatype) => *datatype,
+                DataType::POINTER(datatype) | DataType::HEAPPOINTER(datatype) => *datatype,
                 _ => panic!("Can't perform pointer assignment on a non-pointer!")
             };
         }
 
         let mut new_index = Vec::new();
-        for index in array_index { 
+        if let DataType::ARRAY(inner_type, _) = &identifier_datatype {
+            if matches!(**inner_type, DataType::PRIMITIVE(PrimitiveDataType::String)) && array_index.len() == 1 {
+                new_index.push(self.analyse_string_character_assignment(&array_index[0], &identifier));
+                identifier_datatype = inner_type.as_ref().clone();
+            }
+        }
+        for index in array_index.iter().skip(new_index.len()) {
+            if let ASTNode::MEMBER(name) = index {
+                let (index, datatype) = self.analyse_member(name, &identifier_datatype);
+                identifier_datatype = datatype;
+                new_index.push(index);
+                continue;
+            }
             let index = self.analyse_node(index);
             let index_datatype = index.get_type();
             match index_datatype {
                 DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => {}
                 _ => panic!("Can only index arrays with literal values!")
             };
-            new_index.push(index);
             identifier_datatype = match identifier_datatype {
-                DataType::ARRAY(datatype, _) => *datatype,
-                DataType::ENVIRONMENTVARIABLE(datatype) => DataType::ENVIRONMENTVARIABLE(datatype),
+                DataType::ARRAY(datatype, size) => {
+                    Self::check_literal_index_bounds(&index, Some(size));
+                    *datatype
+                }
+                DataType::ENVIRONMENTVARIABLE(datatype) => {
+                    Self::check_literal_index_bounds(&index, None);
+                    DataType::ENVIRONMENTVARIABLE(datatype)
+                }
                 _ => panic!("Can't index a non-array!")
             };
+            new_index.push(index);
         }
 
-        let expression = self.analyse_node(expression);
+        let mut expression = self.analyse_node(expression);
+        if identifier_datatype.is_bool() {
+            expression = self.normalise_bool(expression);
+        }
         let expression_datatype = expression.get_type();
         let expression = Box::new(expression);
 
+        if let DataType::NONE = expression_datatype {
+            panic!("Can't assign an expression with no value to a variable! {:?}", identifier)
+        }
+
         match identifier_qualifier {
             Qualifier::CONSTANT => panic!("Can't assign to a constant value! {:?}", identifier),
             _ => {}
         }
+        Self::check_constant_not_referenced(&expression);
+
+        if let DataType::STRUCT(_, _) = identifier_datatype {
+            panic!("Structs can only be assigned one member at a time! {:?}", identifier)
+        }
 
         //match identifier_datatype {
         //    DataType::PRIMITIVE(_) => panic!("Can't assign to a constant value! {:?}", identifier),
         //    _ => {}
         //}
 
-        if expression_datatype != identifier_datatype {
+        if expression_datatype != identifier_datatype && !Self::null_compatible(&identifier_datatype, &expression_datatype) {
             panic!("Identifier and expression must be equal in an assignment statement! (Currently {:?} vs {:?})", identifier_datatype, expression_datatype)
         }
         
@@ -495,9 +1069,11 @@ impl BarracudaSemanticAnalyser {
         ASTNode::PRINT { expression }
     }
 
-    fn analyse_return_statement(&mut self, expression: &Box<ASTNode>) -> ASTNode {
-        let expression = Box::new(self.analyse_node(expression));
-        self.symbol_tracker.add_return_type(&expression.get_type());
+    fn analyse_return_statement(&mut self, expression: &Box<Option<ASTNode>>) -> ASTNode {
+        // Returning without a value is a return of type none
+        let expression = Box::new(expression.as_ref().as_ref().map(|expression| self.analyse_node(expression)));
+        let datatype = expression.as_ref().as_ref().map_or(DataType::NONE, |expression| expression.get_type());
+        self.symbol_tracker.add_return_type(&datatype);
         ASTNode::RETURN { expression }
     }
 
@@ -527,6 +1103,9 @@ impl BarracudaSemanticAnalyser {
             DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => {},
             _ => panic!("Literal values must be used for while statement conditions!")
         }
+        if condition.is_constant_false() {
+            self.warnings.push(CompilerWarning::DeadLoop(String::from("while")));
+        }
         ASTNode::WHILE_LOOP { condition, body }
     }
 
@@ -540,40 +1119,33 @@ impl BarracudaSemanticAnalyser {
             DataType::PRIMITIVE(_) | DataType::ENVIRONMENTVARIABLE(_) => {},
             _ => panic!("Literal values must be used for for statement conditions!")
         }
+        if condition.is_constant_false() {
+            self.warnings.push(CompilerWarning::DeadLoop(String::from("for")));
+        }
         ASTNode::FOR_LOOP { initialization, condition, advancement, body }
     }
 
+    /// Functions are registered by declare_functions before analysis. Their bodies are analysed
+    /// when they're called, once the types of their parameters are known.
     fn analyse_function_definition(&mut self, identifier: &Box<ASTNode>, parameters: &Vec<ASTNode>, return_type: &Box<Option<ASTNode>>, body: &Box<ASTNode>) -> ASTNode {
-        if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
-            if !self.functions.contains_key(name) {
-                self.functions.insert(name.clone(), FunctionTracker::new(
-                    parameters.clone(), 
-                    return_type.as_ref().clone(), 
-                    body.as_ref().clone()
-                ));
-                ASTNode::FUNCTION {
-                    identifier: identifier.clone(),
-                    parameters: parameters.clone(),
-                    return_type: return_type.clone(),
-                    body: body.clone()
-                }
-            } else {
-                panic!("Function {} already exisits!", name)
-            }
-        } else {
-            panic!("Malformed AST! Function names should be identifiers!")
+        ASTNode::FUNCTION {
+            identifier: identifier.clone(),
+            parameters: parameters.clone(),
+            return_type: return_type.clone(),
+            body: body.clone()
         }
     }
 
     fn analyse_function_implementation(
         &mut self, 
+        name: &String,
         parameters: &Vec<DataType>, 
         parameter_names: &Vec<String>, 
         parameter_qualifiers: &Vec<Qualifier>,
         return_type: &Option<DataType>, 
         body: &ASTNode
     ) -> (ASTNode, DataType) {
-        self.symbol_tracker.enter_scope();
+        self.symbol_tracker.enter_function_scope();
         for ((identifier, datatype), qualifier) in parameter_names.iter().zip(parameters.iter()).zip(parameter_qualifiers.iter()) { 
             let parameter_datatype = match datatype.clone() {
                 DataType::PRIMITIVE(primitive) => DataType::PRIMITIVE(primitive),
@@ -581,35 +1153,123 @@ impl BarracudaSemanticAnalyser {
             };
             self.mark_identifier(identifier, SymbolType::Variable(parameter_datatype,qualifier.clone()));
         }
-        let body = self.analyse_node(body);
+        // The function scope holding the parameters is the body's scope, so the body isn't analysed as a nested block
+        let body = match body {
+            ASTNode::SCOPE_BLOCK { inner, .. } => {
+                let scope = self.scope_counter.next().unwrap();
+                ASTNode::SCOPE_BLOCK { inner: Box::new(self.analyse_node(inner)), scope }
+            }
+            _ => panic!("Malformed AST! Function body should be a scope block! Full body: {:?}", body)
+        };
         let real_return_type = self.symbol_tracker.get_return_type().clone();
         self.symbol_tracker.exit_scope();
-        if let Some(return_type) = return_type {
+        if let Some(return_type) = &return_type.as_ref().map(|return_type| self.resolve_datatype(return_type)) {
             if return_type != &real_return_type {
                 panic!("Return type of function did not match declared type! ({:?} vs {:?})", return_type, real_return_type)
             }
+            // Falling off the end returns whatever is left in the return store
+            let warning = CompilerWarning::MissingReturn(name.clone());
+            if *return_type != DataType::NONE && !body.always_returns() && !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
         }
         return (body, real_return_type)
     }
 
     fn analyse_function_call(&mut self, identifier: &Box<ASTNode>, arguments: &Vec<ASTNode>) -> ASTNode {
+        // Lookup tables take a function name as an argument so are handled before argument analysis
+        if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
+            if name == "lut" && !self.functions.contains_key(name) {
+                return self.analyse_lookup_table(arguments);
+            }
+            if name == "len" && !self.functions.contains_key(name) {
+                return self.analyse_length(arguments);
+            }
+            if name == "sizeof" && !self.functions.contains_key(name) {
+                return self.analyse_sizeof(arguments);
+            }
+            if name == "malloc" && !self.functions.contains_key(name) {
+                return self.analyse_malloc(arguments);
+            }
+            if name == "free" && !self.functions.contains_key(name) {
+                return self.analyse_free(arguments);
+            }
+            if name == "abs" && !self.functions.contains_key(name) {
+                return self.analyse_abs(arguments);
+            }
+            if name == "sign" && !self.functions.contains_key(name) {
+                return self.analyse_sign(arguments);
+            }
+            if name == "clamp" && !self.functions.contains_key(name) {
+                return self.analyse_clamp(arguments);
+            }
+            if name == "approx_eq" && !self.functions.contains_key(name) {
+                return self.analyse_approx_eq(arguments);
+            }
+            if (name == "rand" || name == "rand_seed") && !self.functions.contains_key(name) {
+                return self.analyse_function_call(&Box::new(ASTNode::IDENTIFIER(format!("__{}", name))), arguments);
+            }
+        }
         let mut typed_arguments: Vec<ASTNode> = vec![];
         for argument in arguments {
-            typed_arguments.push(self.analyse_node(argument))
+            match argument {
+                ASTNode::SPREAD { expression } => {
+                    typed_arguments.append(&mut self.analyse_spread_argument(expression))
+                }
+                _ => typed_arguments.push(self.analyse_node(argument))
+            }
+        }
+        // Fill omitted trailing arguments with their parameter defaults
+        let mut required_arguments = typed_arguments.len();
+        if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
+            if let Some(function) = self.functions.get(name) {
+                required_arguments = function.required_parameter_count();
+                if typed_arguments.len() >= required_arguments {
+                    let defaults = function.get_parameter_defaults().clone();
+                    for default in defaults.iter().skip(typed_arguments.len()).flatten() {
+                        typed_arguments.push(self.analyse_node(default));
+                    }
+                }
+            }
+        }
+        // Arguments bound to bool parameters are normalised like any other bool declaration
+        if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
+            if let Some(function) = self.functions.get(name) {
+                let parameter_datatypes = function.get_innards().0.clone();
+                for (argument, parameter_datatype) in typed_arguments.iter_mut().zip(parameter_datatypes.iter()) {
+                    if parameter_datatype.as_ref().is_some_and(DataType::is_bool) {
+                        *argument = self.normalise_bool(argument.clone());
+                    }
+                }
+            }
         }
         let mut argument_datatypes: Vec<DataType> = vec![];
         let mut argument_types: Vec<(DataType, Qualifier)> = vec![];
         for argument in typed_arguments.iter() {
+            if let DataType::TUPLE(_) = argument.get_type() {
+                panic!("Tuples can't be passed as function arguments! Destructure them first.")
+            }
             // Push tuple of qualifier and datatype to argument_types
             argument_datatypes.push(argument.get_type());
             argument_types.push((argument.get_type(), argument.get_qualifier()));
         }
-        // TODO: Perform a check to see whether an input variable that may be mutable is actually mutated in the function, if not then allow it to be passed into a function with a const parameter qualifier.
         if let ASTNode::IDENTIFIER(name) = identifier.as_ref() {
             if self.functions.contains_key(name) {
                 let function = self.functions.get(name).unwrap();
+                // Mutable arguments can be passed to const parameters the function body never assigns to
+                let (_, parameter_names, parameter_qualifiers, _, body) = function.get_innards();
+                for ((_, argument_qualifier), (parameter_name, parameter_qualifier)) in
+                    argument_types.iter_mut().zip(parameter_names.iter().zip(parameter_qualifiers.iter())) {
+                    if *argument_qualifier == Qualifier::MUTABLE && *parameter_qualifier == Qualifier::CONSTANT
+                        && !body.assigns_to(parameter_name) {
+                        *argument_qualifier = Qualifier::CONSTANT;
+                    }
+                }
                 match function.match_function(&argument_types) {
                     Some((implementation_name, datatype)) => {
+                        if self.implementations_in_progress.contains(&implementation_name) && function.get_innards().3.is_none() {
+                            panic!("Recursive function {} must declare its return type!", name)
+                        }
                         ASTNode::TYPED_NODE {
                             datatype,
                             qualifier: Qualifier::CONSTANT,
@@ -628,16 +1288,31 @@ impl BarracudaSemanticAnalyser {
                             parameter_names.iter().cloned()
                             .zip(parameter_datatypes.iter().cloned().zip(parameter_qualifiers.iter().cloned()))
                             .collect();
-                        let real_datatypes = self.check_parameter_list(&parameters, &argument_types, name);
+                        let return_type = return_type.clone();
+                        let body = body.clone();
+                        let real_datatypes = self.check_parameter_list(&parameters, required_arguments, &argument_types, name);
+                        // The implementation exists before its body is analysed so recursive calls can match it
+                        let function = self.functions.get_mut(name).unwrap();
+                        let implementation_name = function.create_implementation(
+                            name.clone(),
+                            parameter_names.clone(),
+                            real_datatypes.clone(),
+                            parameter_qualifiers.clone(),
+                            return_type.clone().unwrap_or(DataType::NONE),
+                            body.clone()
+                        );
+                        self.implementations_in_progress.insert(implementation_name.clone());
                         let (body, return_type) = self.analyse_function_implementation(
-                            &real_datatypes.clone(),
-                            &parameter_names.clone(),
-                            &parameter_qualifiers.clone(),
-                            &return_type.clone(),
-                            &body.clone()
+                            name,
+                            &real_datatypes,
+                            &parameter_names,
+                            &parameter_qualifiers,
+                            &return_type,
+                            &body
                         );
+                        self.implementations_in_progress.remove(&implementation_name);
                         let function = self.functions.get_mut(name).unwrap();
-                        let implementation_name = function.create_implementation(name.clone(), parameter_names, real_datatypes, parameter_qualifiers, return_type.clone(), body);
+                        function.complete_implementation(&implementation_name, return_type.clone(), body);
                         ASTNode::TYPED_NODE {
                             datatype: return_type,
                             qualifier: Qualifier::CONSTANT,
@@ -652,8 +1327,12 @@ impl BarracudaSemanticAnalyser {
                 for function in BARRACUDA_BUILT_IN_FUNCTIONS {
                     if name == &String::from(format!("__{}", function.to_string().to_lowercase())) {
                         if argument_datatypes == vec![DataType::PRIMITIVE(PrimitiveDataType::F64); function.consume() as usize] {
+                            let datatype = match function.produce() {
+                                0 => DataType::NONE,
+                                _ => DataType::PRIMITIVE(PrimitiveDataType::F64)
+                            };
                             return ASTNode::TYPED_NODE {
-                                datatype: DataType::PRIMITIVE(PrimitiveDataType::F64),
+                                datatype,
                                 qualifier: Qualifier::CONSTANT,
                                 inner: Box::new(ASTNode::FUNC_CALL {
                                     identifier: Box::new(ASTNode::IDENTIFIER(name.clone())),
@@ -670,16 +1349,313 @@ impl BarracudaSemanticAnalyser {
         }
     }
 
-    fn check_parameter_list(&self, parameters: &Vec<(String, (Option<DataType>, Qualifier))>, arguments: &Vec<(DataType, Qualifier)>, name: &String) -> Vec<DataType> {
+    /// Generates a constant lookup table by evaluating a built in function at compile time.
+    ///
+    /// # Syntax:
+    ///     lut(<builtin function>, <count>, <lo>, <hi>)
+    ///
+    /// The function is sampled at count evenly spaced points from lo to hi inclusive.
+    fn analyse_lookup_table(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        if arguments.len() != 4 {
+            panic!("lut expects 4 arguments (function, count, lo, hi) but got {}!", arguments.len())
+        }
+        let function = match &arguments[0] {
+            ASTNode::IDENTIFIER(name) => BARRACUDA_BUILT_IN_FUNCTIONS.iter()
+                .find(|function| name == &format!("__{}", function.to_string().to_lowercase()))
+                .unwrap_or_else(|| panic!("lut can only sample built in functions! ({} isn't built in)", name)),
+            _ => panic!("lut expects a built in function name as its first argument!")
+        };
+        let mut bounds = arguments[1..].iter().map(|argument| {
+            let argument = self.analyse_node(argument);
+            Self::constant_value(&argument).unwrap_or_else(|| panic!("lut arguments must be compile time constants!"))
+        });
+        let (count, lo, hi) = (bounds.next().unwrap(), bounds.next().unwrap(), bounds.next().unwrap());
+        if count < 1.0 || count.fract() != 0.0 {
+            panic!("lut count must be a positive integer! (Currently {})", count)
+        }
+
+        let count = count as usize;
+        let step = if count > 1 { (hi - lo) / (count - 1) as f64 } else { 0.0 };
+        let items = (0..count).map(|i| {
+            let x = lo + step * i as f64;
+            let value = evaluate_builtin_function(*function, &[x])
+                .unwrap_or_else(|| panic!("lut can't evaluate {:?} at compile time!", function));
+            // Non finite samples would be embedded silently, they usually mean the range is wrong
+            if !value.is_finite() {
+                panic!("lut sample of {:?} at {} isn't finite! ({})", function, x, value)
+            }
+            ASTNode::LITERAL(Literal::FLOAT(value))
+        }).collect();
+        self.analyse_array(&items, &Box::new(ASTNode::QUALIFIER(Qualifier::MUTABLE)))
+    }
+
+    /// len gives the character count of strings and the element count of arrays, both known at compile time.
+    fn analyse_length(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        if arguments.len() != 1 {
+            panic!("len expects 1 argument but got {}!", arguments.len())
+        }
+        let argument = self.analyse_node(&arguments[0]);
+        let length = match argument.get_type() {
+            DataType::ARRAY(inner_type, _) if matches!(*inner_type, DataType::PRIMITIVE(PrimitiveDataType::String)) => {
+                self.string_contents(&argument)
+                    .unwrap_or_else(|| panic!("Can only take the length of string literals or constant strings!"))
+                    .len()
+            }
+            DataType::ARRAY(_, size) => size,
+            datatype => panic!("Can only take the length of strings and arrays! (Found {:?})", datatype)
+        };
+        self.analyse_literal(&Literal::INTEGER(length as u64))
+    }
+
+    /// sizeof gives the byte width of a type, named by a primitive type keyword or struct name,
+    /// or of the type of an expression. The expression isn't evaluated.
+    fn analyse_sizeof(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        if arguments.len() != 1 {
+            panic!("sizeof expects 1 argument but got {}!", arguments.len())
+        }
+        let datatype = match &arguments[0] {
+            ASTNode::IDENTIFIER(name) if PrimitiveDataType::parse(name.clone()).is_some() => {
+                DataType::PRIMITIVE(PrimitiveDataType::parse(name.clone()).unwrap())
+            }
+            ASTNode::IDENTIFIER(name) if self.structs.contains_key(name) => self.structs[name].clone(),
+            argument => {
+                let datatype = self.analyse_node(argument).get_type();
+                self.resolve_datatype(&datatype)
+            }
+        };
+        self.analyse_literal(&Literal::INTEGER(datatype.size() as u64))
+    }
+
+    /// malloc allocates size bytes on the heap and returns a pointer to them.
+    /// Lowered to the __malloc built in so the result is typed as a pointer rather than a number.
+    fn analyse_malloc(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        if arguments.len() != 1 {
+            panic!("malloc expects 1 argument but got {}!", arguments.len())
+        }
+        let size = self.analyse_node(&arguments[0]);
+        if !matches!(size.get_type(), DataType::PRIMITIVE(_)) || size.get_type().is_bool() {
+            panic!("malloc size must be a number! Found {:?}", size.get_type())
+        }
+        ASTNode::TYPED_NODE {
+            datatype: DataType::HEAPPOINTER(Box::new(DataType::PRIMITIVE(PrimitiveDataType::F64))),
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::FUNC_CALL {
+                identifier: Box::new(ASTNode::IDENTIFIER(String::from("__malloc"))),
+                arguments: vec![size],
+            })
+        }
+    }
+
+    /// free releases heap memory previously returned by malloc. It doesn't produce a value.
+    fn analyse_free(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        if arguments.len() != 1 {
+            panic!("free expects 1 argument but got {}!", arguments.len())
+        }
+        let pointer = self.analyse_node(&arguments[0]);
+        if !matches!(pointer.get_type(), DataType::POINTER(_) | DataType::HEAPPOINTER(_)) {
+            panic!("free expects a pointer! Found {:?}", pointer.get_type())
+        }
+        ASTNode::TYPED_NODE {
+            datatype: DataType::NONE,
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::FUNC_CALL {
+                identifier: Box::new(ASTNode::IDENTIFIER(String::from("__free"))),
+                arguments: vec![pointer],
+            })
+        }
+    }
+
+    /// abs gives the magnitude of a number. Integers are held as whole floats so both use FABS.
+    fn analyse_abs(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        let arguments = self.analyse_numeric_arguments("abs", arguments, 1);
+        let datatype = arguments[0].get_type();
+        if let Some(value) = Self::constant_value(&arguments[0]) {
+            return self.analyse_number(value.abs(), datatype.is_integer());
+        }
+        Self::builtin_call("__fabs", arguments, datatype)
+    }
+
+    /// sign gives -1, 0 or 1 depending on the sign of a number.
+    /// Lowered to a SIGN unary operation so the argument is only evaluated once.
+    fn analyse_sign(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        let mut arguments = self.analyse_numeric_arguments("sign", arguments, 1);
+        if let Some(value) = Self::constant_value(&arguments[0]) {
+            let sign = if value > 0.0 { 1.0 } else if value < 0.0 { -1.0 } else { 0.0 };
+            return self.analyse_number(sign, true);
+        }
+        ASTNode::TYPED_NODE {
+            datatype: DataType::PRIMITIVE(PrimitiveDataType::I64),
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::UNARY_OP { op: UnaryOperation::SIGN, expression: Box::new(arguments.remove(0)) })
+        }
+    }
+
+    /// clamp(x, lo, hi) limits x to the range lo to hi, lowered to FMAX then FMIN.
+    fn analyse_clamp(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        let mut arguments = self.analyse_numeric_arguments("clamp", arguments, 3);
+        let datatype = arguments[0].get_type();
+        let integer = arguments.iter().all(|argument| argument.get_type().is_integer());
+        let (lo, hi) = (Self::constant_value(&arguments[1]), Self::constant_value(&arguments[2]));
+        if let (Some(lo), Some(hi)) = (lo, hi) {
+            if lo > hi {
+                panic!("clamp lower bound {} is greater than its upper bound {}!", lo, hi)
+            }
+            if let Some(value) = Self::constant_value(&arguments[0]) {
+                return self.analyse_number(value.max(lo).min(hi), integer);
+            }
+        }
+        let hi = arguments.pop().unwrap();
+        let lower_bounded = Self::builtin_call("__fmax", arguments, datatype.clone());
+        Self::builtin_call("__fmin", vec![lower_bounded, hi], datatype)
+    }
+
+    /// approx_eq(a, b, eps) is true when a and b differ by less than eps, lowered to |a - b| < eps.
+    /// This is only a helper for comparing floats, == still compares exactly.
+    fn analyse_approx_eq(&mut self, arguments: &Vec<ASTNode>) -> ASTNode {
+        let mut arguments = self.analyse_numeric_arguments("approx_eq", arguments, 3);
+        let constants: Option<Vec<f64>> = arguments.iter().map(Self::constant_value).collect();
+        if let Some(constants) = constants {
+            return self.analyse_literal(&Literal::BOOL((constants[0] - constants[1]).abs() < constants[2]));
+        }
+        let epsilon = arguments.pop().unwrap();
+        let datatype = DataType::PRIMITIVE(PrimitiveDataType::F64);
+        let difference = ASTNode::TYPED_NODE {
+            datatype: datatype.clone(),
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::BINARY_OP {
+                op: BinaryOperation::SUB,
+                lhs: Box::new(arguments.remove(0)),
+                rhs: Box::new(arguments.remove(0))
+            })
+        };
+        ASTNode::TYPED_NODE {
+            datatype: DataType::PRIMITIVE(PrimitiveDataType::Bool),
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::BINARY_OP {
+                op: BinaryOperation::LESS_THAN,
+                lhs: Box::new(Self::builtin_call("__fabs", vec![difference], datatype)),
+                rhs: Box::new(epsilon)
+            })
+        }
+    }
+
+    /// Analyses the arguments of a numeric helper, checking their count and that each is a number.
+    fn analyse_numeric_arguments(&mut self, name: &str, arguments: &Vec<ASTNode>, count: usize) -> Vec<ASTNode> {
+        if arguments.len() != count {
+            panic!("{} expects {} argument{} but got {}!", name, count, if count == 1 { "" } else { "s" }, arguments.len())
+        }
+        arguments.iter().map(|argument| {
+            let argument = self.analyse_node(argument);
+            if !argument.get_type().is_numeric() {
+                panic!("{} expects numbers! Found {:?}", name, argument.get_type())
+            }
+            argument
+        }).collect()
+    }
+
+    /// Typed literal for a number known at compile time. Negative numbers are negated literals.
+    fn analyse_number(&mut self, value: f64, integer: bool) -> ASTNode {
+        let literal = match integer {
+            true => self.analyse_literal(&Literal::INTEGER(value.abs() as u64)),
+            false => self.analyse_literal(&Literal::FLOAT(value.abs()))
+        };
+        if value >= 0.0 {
+            return literal;
+        }
+        ASTNode::TYPED_NODE {
+            datatype: literal.get_type(),
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::UNARY_OP { op: UnaryOperation::NEGATE, expression: Box::new(literal) })
+        }
+    }
+
+    fn builtin_call(name: &str, arguments: Vec<ASTNode>, datatype: DataType) -> ASTNode {
+        ASTNode::TYPED_NODE {
+            datatype,
+            qualifier: Qualifier::CONSTANT,
+            inner: Box::new(ASTNode::FUNC_CALL {
+                identifier: Box::new(ASTNode::IDENTIFIER(String::from(name))),
+                arguments,
+            })
+        }
+    }
+
+    /// Replaces array sizes named by compile time constants with their value and fills in the fields of structs.
+    /// Only constants initialised with literals are known at compile time so sizes can't name anything else.
+    fn resolve_datatype(&self, datatype: &DataType) -> DataType {
+        match datatype {
+            DataType::NAMEDARRAY(inner, name) => {
+                let size = match self.symbol_tracker.find_constant(name) {
+                    Some(Literal::FLOAT(value)) if *value >= 0.0 && value.fract() == 0.0 => *value as usize,
+                    Some(Literal::INTEGER(value)) => *value as usize,
+                    Some(literal) => panic!("Array size {} must be a non-negative integer! Found {:?}", name, literal),
+                    None => panic!("Array size {} isn't a compile time constant!", name)
+                };
+                DataType::ARRAY(Box::new(self.resolve_datatype(inner)), size)
+            }
+            DataType::ARRAY(inner, size) => DataType::ARRAY(Box::new(self.resolve_datatype(inner)), *size),
+            DataType::POINTER(inner) => DataType::POINTER(Box::new(self.resolve_datatype(inner))),
+            DataType::HEAPPOINTER(inner) => DataType::HEAPPOINTER(Box::new(self.resolve_datatype(inner))),
+            DataType::TUPLE(items) => DataType::TUPLE(items.iter().map(|item| self.resolve_datatype(item)).collect()),
+            DataType::STRUCT(name, _) => match self.structs.get(name) {
+                Some(datatype) => datatype.clone(),
+                None => panic!("Unknown datatype {}!", name)
+            },
+            other => other.clone()
+        }
+    }
+
+    /// Returns the value of a typed literal, or a negated typed literal.
+    fn constant_value(node: &ASTNode) -> Option<f64> {
+        match node {
+            ASTNode::TYPED_NODE { inner, .. } => match inner.as_ref() {
+                ASTNode::LITERAL(Literal::FLOAT(value)) => Some(*value),
+                ASTNode::LITERAL(Literal::INTEGER(value)) => Some(*value as f64),
+                ASTNode::UNARY_OP { op: UnaryOperation::NEGATE, expression } => Self::constant_value(expression).map(|value| -value),
+                ASTNode::UNARY_OP { op: UnaryOperation::PLUS, expression } => Self::constant_value(expression),
+                _ => None
+            }
+            _ => None
+        }
+    }
+
+    /// Expands a spread argument into one typed argument per array element.
+    /// Array literals are spread directly, array identifiers are spread into indexes of each element.
+    fn analyse_spread_argument(&mut self, expression: &Box<ASTNode>) -> Vec<ASTNode> {
+        let typed_expression = self.analyse_node(expression);
+        let size = match typed_expression.get_type() {
+            DataType::ARRAY(_, size) => size,
+            datatype => panic!("Can only spread arrays into function arguments! (spreading {:?})", datatype)
+        };
+        match (expression.as_ref(), typed_expression) {
+            (ASTNode::ARRAY { .. }, ASTNode::TYPED_NODE { inner, .. }) => match *inner {
+                ASTNode::ARRAY { items, .. } => items,
+                _ => panic!("Malformed AST! Array literal wasn't typed as an array!")
+            }
+            (ASTNode::IDENTIFIER(_), _) => {
+                (0..size).map(|index| self.analyse_array_index(
+                    &Box::new(ASTNode::LITERAL(Literal::INTEGER(index as u64))),
+                    expression
+                )).collect()
+            }
+            _ => panic!("Can only spread array identifiers or array literals into function arguments!")
+        }
+    }
+
+    fn check_parameter_list(&self, parameters: &Vec<(String, (Option<DataType>, Qualifier))>, required: usize, arguments: &Vec<(DataType, Qualifier)>, name: &String) -> Vec<DataType> {
         if parameters.len() != arguments.len() {
-            panic!("When calling function {}, need to use {} parameters! (Used {})", name, parameters.len(), arguments.len())
+            if required == parameters.len() {
+                panic!("When calling function {}, need to use {} parameters! (Used {})", name, parameters.len(), arguments.len())
+            }
+            panic!("When calling function {}, need to use {} to {} parameters! (Used {})", name, required, parameters.len(), arguments.len())
         }
         let mut real_types = vec![];
         for ((parameter_name, (parameter_datatype, parameter_qualifier)), (argument_datatype, argument_qualifier)) in parameters.iter().zip(arguments.iter()) {
-            let final_datatype = match parameter_datatype {
+            // Named array sizes are resolved before comparing as arguments always have a known size
+            let final_datatype = match parameter_datatype.as_ref().map(|datatype| self.resolve_datatype(datatype)) {
                 Some(parameter_datatype) => {
-                    if parameter_datatype == argument_datatype {
-                        parameter_datatype.clone()
+                    if &parameter_datatype == argument_datatype {
+                        parameter_datatype
                     } else {
                         panic!("Type of parameter {:?} in function {} didn't match! ({:?} vs {:?})", parameter_name, name, parameter_datatype, argument_datatype)
                     }
@@ -707,11 +1683,29 @@ impl BarracudaSemanticAnalyser {
         ASTNode::STATEMENT_LIST(new_statements)
     }
 
-    // Currently functions are the only use of scope blocks. If this changes, the method should have enter_scope and exit_scope calls added,
-    // and functions should bypass this function with a match statement. It was done this way to prevent functions causing two scopes from being created.
+    fn analyse_source_line(&mut self, line: usize, statement: &Box<ASTNode>) -> ASTNode {
+        // Errors are located at the innermost statement, so the enclosing line is restored afterwards
+        let (enclosing_line, _) = location();
+        set_location(line, 0);
+        let statement = Box::new(self.analyse_node(statement));
+        set_location(enclosing_line, 0);
+        ASTNode::SOURCE_LINE { line, statement }
+    }
+
+    // Scope blocks are the bodies of control flow statements and standalone blocks. Their symbols are only visible inside
+    // the block. Function bodies bypass this function as their scope is entered with the parameters.
     fn analyse_scope_block(&mut self, inner: &Box<ASTNode>) -> ASTNode {
         let scope = self.scope_counter.next().unwrap();
+        self.symbol_tracker.enter_scope();
         let inner = Box::new(self.analyse_node(inner));
+
+        // Returns inside the block return from the enclosing function
+        let returns = self.symbol_tracker.has_return_type();
+        let return_type = self.symbol_tracker.get_return_type().clone();
+        self.symbol_tracker.exit_scope();
+        if returns {
+            self.symbol_tracker.add_return_type(&return_type);
+        }
         ASTNode::SCOPE_BLOCK { inner, scope }
     }
 
@@ -724,16 +1718,38 @@ impl SemanticAnalyser for BarracudaSemanticAnalyser {
             symbol_tracker: ScopeTracker::new(),
             scope_counter: ScopeIdGenerator::new(),
             env_vars: HashMap::new(),
-            functions: HashMap::new()
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            referenced_identifiers: HashSet::new(),
+            implementations_in_progress: HashSet::new(),
+            max_function_count: 4096,
+            warnings: vec![],
+            warn_shadowing: false,
+            target_width: 64
         }
     }
 
     /// Parse processes a source string into an abstract syntax tree
-    fn analyse(mut self, root_node: ASTNode, env_vars: EnvironmentSymbolContext) -> AbstractSyntaxTree {
+    fn analyse(mut self, mut root_node: ASTNode, env_vars: EnvironmentSymbolContext) -> AbstractSyntaxTree {
         self.scope_counter.next();
+        self.find_referenced_identifiers(&mut root_node);
+        self.declare_functions(&mut root_node);
         self.env_vars = env_vars.copy_addresses();
         let root = self.analyse_node(&root_node);
         let functions = self.functions;
-        AbstractSyntaxTree::new(root, env_vars, functions)
+        AbstractSyntaxTree::new(root, env_vars, functions).with_warnings(self.warnings)
+    }
+
+    fn set_max_function_count(&mut self, max_function_count: usize) {
+        self.max_function_count = max_function_count;
+    }
+
+    fn set_warn_shadowing(&mut self, warn_shadowing: bool) {
+        self.warn_shadowing = warn_shadowing;
+    }
+
+    fn set_target_width(&mut self, target_width: usize) {
+        self.target_width = target_width;
     }
 }
\ No newline at end of file
@@ -6,6 +6,7 @@ pub struct FunctionTracker {
     parameter_names: Vec<String>,
     parameters: Vec<Option<DataType>>,
     parameter_qualifiers: Vec<Qualifier>,
+    parameter_defaults: Vec<Option<ASTNode>>,
     return_type: Option<DataType>,
     body: ASTNode,
     implementations: Vec<FunctionImplementation>,
@@ -23,9 +24,10 @@ impl FunctionTracker {
         let mut parameter_names = vec![];
         let mut parameter_types = vec![];
         let mut parameter_qualifiers = vec![];
+        let mut parameter_defaults = vec![];
         for parameter in parameters {
             match parameter {
-                ASTNode::PARAMETER { datatype, identifier, qualifier } => {
+                ASTNode::PARAMETER { datatype, identifier, qualifier, default } => {
                     let datatype = match datatype.as_ref() {
                         Some(datatype) => match datatype {
                             ASTNode::DATATYPE(datatype) => Some(datatype.clone()),
@@ -41,9 +43,20 @@ impl FunctionTracker {
                         ASTNode::QUALIFIER(qualifier) => qualifier.clone(),
                         _ => panic!("Malformed AST! Node {:?} should have been a qualifier but wasn't!", qualifier)
                     };
+                    // Defaults are compile time literals and must come after all required parameters
+                    match default.as_ref() {
+                        Some(default) if !Self::is_literal(default) => {
+                            panic!("Default value of parameter {} must be a literal! Found {:?}", identifier, default)
+                        }
+                        None if parameter_defaults.iter().any(|default: &Option<ASTNode>| default.is_some()) => {
+                            panic!("Required parameter {} can't follow a parameter with a default value!", identifier)
+                        }
+                        _ => {}
+                    }
                     parameter_names.push(identifier);
                     parameter_types.push(datatype);
                     parameter_qualifiers.push(qualifier);
+                    parameter_defaults.push(*default);
                 },
                 _ => panic!("Malformed AST! Parameter wasn't a parameter, instead it was {:?}", parameter)
             };
@@ -59,12 +72,21 @@ impl FunctionTracker {
             parameter_names,
             parameters: parameter_types,
             parameter_qualifiers,
+            parameter_defaults,
             return_type,
             body,
             implementations: Vec::new()
         }
     }
 
+    fn is_literal(node: &ASTNode) -> bool {
+        match node {
+            ASTNode::LITERAL(_) => true,
+            ASTNode::UNARY_OP { expression, .. } => Self::is_literal(expression),
+            _ => false
+        }
+    }
+
     pub fn match_function(&self, arguments: &Vec<(DataType, Qualifier)>) -> Option<(String, DataType)> {
         for implementation in &self.implementations {
             if implementation.matches_arguments(arguments) {
@@ -78,6 +100,15 @@ impl FunctionTracker {
         (&self.parameters, &self.parameter_names, &self.parameter_qualifiers, &self.return_type, &self.body)
     }
 
+    pub fn get_parameter_defaults(&self) -> &Vec<Option<ASTNode>> {
+        &self.parameter_defaults
+    }
+
+    /// Number of parameters that don't have a default value
+    pub fn required_parameter_count(&self) -> usize {
+        self.parameter_defaults.iter().filter(|default| default.is_none()).count()
+    }
+
     pub fn get_implementations(&self) -> &Vec<FunctionImplementation> {
         &self.implementations
     }
@@ -89,6 +120,15 @@ impl FunctionTracker {
         self.implementations.push(implementation);
         return implementation_name;
     }
+
+    /// Replaces the body and return type of an implementation once its body has been analysed
+    pub fn complete_implementation(&mut self, name: &String, return_type: DataType, body: ASTNode) {
+        let implementation = self.implementations.iter_mut()
+            .find(|implementation| &implementation.name == name)
+            .unwrap();
+        implementation.return_type = return_type;
+        implementation.body = body;
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -10,6 +10,15 @@ pub trait SemanticAnalyser {
 
     /// Analyse an Abstract Syntax Tree and annotate it with types.
     fn analyse(self, root_node: ASTNode, env_vars: EnvironmentSymbolContext) -> AbstractSyntaxTree;
+
+    /// Set the maximum number of functions a program can define.
+    fn set_max_function_count(&mut self, max_function_count: usize);
+
+    /// Set whether declarations shadowing a symbol of an enclosing scope produce a warning.
+    fn set_warn_shadowing(&mut self, warn_shadowing: bool);
+
+    /// Set the word size in bits of the VM the program targets.
+    fn set_target_width(&mut self, target_width: usize);
 }
 
 // Concrete Definition Export
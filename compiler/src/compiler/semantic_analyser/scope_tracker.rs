@@ -1,10 +1,14 @@
 use std::collections::HashMap;
 
-use crate::compiler::ast::{symbol_table::SymbolType, datatype::DataType};
+use crate::compiler::ast::{symbol_table::SymbolType, datatype::DataType, Literal};
 
 pub(crate) struct ScopeTracker {
     scopes: Vec<HashMap<String, SymbolType>>,
+    constants: Vec<HashMap<String, Literal>>,
+    strings: Vec<HashMap<String, String>>,
     return_types: Vec<Option<DataType>>,
+    // Index of the first scope of each function being analysed
+    function_scopes: Vec<usize>,
 }
 
 // A lightweight scope tracker made for semantic analysis.
@@ -13,17 +17,31 @@ pub(crate) struct ScopeTracker {
 // I'm not quite sure how to do that, though.
 impl ScopeTracker {
     pub fn new() -> Self {
-        ScopeTracker { scopes: vec![HashMap::new()], return_types: vec![] }
+        ScopeTracker { scopes: vec![HashMap::new()], constants: vec![HashMap::new()], strings: vec![HashMap::new()], return_types: vec![], function_scopes: vec![] }
     }
 
     pub fn enter_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.constants.push(HashMap::new());
+        self.strings.push(HashMap::new());
         self.return_types.push(None);
     }
 
+    /// Enters the outermost scope of a function body.
+    /// Functions are analysed where they're called so this marks where the caller's scopes end.
+    pub fn enter_function_scope(&mut self) {
+        self.enter_scope();
+        self.function_scopes.push(self.scopes.len() - 1);
+    }
+
     pub fn exit_scope(&mut self) {
+        if self.function_scopes.last() == Some(&(self.scopes.len() - 1)) {
+            self.function_scopes.pop();
+        }
         if self.scopes.len() > 1 {
             self.scopes.pop();
+            self.constants.pop();
+            self.strings.pop();
             self.return_types.pop();
         }
     }
@@ -48,6 +66,48 @@ impl ScopeTracker {
         None
     }
 
+    /// Checks whether an identifier declared in the current scope would shadow a symbol of an
+    /// enclosing scope. Inside a function only the global scope and the function's own scopes
+    /// enclose it, not the scopes of the caller.
+    pub fn shadows_symbol(&self, identifier: &String) -> bool {
+        let current_scope = self.scopes.len() - 1;
+        let function_scope = self.function_scopes.last().copied().unwrap_or(0);
+        self.scopes[..current_scope].iter().enumerate()
+            .any(|(index, scope)| (index == 0 || index >= function_scope) && scope.contains_key(identifier))
+    }
+
+    /// Records the known literal value of a constant symbol in the current scope.
+    /// The symbol should already have been added with add_symbol.
+    pub fn add_constant(&mut self, identifier: &String, literal: Literal) {
+        self.constants.last_mut().unwrap().insert(identifier.to_string(), literal);
+    }
+
+    /// Finds the literal value of a symbol if the nearest symbol with that identifier is a known constant.
+    pub fn find_constant(&self, identifier: &String) -> Option<&Literal> {
+        for (scope, constants) in self.scopes.iter().zip(self.constants.iter()).rev() {
+            if scope.contains_key(identifier) {
+                return constants.get(identifier)
+            }
+        }
+        None
+    }
+
+    /// Records the contents of a constant string symbol in the current scope.
+    /// The symbol should already have been added with add_symbol.
+    pub fn add_string(&mut self, identifier: &String, contents: String) {
+        self.strings.last_mut().unwrap().insert(identifier.to_string(), contents);
+    }
+
+    /// Finds the contents of a symbol if the nearest symbol with that identifier is a constant string.
+    pub fn find_string(&self, identifier: &String) -> Option<&String> {
+        for (scope, strings) in self.scopes.iter().zip(self.strings.iter()).rev() {
+            if scope.contains_key(identifier) {
+                return strings.get(identifier)
+            }
+        }
+        None
+    }
+
     pub fn add_return_type(&mut self, datatype: &DataType) {
         let new_type = match self.return_types.last().unwrap() {
             Some(return_type) => {
@@ -63,6 +123,11 @@ impl ScopeTracker {
         self.return_types[x] = Some(new_type);
     }
 
+    /// Whether the current scope contains a return statement, including returns without a value
+    pub fn has_return_type(&self) -> bool {
+        self.return_types.last().unwrap().is_some()
+    }
+
     pub fn get_return_type(&self) -> &DataType {
         match self.return_types.last().unwrap() {
             Some(datatype) => datatype,
@@ -90,3 +90,18 @@ pub fn pack_string_to_f64_array(input: &str, precision: usize) -> Vec<f64> {
 
     result
 }
+
+/// Reverses pack_string_to_f64_array for 64 bit packing.
+/// The final chunk is padded with leading zero bytes which are dropped.
+pub fn unpack_f64_array_to_string(input: &[f64]) -> String {
+    let mut bytes = Vec::new();
+    for (index, value) in input.iter().enumerate() {
+        let chunk: Vec<u8> = value.to_bits().to_le_bytes().to_vec();
+        if index == input.len() - 1 {
+            bytes.extend(chunk.into_iter().skip_while(|&byte| byte == 0));
+        } else {
+            bytes.extend(chunk);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
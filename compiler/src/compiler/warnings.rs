@@ -0,0 +1,59 @@
+use std::fmt;
+
+/// CompilerWarning describes a suspicious but valid construct found during compilation.
+/// Warnings don't stop compilation, they are attached to the generated ProgramCode and
+/// reported to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompilerWarning {
+    /// A loop whose condition is always false, so its body never executes.
+    /// Holds the kind of loop, e.g. "while" or "for".
+    DeadLoop(String),
+
+    /// Literal values that can't be represented exactly at the output precision.
+    PrecisionLoss {
+        rounded_values: usize,
+        precision: usize
+    },
+
+    /// An integer literal too large to be an exact float outside of an i64 typed context.
+    /// Holds the literal and the float it's rounded to.
+    IntegerPrecisionLoss(u64, f64),
+
+    /// An integer literal initialising a float typed declaration too narrow to hold it exactly.
+    /// Holds the identifier and the literal.
+    IntegerWidening(String, f64),
+
+    /// A declaration that shadows a symbol of an enclosing scope. Only found when enabled.
+    /// Holds the identifier and the line it's declared on.
+    Shadowing(String, usize),
+
+    /// A function with a declared return type that can reach the end of its body without returning.
+    /// Holds the function name.
+    MissingReturn(String),
+}
+
+impl fmt::Display for CompilerWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompilerWarning::DeadLoop(kind) => {
+                write!(f, "{} loop condition is always false so its body never executes", kind)
+            }
+            CompilerWarning::PrecisionLoss { rounded_values, precision } => {
+                write!(f, "{} values lose precision when rounded to f{}", rounded_values, precision)
+            }
+            CompilerWarning::IntegerPrecisionLoss(literal, rounded) => {
+                write!(f, "integer literal {} is larger than 2^53 so loses precision as a float, it will be rounded to {}. \
+                    Declare it as i64 to keep its exact value", literal, rounded)
+            }
+            CompilerWarning::IntegerWidening(identifier, literal) => {
+                write!(f, "integer literal {} can't be held exactly by the float type of {} so is rounded when widened", literal, identifier)
+            }
+            CompilerWarning::Shadowing(identifier, line) => {
+                write!(f, "declaration of {} on line {} shadows a declaration in an enclosing scope", identifier, line)
+            }
+            CompilerWarning::MissingReturn(function) => {
+                write!(f, "function {} has a return type but can reach the end of its body without returning", function)
+            }
+        }
+    }
+}
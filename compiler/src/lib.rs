@@ -9,6 +9,7 @@ extern crate barracuda_common;
 use safer_ffi::prelude::*;
 
 use compiler::{Compiler, EnvironmentSymbolContext, PrimitiveDataType, Qualifier};
+use compiler::backend::builtin_functions::builtin_function_signatures;
 //use crate::compiler::utils::pack_string_to_f64_array;
 
 // Internal Modules
@@ -20,8 +21,8 @@ type ANALYSER = compiler::BarracudaSemanticAnalyser;
 type GENERATOR = compiler::BarracudaByteCodeGenerator;
 
 
-/// Compiler response describes a successful compilation result
-/// It contains the relevant vectors required to run the program
+/// Compiler response describes the result of a compilation
+/// On success it contains the relevant vectors required to run the program
 /// code on the barracuda virtual machine.
 #[derive_ReprC]
 #[repr(C)]
@@ -34,11 +35,19 @@ pub struct CompilerResponse {
     instructions_list: repr_c::Vec<u32>,
 
     /// Operations list describes the operation to run during a OP instruction.
+    /// Empty when compiling for a 32 bit target, see operations_list_32.
     operations_list: repr_c::Vec<u64>,
 
     /// Value list describes the value to load during a VALUE instruction.
+    /// Empty when compiling for a 32 bit target, see values_list_32.
     values_list: repr_c::Vec<f64>,
 
+    /// Operations list packed as u32 for 32 bit targets. Empty for 64 bit targets.
+    operations_list_32: repr_c::Vec<u32>,
+
+    /// Value list as f32 for 32 bit targets. Empty for 64 bit targets.
+    values_list_32: repr_c::Vec<f32>,
+
     /// Recommended stack size is an auto generated estimate for the stack size required
     /// to execute the program code. This will give the exact min required size if analysis
     /// goes okay otherwise it will use a default large size.
@@ -49,7 +58,54 @@ pub struct CompilerResponse {
     user_space_size: repr_c::Vec<u64>,
 
     /// User space is a vector of f64 values that are used to store user defined variables.
+    /// Empty when compiling for a 32 bit target, see user_space_32.
     user_space: repr_c::Vec<f64>,
+
+    /// User space as f32 for 32 bit targets. Empty for 64 bit targets.
+    user_space_32: repr_c::Vec<f32>,
+
+    /// Success is false if the request couldn't be compiled, in which case every list is empty.
+    success: bool,
+
+    /// Diagnostics lists the error that stopped compilation. Empty on success.
+    diagnostics: repr_c::Vec<Diagnostic>,
+}
+
+/// Diagnostic describes an error found when checking or compiling a program.
+#[derive_ReprC]
+#[repr(C)]
+pub struct Diagnostic {
+    /// Message is a null-terminated string describing the error.
+    message: char_p::Box,
+
+    /// Line of the source the error was found on, starting at 1. 0 if the location is unknown.
+    line: usize,
+
+    /// Column of the source the error was found on, starting at 1. 0 if the location is unknown
+    /// or the error applies to the whole line.
+    column: usize,
+}
+
+/// Check response describes the result of checking a program for errors without compiling it.
+#[derive_ReprC]
+#[repr(C)]
+pub struct CheckResponse {
+    /// Success is true if the program has no errors.
+    success: bool,
+
+    /// Diagnostics lists the errors found in the program.
+    diagnostics: repr_c::Vec<Diagnostic>,
+}
+
+/// BuiltinFunction describes a built in function that can be called from source.
+#[derive_ReprC]
+#[repr(C)]
+pub struct BuiltinFunction {
+    /// Name is a null-terminated string of the name used to call the function, e.g. __sin.
+    name: char_p::Box,
+
+    /// Argument count is the number of arguments the function takes.
+    argument_count: i8,
 }
 
 /// EnvironmentVariable describes an environment variable the program will have access to in the
@@ -86,7 +142,83 @@ pub struct CompilerRequest {
     env_vars: repr_c::Vec<EnvironmentVariable>,
 
     /// Numerical precision is floating point bit-precision to use for the program. (default: 32)
+    /// Must be 8, 16, 32, 64 or 128, or 0 for the default. Other values are an error, reported
+    /// as a diagnostic by check and compile.
     precision: usize,
+
+    /// Max stack size override replaces the estimated recommended stack size when non-zero.
+    /// Use 0 to keep the estimate. The estimate of recursive programs includes a conservative
+    /// default margin as their depth can't be known; an override replaces that margin too, so
+    /// hosts running recursive code are responsible for choosing a large enough size.
+    max_stack_size_override: usize,
+
+    /// Quantise literals rounds numeric literals to the program precision before they are
+    /// emitted, so values_list holds exactly what a VM of that precision will store.
+    quantise_literals: bool,
+
+    /// Defines are names enabling #if blocks for conditional compilation, as if each was
+    /// declared with #define at the start of the code.
+    defines: repr_c::Vec<char_p::Box>,
+
+    /// Target width is the word size of the VM the program will run on. (default: 64)
+    /// 64 (or 0) gives u32 instructions, u64 operations and f64 values and user space.
+    /// 32 gives u32 instructions, u32 operations and f32 values and user space, which are
+    /// returned in the *_32 fields of the response instead. Strings are packed 8 characters to
+    /// a 64 bit value so string literals are an error on 32 bit targets. Other values are an
+    /// error, reported as a diagnostic by check and compile.
+    target_width: usize,
+}
+
+// Private
+fn generate_defines(request: &CompilerRequest) -> Vec<String> {
+    request.defines.iter().map(|define| String::from(define.to_str())).collect()
+}
+
+// Private
+fn validate_precision(request: &CompilerRequest) -> Result<usize, String> {
+    match request.precision {
+        0 => Ok(32),
+        8 | 16 | 32 | 64 | 128 => Ok(request.precision),
+        precision => Err(format!("Unsupported precision {}! Use 8, 16, 32, 64 or 128.", precision))
+    }
+}
+
+// Private
+fn validate_target_width(request: &CompilerRequest) -> Result<usize, String> {
+    match request.target_width {
+        0 => Ok(64),
+        32 | 64 => Ok(request.target_width),
+        target_width => Err(format!("Unsupported target width {}! Use 32 or 64.", target_width))
+    }
+}
+
+// Private
+// Pointers are encoded as the bits of a usize in an f64, making them subnormal. These would
+// round to zero as an f32 so they keep their bits instead.
+fn value_to_f32(value: f64) -> f32 {
+    if value.is_subnormal() {
+        f32::from_bits(value.to_bits() as u32)
+    } else {
+        value as f32
+    }
+}
+
+// Private
+fn failed_compile_response(message: String, line: usize, column: usize) -> CompilerResponse {
+    CompilerResponse {
+        code_text: String::new().try_into().unwrap(),
+        instructions_list: vec![].into(),
+        operations_list: vec![].into(),
+        values_list: vec![].into(),
+        operations_list_32: vec![].into(),
+        values_list_32: vec![].into(),
+        recommended_stack_size: 0,
+        user_space_size: vec![].into(),
+        user_space: vec![].into(),
+        user_space_32: vec![].into(),
+        success: false,
+        diagnostics: vec![Diagnostic { message: message.try_into().unwrap(), line, column }].into(),
+    }
 }
 
 // Private
@@ -110,23 +242,39 @@ fn generate_environment_context(request: &CompilerRequest) -> EnvironmentSymbolC
 /// low level instructions. The memory for the compiler response
 /// is allocated on call, it is then the responsibility of the caller to
 /// free this memory via free_compile_response.
+/// Invalid requests and programs give a response with success set to false and a diagnostic
+/// describing the error.
 #[ffi_export]
 pub fn compile(request: &CompilerRequest) -> CompilerResponse {
+    let precision = match validate_precision(request) {
+        Ok(precision) => precision,
+        Err(message) => return failed_compile_response(message, 0, 0)
+    };
+    let target_width = match validate_target_width(request) {
+        Ok(target_width) => target_width,
+        Err(message) => return failed_compile_response(message, 0, 0)
+    };
     let env_vars = generate_environment_context(&request);
 
     let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default()
         .set_environment_variables(env_vars.clone()).set_environment_variable_count(request.env_vars.len())
-        .set_precision(request.precision);
+        .set_precision(precision)
+        .set_target_width(target_width)
+        .set_quantise_literals(request.quantise_literals)
+        .set_defines(generate_defines(request));
 
     //compiler.set_environment_variable_count(request.env_vars.len());
-    let program_code = compiler.compile_str(request.code_text.to_str());
+    let program_code = match compiler.try_compile_str(request.code_text.to_str()) {
+        Ok(program_code) => program_code,
+        Err(diagnostic) => return failed_compile_response(diagnostic.message, diagnostic.line, diagnostic.column)
+    };
     let compiled_text = program_code.to_string();
 
     // Convert program code components into primitives
     let instructions: Vec<u32> = program_code.instructions.into_iter().rev()
                                     .map(|instr| instr.as_u32()).collect();
-    let operations: Vec<u64> = program_code.operations.into_iter().rev()
-                                    .map(|op| op.as_u32() as u64).collect();
+    let operations: Vec<u32> = program_code.operations.into_iter().rev()
+                                    .map(|op| op.as_u32()).collect();
     let values: Vec<f64> = program_code.values.into_iter().rev()
                                     .map(|value| value as f64).collect();
 
@@ -139,17 +287,42 @@ pub fn compile(request: &CompilerRequest) -> CompilerResponse {
     let const_user_space: Vec<f64> = program_code.constant_user_space.into_iter().map(|value| value as f64).collect();
     let user_space: Vec<f64> = mut_user_space.iter().chain(const_user_space.iter()).copied().collect();
 
+    // Only the lists matching the target width are filled
+    let (operations, operations_32, values, values_32, user_space, user_space_32) = match target_width {
+        64 => (operations.into_iter().map(|op| op as u64).collect(), vec![], values, vec![], user_space, vec![]),
+        _ => (
+            vec![], operations, vec![], values.into_iter().map(value_to_f32).collect(),
+            vec![], user_space.into_iter().map(value_to_f32).collect()
+        )
+    };
+
+    // Environment variables are counted in the user space region matching their qualifier
     let mut user_space_size: Vec<u64> = program_code.user_space_size;
-    user_space_size[0] += env_vars.copy_addresses().len() as u64; // TODO: Make const environment add to constant size whereas mutable adds to mutable size (once const/mut env vars are implemented)
+    for (_, (_, _, qualifier, _)) in env_vars.copy_addresses() {
+        match qualifier {
+            Qualifier::MUTABLE => user_space_size[0] += 1,
+            Qualifier::CONSTANT => user_space_size[1] += 1,
+        }
+    }
+
+    let recommended_stack_size = match request.max_stack_size_override {
+        0 => program_code.max_stack_size,
+        max_stack_size_override => max_stack_size_override
+    };
 
     CompilerResponse {
         code_text: compiled_text.try_into().unwrap(),
         instructions_list: repr_c::Vec::try_from(instructions).unwrap(),
         operations_list: repr_c::Vec::try_from(operations).unwrap(),
         values_list: repr_c::Vec::try_from(values).unwrap(),
-        recommended_stack_size: program_code.max_stack_size,
+        operations_list_32: repr_c::Vec::try_from(operations_32).unwrap(),
+        values_list_32: repr_c::Vec::try_from(values_32).unwrap(),
+        recommended_stack_size,
         user_space_size: repr_c::Vec::try_from(user_space_size).unwrap(),
-        user_space: repr_c::Vec::try_from(user_space).unwrap()
+        user_space: repr_c::Vec::try_from(user_space).unwrap(),
+        user_space_32: repr_c::Vec::try_from(user_space_32).unwrap(),
+        success: true,
+        diagnostics: vec![].into(),
     }
 }
 
@@ -162,6 +335,79 @@ pub fn free_compile_response(response: CompilerResponse) {
     drop(response.instructions_list);
     drop(response.operations_list);
     drop(response.values_list);
+    drop(response.operations_list_32);
+    drop(response.values_list_32);
+    drop(response.diagnostics);
+}
+
+
+/// Check parses and analyses a CompilerRequest without generating code, returning the errors
+/// found. This is quicker than compiling so suits editor integrations. The memory for the
+/// check response is allocated on call, it is then the responsibility of the caller to free
+/// this memory via free_check_response.
+#[ffi_export]
+pub fn check(request: &CompilerRequest) -> CheckResponse {
+    let precision = match validate_precision(request) {
+        Ok(precision) => precision,
+        Err(message) => {
+            let diagnostic = Diagnostic { message: message.try_into().unwrap(), line: 0, column: 0 };
+            return CheckResponse { success: false, diagnostics: vec![diagnostic].into() }
+        }
+    };
+    let target_width = match validate_target_width(request) {
+        Ok(target_width) => target_width,
+        Err(message) => {
+            let diagnostic = Diagnostic { message: message.try_into().unwrap(), line: 0, column: 0 };
+            return CheckResponse { success: false, diagnostics: vec![diagnostic].into() }
+        }
+    };
+    let env_vars = generate_environment_context(request);
+
+    let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default()
+        .set_environment_variables(env_vars)
+        .set_precision(precision)
+        .set_target_width(target_width)
+        .set_defines(generate_defines(request));
+
+    let diagnostics: Vec<Diagnostic> = compiler.check_str(request.code_text.to_str()).into_iter()
+        .map(|diagnostic| Diagnostic {
+            message: diagnostic.message.try_into().unwrap(),
+            line: diagnostic.line,
+            column: diagnostic.column,
+        }).collect();
+
+    CheckResponse {
+        success: diagnostics.is_empty(),
+        diagnostics: diagnostics.into(),
+    }
+}
+
+/// Frees a check response returned via the API
+/// Calling the function is a requirement after using a response.
+#[ffi_export]
+pub fn free_check_response(response: CheckResponse) {
+    drop(response.diagnostics);
+}
+
+/// Lists the built in functions available to programs along with their argument counts,
+/// with names in the __name form used in source.
+/// The memory for the list is allocated on call, it is then the responsibility of the caller
+/// to free this memory via free_builtin_functions.
+#[ffi_export]
+pub fn builtin_functions() -> repr_c::Vec<BuiltinFunction> {
+    builtin_function_signatures().into_iter()
+        .map(|(name, argument_count)| BuiltinFunction {
+            name: name.try_into().unwrap(),
+            argument_count,
+        })
+        .collect::<Vec<BuiltinFunction>>()
+        .into()
+}
+
+/// Frees a built in function list returned via the API
+#[ffi_export]
+pub fn free_builtin_functions(functions: repr_c::Vec<BuiltinFunction>) {
+    drop(functions);
 }
 
 
@@ -182,9 +428,11 @@ mod tests {
 
     use barracuda_common::BarracudaInstructions;
     use barracuda_common::BarracudaOperators;
+    use barracuda_common::SymbolLocation;
     use barracuda_common::BarracudaInstructions::*;
     use barracuda_common::BarracudaOperators::*;
     use barracuda_common::FixedBarracudaOperators::*;
+    use crate::compiler::backend::builtin_functions::BARRACUDA_BUILT_IN_FUNCTIONS;
 
     use super::*;
     
@@ -209,6 +457,11 @@ mod tests {
     fn compile_and_merge_with_env_vars(text: &str, env_vars: EnvironmentSymbolContext) -> Vec<MergedInstructions> {
         let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default()
             .set_environment_variables(env_vars);
+        compile_and_merge_with_compiler(text, compiler)
+    }
+
+    // Compiles a program string with a configured compiler and converts the result to merged instructions.
+    fn compile_and_merge_with_compiler(text: &str, compiler: Compiler<PARSER, ANALYSER, GENERATOR>) -> Vec<MergedInstructions> {
         let code = compiler.compile_str(text);
         assert!(code.values.len() == code.operations.len() && code.values.len() == code.instructions.len());
         let mut out: Vec<MergedInstructions> = vec![];
@@ -274,6 +527,12 @@ mod tests {
             ("1.0e0", 1.0),
             ("1.0e+0", 1.0),
             ("1.0e-0", 1.0),
+            (".5", 0.5),
+            (".5e3", 500.0),
+            (".25E-1", 0.025),
+            ("5e3", 5000.0),
+            ("5E3", 5000.0),
+            ("5e+3", 5000.0),
             ("1.7976931348623157e308", f64::MAX), // Maximum float
             ("2.2250738585072014e-308", f64::MIN_POSITIVE), // Minimum positive float
             // Booleans
@@ -281,11 +540,57 @@ mod tests {
             ("true", 1.0),
         ];
         for (text, value) in &literals {
-            let stack = compile_and_merge(&format!("let a = {};", text));
+            let stack = compile_and_merge(&format!("let mut a = {};", text));
             assert_eq!(vec![Val(*value)], stack);
         }
     }
 
+    // Integer literals up to 2^53 are exact floats, larger ones are rounded with a warning.
+    #[test]
+    fn large_integer_literals() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let mut a = 9007199254740992;");
+        assert!(code.warnings.is_empty());
+        assert_eq!(vec![Val(9007199254740992.0)], compile_and_merge("let mut a = 9007199254740992;"));
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let mut a = 9007199254740993;");
+        assert_eq!(vec![
+            "integer literal 9007199254740993 is larger than 2^53 so loses precision as a float, it will be rounded to 9007199254740992. \
+                Declare it as i64 to keep its exact value".to_string()
+        ], code.warnings);
+        assert_eq!(vec![Val(9007199254740992.0)], compile_and_merge("let mut a = 9007199254740993;"));
+    }
+
+    // Integer literals declared as i64 keep their exact bit pattern.
+    #[test]
+    fn large_integer_literal_i64() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(64);
+        let stack = compile_and_merge_with_compiler("let mut a: i64 = 9223372036854775807;", compiler);
+        // The bit pattern of i64::MAX is a NaN so the bits are compared
+        match stack[..] {
+            [Val(value)] => assert_eq!(9223372036854775807, value.to_bits()),
+            _ => panic!("Expected a single value, got {:?}", stack)
+        }
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(64);
+        let code = compiler.compile_str("let mut a: i64 = 9007199254740993;");
+        assert!(code.warnings.is_empty());
+    }
+
+    // Bit patterns are only exact when values are emitted at 64 bit precision.
+    #[test]
+    #[should_panic(expected = "needs 64 bit precision to keep its exact value!")]
+    fn large_integer_literal_i64_precision() {
+        compile_and_merge("let mut a: i64 = 9007199254740993;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Integer literal 18446744073709551616 is too large!")]
+    fn integer_literal_too_large() {
+        compile_and_merge("let mut a = 18446744073709551616;");
+    }
+
     // Tests that all binary operators compile properly.
     // These are operators in the form a OP b.
     #[test]
@@ -303,10 +608,6 @@ mod tests {
             ("<=", LTEQ),
             (">", GT),
             ("<", LT),
-            ("&&", AND),
-            ("and", AND),
-            ("||", OR),
-            ("or", OR),
             ("<<", LSHIFT),
             (">>", RSHIFT),
         ];
@@ -323,32 +624,155 @@ mod tests {
         assert_eq!(vec![Val(1.0), Val(2.0), Val(3.0), Op(FIXED(TERNARY))], stack);
     }
 
+    // Tests that chained ternaries group from the right, as if the later ternaries were parenthesised.
+    #[test]
+    fn ternary_operator_chaining() {
+        compile_and_assert_equal("let a = false ? 1 : true ? 2 : 3;", "let a = false ? 1 : (true ? 2 : 3);");
+        compile_and_assert_equal("let a = true ? false ? 1 : 2 : 3;", "let a = true ? (false ? 1 : 2) : 3;");
+        compile_and_assert_equal("let x = 2; let a = x < 1 ? 1 : x < 2 ? 2 : x < 3 ? 3 : 4;",
+                                 "let x = 2; let a = x < 1 ? 1 : (x < 2 ? 2 : (x < 3 ? 3 : 4));");
+        let stack = compile_and_merge("let a = false ? 1 : true ? 2 : 3;");
+        assert_eq!(vec![Val(0.0), Val(1.0), Val(1.0), Val(2.0), Val(3.0), Op(FIXED(TERNARY)), Op(FIXED(TERNARY))], stack);
+    }
+
+    #[test]
+    #[should_panic(expected = "Ternary conditions must be booleans! (currently POINTER(PRIMITIVE(F64)))")]
+    fn ternary_operator_chaining_condition() {
+        compile_and_merge("let mut b = 1; let a = false ? 1 : &b ? 3 : 4;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Branches of ternary operator must be the same type! (PRIMITIVE(F64) vs POINTER(PRIMITIVE(F64)))")]
+    fn ternary_operator_chaining_branch_types() {
+        compile_and_merge("let mut b = 1; let a = false ? 1 : true ? 2 : &b;");
+    }
+
+    // Tests that the ternary operator selects between pointers, including pointers to arrays.
+    #[test]
+    fn ternary_operator_pointers() {
+        let reference = |position| vec![Val(ptr(position)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))];
+        let stack = compile_and_merge("let mut a = 1; let mut b = 2; let mut c = true ? &a : &b;");
+        assert_eq!([vec![Val(1.0)], reference(1), reference(2), vec![Op(FIXED(TERNARY))]].concat(), stack[2..]);
+
+        compile_and_merge("let mut a = [1, 2]; let mut b = [3, 4]; let c = true ? &a : &b; let mut d = (*c)[0];");
+    }
+
+    #[test]
+    #[should_panic(expected = "Branches of ternary operator must be single values, not ARRAY(PRIMITIVE(F64), 2)! Select pointers to them instead.")]
+    fn ternary_operator_arrays() {
+        compile_and_merge("let mut a = [1, 2]; let mut b = [3, 4]; let c = true ? a : b;");
+    }
+
+    // Tests that logical operators compile properly on boolean operands.
+    #[test]
+    fn logical_operators() {
+        let logical_operators = vec![
+            ("&&", AND),
+            ("and", AND),
+            ("||", OR),
+            ("or", OR),
+        ];
+        for (text, op) in &logical_operators {
+            let stack = compile_and_merge(&format!("let a = true{}false;", text));
+            assert_eq!(vec![Val(1.0), Val(0.0), Op(FIXED(*op))], stack);
+        }
+        let stack = compile_and_merge("let a = (4 < 5) && (5 > 4);");
+        assert_eq!(vec![Val(4.0), Val(5.0), Op(FIXED(LT)), Val(5.0), Val(4.0), Op(FIXED(GT)), Op(FIXED(AND))], stack);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot use operation AND on non boolean types!")]
+    fn logical_operator_non_boolean() {
+        compile_and_merge("let a = 3 && 5;");
+    }
+
+    // Tests that non boolean values stored as booleans are normalised by comparing with 0.
+    // Comparisons already produce exactly 1 or 0 so they're stored as is.
+    #[test]
+    fn boolean_normalisation() {
+        let stack = compile_and_merge("let a: bool = 1 < 2;");
+        assert_eq!(vec![Val(1.0), Val(2.0), Op(FIXED(LT))], stack);
+        compile_and_assert_equal("let mut a = true;", "let mut a: bool = 5;");
+        let stack = compile_and_merge("let mut a = 5; let mut b: bool = a;");
+        assert_eq!(vec![
+            Val(5.0), Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ)), Val(0.0), Op(FIXED(NEQ))
+        ], stack);
+
+        // Assignments to booleans and arguments to bool parameters are normalised too
+        compile_and_assert_equal("let mut b = false; b = true;", "let mut b = false; b = 5;");
+        compile_and_assert_equal("let mut b: bool = false; let mut a = 5; b = a != 0;", "let mut b: bool = false; let mut a = 5; b = a;");
+        compile_and_assert_equal("fn f(x: bool) { if x { print(1); } } f(true);", "fn f(x: bool) { if x { print(1); } } f(5);");
+        compile_and_assert_equal("fn f(x: bool) { if x { print(1); } } let mut a = 5; f(a != 0);", "fn f(x: bool) { if x { print(1); } } let mut a = 5; f(a);");
+        compile_and_assert_equal("fn f(x) { print(x); } f(5.0);", "fn f(x) { print(x); } f(5);");
+    }
+
+    // Booleans are ordered by their value of 1 or 0, so false < true
+    #[test]
+    fn boolean_comparison() {
+        let stack = compile_and_merge("let mut a = true > false;");
+        assert_eq!(vec![Val(1.0), Val(0.0), Op(FIXED(GT))], stack);
+        let stack = compile_and_merge("let mut a = true; let mut b = a <= false;");
+        assert_eq!(vec![Val(1.0)], stack[..1]);
+        assert_eq!(generate_variable_call(1), stack[1..6]);
+        assert_eq!(vec![Val(0.0), Op(FIXED(LTEQ))], stack[6..]);
+    }
+
+    // Characters read from strings are compared by their character codes
+    #[test]
+    fn character_comparison() {
+        let stack = compile_and_merge(r#"let const s = "ab"; let mut a = s[0] < s[1];"#);
+        assert_eq!(vec![Val(97.0), Val(98.0), Op(FIXED(LT))], stack[1..]);
+        compile_and_assert_equal(r#"let mut a = "b"[0] >= 97;"#, "let mut a = 98 >= 97;");
+    }
+
+    // Booleans read through pointers into host memory are normalised before being compared
+    #[test]
+    fn external_boolean_pointer_comparison() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::Bool, Qualifier::CONSTANT, "*".to_string());
+        let stack = compile_and_merge_with_env_vars("extern a; let p = &a; let mut b = *p > false;", env_vars);
+        assert_eq!(generate_variable_call(1), stack[2..7]);
+        assert_eq!(vec![Op(FIXED(READ_F64)), Val(0.0), Op(FIXED(NEQ)), Val(0.0), Op(FIXED(GT))], stack[7..]);
+    }
+
     // Tests that all unary operators compile properly.
     // These are operators in the form OP a.
     #[test]
     fn unary_operators() {
-        let unary_operators = vec![
-            ("!", NOT),
-            ("-", NEGATE),        
-        ];
-        for (text, op) in &unary_operators {
-            let stack = compile_and_merge(&format!("let a = {}4;", text));
-            assert_eq!(vec![Val(4.0), Op(FIXED(*op))], stack);
-        }
+        let stack = compile_and_merge("let a = -4;");
+        assert_eq!(vec![Val(4.0), Op(FIXED(NEGATE))], stack);
+        let stack = compile_and_merge("let a = !true;");
+        assert_eq!(vec![Val(1.0), Op(FIXED(NOT))], stack);
+        let stack = compile_and_merge("let a = +4;");
+        assert_eq!(vec![Val(4.0)], stack);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot use operation PLUS on non numeric type")]
+    fn plus_non_numeric() {
+        compile_and_merge("let a = +true;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot use operation NOT on non boolean type")]
+    fn not_non_boolean() {
+        compile_and_merge("let a = !4;");
     }
 
     // Tests that whitespace and comments are ignored as expected.
-    // The statement 'let a = true;' has no signigicance in the below tests.
+    // The statement 'let mut a = true;' has no signigicance in the below tests.
     // It's just there to make sure whitespace and comments are ignored correctly.
     #[test]
     fn whitespace_and_comments_ignored() {
         let test_cases = vec![
-            "     let a = true    ;    ",
-            "\nlet a = true\n;\n", 
-            "\tlet a = true\t;\t", 
-            "\rlet a = true\r;\r",
-            "//comment\nlet a = true;//comment\n//comment",
-            "/*multiline\ncomment*/let a = true;/*multiline comment*//*multiline\ncomment*/",
+            "     let mut a = true    ;    ",
+            "\nlet mut a = true\n;\n", 
+            "\tlet mut a = true\t;\t", 
+            "\rlet mut a = true\r;\r",
+            "//comment\nlet mut a = true;//comment\n//comment",
+            "/*multiline\ncomment*/let mut a = true;/*multiline comment*//*multiline\ncomment*/",
+            "/* outer /* inner */ still comment */let mut a = true;",
+            "/*/**/*//* a /* b /* c */ */ */let mut a = true;// /* unclosed in line comment",
         ];
 
         for test_case in &test_cases {
@@ -357,6 +781,102 @@ mod tests {
         }
     }
 
+    // Tests that stray semicolons are empty statements which don't change the program
+    #[test]
+    fn empty_statements() {
+        compile_and_assert_equal("let a = 1;;;", "let a = 1;");
+        compile_and_assert_equal(";fn f() { ; print(1);; } f();;", "fn f() { print(1); } f();");
+        compile_and_assert_equal("let mut a = 1; if a > 0 { a = 2;; }; while a > 3 { ; };", "let mut a = 1; if a > 0 { a = 2; } while a > 3 { }");
+    }
+
+    // Tests that #if blocks are kept only when their name is defined
+    #[test]
+    fn conditional_compilation() {
+        let source = "#if FAST\nlet mut a = 1;\n#else\nlet mut a = 2;\n#endif";
+        compile_and_assert_equal(&format!("#define FAST\n{}", source), "let mut a = 1;");
+        compile_and_assert_equal(source, "let mut a = 2;");
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_defines(vec![String::from("FAST")]);
+        assert_eq!(compile_and_merge("let mut a = 1;"), compile_and_merge_with_compiler(source, compiler));
+
+        // Blocks nested in removed blocks are removed too, along with their defines
+        compile_and_assert_equal("#if A\n#define B\n#if B\nlet mut a = 1;\n#endif\n#endif\n#if B\nlet mut b = 2;\n#endif\nlet mut c = 3;",
+            "let mut c = 3;");
+
+        // Removed lines are left empty so errors keep their line numbers
+        let response = check_request("#if A\nlet mut a = 1;\n#endif\nlet b = ;");
+        assert_eq!(4, response.diagnostics[0].line);
+        free_check_response(response);
+    }
+
+    #[test]
+    #[should_panic(expected = "#if without a matching #endif!")]
+    fn conditional_compilation_unclosed() {
+        compile_and_merge("#if A\nlet mut a = 1;");
+    }
+
+    #[test]
+    #[should_panic(expected = "#endif without a matching #if!")]
+    fn conditional_compilation_unopened() {
+        compile_and_merge("let mut a = 1;\n#endif");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid preprocessor directive #if A B!")]
+    fn conditional_compilation_invalid_directive() {
+        compile_and_merge("#if A B\nlet mut a = 1;\n#endif");
+    }
+
+    // Only #define, #if, #else and #endif lines are directives, other lines starting with # are left to the parser
+    #[test]
+    fn conditional_compilation_other_hash_lines() {
+        compile_and_assert_equal("let mut a = 1;\n/* notes\n# heading\n#ifdef\n*/", "let mut a = 1;");
+        compile_and_assert_equal("#if A\n/*\n# heading\n*/\n#endif\nlet mut a = 1;", "let mut a = 1;");
+
+        let response = check_request("#ifdef A\nlet mut a = 1;");
+        assert!(!response.success);
+        assert_eq!(1, response.diagnostics[0].line);
+        assert!(response.diagnostics[0].message.to_str().starts_with("Syntax Error"));
+        free_check_response(response);
+    }
+
+    // Floats need digits before the exponent and the exponent needs digits
+    #[test]
+    fn malformed_float_literals() {
+        for text in &[".e3", "5e", "5.e", ".", "5e+"] {
+            let response = check_request(&format!("let mut a = {};", text));
+            assert!(!response.success, "{} should be rejected", text);
+            assert!(response.diagnostics[0].message.to_str().starts_with("Syntax Error"));
+            free_check_response(response);
+        }
+    }
+
+    // Hexadecimal floats give the same value as the equivalent decimal literal
+    #[test]
+    fn hex_float_literals() {
+        compile_and_assert_equal("let mut a = 0x1.8p3;", "let mut a = 12.0;");
+        compile_and_assert_equal("let mut a = 0x1p-2;", "let mut a = 0.25;");
+        compile_and_assert_equal("let mut a = 0X1.8P+1;", "let mut a = 3.0;");
+        compile_and_assert_equal("let mut a = 0x.8p1;", "let mut a = 1.0;");
+        compile_and_assert_equal("let mut a = 0xA.8p0;", "let mut a = 10.5;");
+    }
+
+    // The binary exponent of a hexadecimal float is required
+    #[test]
+    fn malformed_hex_float_literals() {
+        for text in &["0x1.8", "0x.p1", "0x1p", "0x1.8e3"] {
+            let response = check_request(&format!("let mut a = {};", text));
+            assert!(!response.success, "{} should be rejected", text);
+            free_check_response(response);
+        }
+    }
+
+    // Block comments nest, so closing only the inner comment leaves the statement commented out
+    #[test]
+    #[should_panic(expected = "Syntax Error:  --> 1:1")]
+    fn unclosed_nested_comment() {
+        compile_and_merge("/* outer /* inner */ let mut a = true;");
+    }
+
     // Tests to make sure unary operations work with operator precedence.
     #[test]
     fn unary_operator_precedence() {
@@ -395,7 +915,9 @@ mod tests {
             for (op_str_2, precedence_2, operation_2) in &operators {
                 let text = &format!("let a = 1{}2{}3;", op_str_1, op_str_2);
                 let stack = compile_and_merge(text);
-                if precedence_1 >= precedence_2 {
+                // Only ^ is right associative
+                let left_first = precedence_1 > precedence_2 || (precedence_1 == precedence_2 && *op_str_1 != "^");
+                if left_first {
                     assert_eq!(vec![Val(1.0), Val(2.0), Op(FIXED(*operation_1)), Val(3.0), Op(FIXED(*operation_2))], stack);
                 } else {
                     assert_eq!(vec![Val(1.0), Val(2.0), Val(3.0), Op(FIXED(*operation_2)), Op(FIXED(*operation_1))], stack);
@@ -405,6 +927,15 @@ mod tests {
     }
 
 
+    // Tests that powers are evaluated from the right, as in maths
+    #[test]
+    fn power_right_associativity() {
+        compile_and_assert_equal("let mut a = 2^3^2;", "let mut a = 2^(3^2);");
+        let stack = compile_and_merge("let mut a = 2^3^2;");
+        assert_eq!(vec![Val(2.0), Val(3.0), Val(2.0), Op(FIXED(POW)), Op(FIXED(POW))], stack);
+        compile_and_assert_equal("let mut a = 2*3^2^2;", "let mut a = 2*(3^(2^2));");
+    }
+
     #[test]
     fn builtin_functions() {
         let functions = vec![ACOS,ACOSH,ASIN,ASINH,ATAN,ATAN2,ATANH,CBRT,CEIL,CPYSGN,COS,COSH,
@@ -422,6 +953,303 @@ mod tests {
         }
     }
 
+    // Creates an FFI environment variable description.
+    fn ffi_environment_variable(identifier: &str, ptr_offset: usize, qualifier: &str) -> EnvironmentVariable {
+        EnvironmentVariable {
+            identifier: identifier.to_string().try_into().unwrap(),
+            ptr_offset,
+            datatype: "f64".to_string().try_into().unwrap(),
+            qualifier: qualifier.to_string().try_into().unwrap(),
+            ptr_levels: "".to_string().try_into().unwrap(),
+        }
+    }
+
+    // Tests that const and mutable environment variables are counted in separate user space regions.
+    #[test]
+    fn environment_variable_user_space_size() {
+        let request = CompilerRequest {
+            code_text: "extern a; extern b; let mut c = [1, 2]; let const d = [3];".to_string().try_into().unwrap(),
+            env_vars: vec![
+                ffi_environment_variable("a", 0, "const"),
+                ffi_environment_variable("b", 1, "mut"),
+                ffi_environment_variable("x", 2, "const"),
+            ].into(),
+            precision: 32,
+            max_stack_size_override: 0,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width: 64,
+        };
+        let response = compile(&request);
+        assert_eq!(vec![3, 3], response.user_space_size.to_vec());
+        free_compile_response(response);
+    }
+
+    fn check_request(text: &str) -> CheckResponse {
+        check(&CompilerRequest {
+            code_text: text.to_string().try_into().unwrap(),
+            env_vars: vec![].into(),
+            precision: 32,
+            max_stack_size_override: 0,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width: 64,
+        })
+    }
+
+    // Tests that checking a valid program gives no diagnostics.
+    #[test]
+    fn check_success() {
+        let response = check_request("let mut a = 1;\nprint(a);");
+        assert!(response.success);
+        assert_eq!(0, response.diagnostics.len());
+        free_check_response(response);
+    }
+
+    // Tests that syntax errors are located at their line and column.
+    #[test]
+    fn check_syntax_error() {
+        let response = check_request("let mut a = 1;\nlet b = ;");
+        assert!(!response.success);
+        let diagnostic = &response.diagnostics[0];
+        assert!(diagnostic.message.to_str().starts_with("Syntax Error"));
+        assert_eq!((2, 9), (diagnostic.line, diagnostic.column));
+        free_check_response(response);
+    }
+
+    // Tests that semantic errors are located at the innermost statement's line.
+    #[test]
+    fn check_semantic_error() {
+        let response = check_request("let a = 1;\nfn f() {\n    let b = c;\n}\nf();");
+        assert!(!response.success);
+        let diagnostic = &response.diagnostics[0];
+        assert_eq!((3, 0), (diagnostic.line, diagnostic.column));
+        free_check_response(response);
+    }
+
+    // Tests that try_compile_str returns program code or the error found.
+    #[test]
+    fn try_compile_str() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let program = compiler.try_compile_str("let mut a = 1;").unwrap();
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert_eq!(compiler.compile_str("let mut a = 1;").to_string(), program.to_string());
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let diagnostic = compiler.try_compile_str("let mut a = 1;\nlet b = c;").unwrap_err();
+        assert!(diagnostic.message.contains("c"));
+        assert_eq!(2, diagnostic.line);
+    }
+
+    // Tests that a non-zero stack size override replaces the estimate.
+    #[test]
+    fn max_stack_size_override() {
+        let text = "let mut a = 1; let mut b = a * (a + 2);";
+        let request = |max_stack_size_override| CompilerRequest {
+            code_text: text.to_string().try_into().unwrap(),
+            env_vars: vec![].into(),
+            precision: 32,
+            max_stack_size_override,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width: 64,
+        };
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let estimate = compiler.compile_str(text).max_stack_size;
+
+        let response = compile(&request(0));
+        assert_eq!(estimate, response.recommended_stack_size);
+        free_compile_response(response);
+
+        let response = compile(&request(16));
+        assert_eq!(16, response.recommended_stack_size);
+        free_compile_response(response);
+    }
+
+    // Tests that precisions other than the supported float widths are rejected
+    #[test]
+    fn invalid_precision() {
+        let request = |precision| CompilerRequest {
+            code_text: "let mut a = 1;".to_string().try_into().unwrap(),
+            env_vars: vec![].into(),
+            precision,
+            max_stack_size_override: 0,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width: 64,
+        };
+        let response = check(&request(7));
+        assert!(!response.success);
+        assert_eq!("Unsupported precision 7! Use 8, 16, 32, 64 or 128.", response.diagnostics[0].message.to_str());
+        assert_eq!(0, response.diagnostics[0].line);
+        free_check_response(response);
+
+        let response = check(&request(64));
+        assert!(response.success);
+        free_check_response(response);
+    }
+
+    // Tests that compile reports invalid requests and programs in a failed response
+    #[test]
+    fn compile_failure() {
+        let request = |text: &str, precision, target_width| CompilerRequest {
+            code_text: text.to_string().try_into().unwrap(),
+            env_vars: vec![].into(),
+            precision,
+            max_stack_size_override: 0,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width,
+        };
+        let response = compile(&request("let mut a = 1;", 7, 64));
+        assert!(!response.success);
+        assert_eq!("Unsupported precision 7! Use 8, 16, 32, 64 or 128.", response.diagnostics[0].message.to_str());
+        assert_eq!(0, response.diagnostics[0].line);
+        assert!(response.instructions_list.is_empty());
+        free_compile_response(response);
+
+        let response = compile(&request("let mut a = 1;", 32, 16));
+        assert!(!response.success);
+        assert_eq!("Unsupported target width 16! Use 32 or 64.", response.diagnostics[0].message.to_str());
+        free_compile_response(response);
+
+        let response = compile(&request("let mut a = 1;\nlet mut b = c;", 32, 64));
+        assert!(!response.success);
+        assert_eq!(2, response.diagnostics[0].line);
+        free_compile_response(response);
+
+        let response = compile(&request("let mut a = 1;", 32, 64));
+        assert!(response.success);
+        assert!(response.diagnostics.is_empty());
+        free_compile_response(response);
+    }
+
+    // Tests that 32 bit targets get u32 operations and f32 values, keeping the bits of pointers.
+    #[test]
+    fn target_width() {
+        let request = |target_width| CompilerRequest {
+            code_text: "let mut a = [1.5, 2]; let mut b = a[1] * 0.5;".to_string().try_into().unwrap(),
+            env_vars: vec![].into(),
+            precision: 32,
+            max_stack_size_override: 0,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width,
+        };
+        let wide = compile(&request(64));
+        let narrow = compile(&request(32));
+        assert_eq!(0, narrow.operations_list.len());
+        assert_eq!(0, narrow.values_list.len());
+        assert_eq!(0, narrow.user_space.len());
+        assert_eq!(0, wide.operations_list_32.len());
+        assert_eq!(wide.instructions_list.to_vec(), narrow.instructions_list.to_vec());
+        assert_eq!(wide.operations_list.iter().map(|op| *op as u32).collect::<Vec<u32>>(), narrow.operations_list_32.to_vec());
+        assert_eq!(vec![1.5, 2.0], narrow.user_space_32.to_vec());
+        for (wide_value, narrow_value) in wide.values_list.iter().zip(narrow.values_list_32.iter()) {
+            if wide_value.is_subnormal() {
+                assert_eq!(wide_value.to_bits(), narrow_value.to_bits() as u64);
+            } else {
+                assert_eq!(*wide_value as f32, *narrow_value);
+            }
+        }
+        free_compile_response(wide);
+        free_compile_response(narrow);
+    }
+
+    // Tests that strings, which can't be packed into 32 bit values, are reported for 32 bit targets.
+    #[test]
+    fn target_width_string() {
+        let request = |target_width| CompilerRequest {
+            code_text: "let mut a = 1;\nlet b = \"hello world\"; print(b);".to_string().try_into().unwrap(),
+            env_vars: vec![].into(),
+            precision: 32,
+            max_stack_size_override: 0,
+            quantise_literals: false,
+            defines: vec![].into(),
+            target_width,
+        };
+        let response = compile(&request(32));
+        assert!(!response.success);
+        assert_eq!("String literals need a 64 bit target as they are packed 8 characters to a value! (Target is 32 bit)",
+                   response.diagnostics[0].message.to_str());
+        assert_eq!(2, response.diagnostics[0].line);
+        assert!(response.values_list_32.is_empty());
+        free_compile_response(response);
+
+        let response = check(&request(32));
+        assert!(!response.success);
+        free_check_response(response);
+
+        let response = compile(&request(0));
+        assert!(response.success);
+        free_compile_response(response);
+    }
+
+    // Tests that compiling a program using built-in functions twice gives identical output.
+    #[test]
+    fn builtin_functions_deterministic() {
+        let text = "let a = __sqrt(2.0); let b = __pow(a, 2.0); let c = __sqrt(b);";
+        let first: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let second: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert_eq!(first.compile_str(text).to_string(), second.compile_str(text).to_string());
+
+        // Only the built-ins a program calls are registered
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert_eq!(vec!["__sqrt".to_string()], compiler.compile_str("let mut a = 2.0; let b = __sqrt(a);").builtin_functions);
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert_eq!(vec!["__pow".to_string(), "__sqrt".to_string()], compiler.compile_str(text).builtin_functions);
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert!(compiler.compile_str("let mut a = 1;").builtin_functions.is_empty());
+    }
+
+    // Tests that saved output doesn't depend on where the source or destination files live.
+    #[test]
+    fn compile_and_save_reproducible() {
+        let text = "fn f(a) { return a * 2; } let b = f(3); let mut s = \"b\"; print(s); print(b);";
+        let mut outputs = vec![];
+        for dir_name in ["barracuda_reproducible_a", "barracuda_reproducible_b"] {
+            let dir = std::env::temp_dir().join(dir_name);
+            std::fs::create_dir_all(&dir).unwrap();
+            let source = dir.join("program.bc");
+            let dest = dir.join("program.bct");
+            std::fs::write(&source, text).unwrap();
+            let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+            compiler.compile_and_save(&source, &dest, true).unwrap();
+            outputs.push(std::fs::read(&dest).unwrap());
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    // Writing to a buffer gives the same text as displaying the compiled program
+    #[test]
+    fn compile_to_writer() {
+        let text = "let mut a = 1; print(a);";
+        let mut buffer: Vec<u8> = vec![];
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        compiler.compile_to_writer(text, &mut buffer, true).unwrap();
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let expected = compiler.compile_str(text).decorated().to_string();
+        assert_eq!(expected, String::from_utf8(buffer).unwrap());
+    }
+
+    // Decorated output keeps its comments on their own lines so it parses back to the same program
+    #[test]
+    fn decorated_output_round_trip() {
+        use barracuda_common::{BarracudaCodeTextParser, ProgramCodeParser};
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("fn f(a) { return a * 2; } let mut b = [1, 2]; print(f(b[1]));").decorated();
+        let mut parsed = BarracudaCodeTextParser::new().parse_str(&code.to_string()).unwrap();
+        // The text parser stores the program reversed, ready to be consumed from the end
+        parsed.instructions.reverse();
+        parsed.operations.reverse();
+        parsed.values.reverse();
+        assert_eq!(code.instructions, parsed.instructions);
+        assert_eq!(code.operations, parsed.operations);
+        assert_eq!(code.values, parsed.values);
+    }
+
     // Tests that parentheses work with operator precedence.
     #[test]
     fn parentheses_precedence() {
@@ -492,13 +1320,87 @@ mod tests {
     #[test]
     fn function_call() {
         let stack = compile_and_merge(
-            "fn test_func() {} let a = test_func();");
+            "fn test_func() {} let a = 1; test_func();");
         let (function_def, test_func_location, position) 
             = generate_empty_function_definition(0);
         assert_eq!(function_def, stack[..position]);
-        let (function_call, _) 
+        let (function_call, position_2) 
             = generate_default_function_call(position, test_func_location);
-        assert_eq!(function_call, stack[position..]);
+        assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_2..]);
+    }
+
+    // Tests functions can be called before they're defined
+    #[test]
+    fn forward_function_reference() {
+        let stack = compile_and_merge(
+            "let a = 1; test_func(); fn test_func() {}");
+        let (function_call, position) 
+            = generate_default_function_call(0, 18);
+        assert_eq!(function_call, stack[..position]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position..position + 1]);
+        let (function_def, test_func_location, _) 
+            = generate_empty_function_definition(position + 1);
+        assert_eq!(18, test_func_location);
+        assert_eq!(function_def, stack[position + 1..]);
+
+        // Calls within a function body can also refer forward to where b starts, after the jump over its body
+        let stack = compile_and_merge("fn a() -> f64 { return b(2); } let mut x = a(); fn b(y) -> f64 { return y; }");
+        assert_eq!(vec![54], single_argument_call_targets(&stack));
+        assert_eq!(Instr(GOTO), stack[51]);
+    }
+
+    // Returns the targets of function calls with one argument, which jump to the function and then drop the argument.
+    fn single_argument_call_targets(stack: &[MergedInstructions]) -> Vec<usize> {
+        stack.windows(3).filter_map(|window| match window {
+            [Val(target), Instr(GOTO), Op(FIXED(DROP))] => Some(target.to_bits() as usize),
+            _ => None
+        }).collect()
+    }
+
+    // Tests functions can call themselves and each other recursively
+    #[test]
+    fn recursive_functions() {
+        let texts = vec![
+            "fn is_even(n) -> bool { if n == 0 { return true; } else { return is_odd(n - 1); } }
+             fn is_odd(n) -> bool { if n == 0 { return false; } else { return is_even(n - 1); } }
+             let mut a = is_even(10);",
+            "fn factorial(n) -> f64 { if n <= 1 { return 1; } else { return n * factorial(n - 1); } } let mut a = factorial(5);",
+        ];
+        for text in &texts {
+            let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+            assert!(compiler.compile_str(text).warnings.is_empty(), "{}", text);
+        }
+
+        // is_even starts at 4 and is_odd at 70, each skipped over by a jump to the end of its body.
+        // Each calls the other and the program calls is_even.
+        let stack = compile_and_merge(texts[0]);
+        assert_eq!(vec![Val(ptr(68)), Instr(GOTO)], stack[..2]);
+        assert_eq!(vec![Val(ptr(134)), Instr(GOTO)], stack[66..68]);
+        assert_eq!(vec![70, 4, 4], single_argument_call_targets(&stack));
+
+        // Defining is_odd first swaps where the functions start but not which calls which
+        let stack = compile_and_merge(
+            "fn is_odd(n) -> bool { if n == 0 { return false; } else { return is_even(n - 1); } }
+             fn is_even(n) -> bool { if n == 0 { return true; } else { return is_odd(n - 1); } }
+             let mut a = is_even(10);");
+        assert_eq!(vec![70, 4, 70], single_argument_call_targets(&stack));
+
+        // factorial calls itself from its own body
+        let stack = compile_and_merge(texts[1]);
+        assert_eq!(vec![4, 4], single_argument_call_targets(&stack));
+    }
+
+    #[test]
+    #[should_panic(expected = "Recursive function factorial must declare its return type!")]
+    fn recursive_function_without_return_type() {
+        compile_and_merge("fn factorial(n) { if n <= 1 { return 1; } else { return n * factorial(n - 1); } } let mut a = factorial(5);");
+    }
+
+    #[test]
+    #[should_panic(expected = "Function a already exisits!")]
+    fn duplicate_function() {
+        compile_and_merge("fn a() {} a(); fn a() {}");
     }
 
     // Tests calling a function without assigning it to a variable works
@@ -519,26 +1421,29 @@ mod tests {
     #[test]
     fn function_multiple_call() {
         let stack = compile_and_merge(
-            "fn test_func() {} let a = test_func(); let b = test_func(); let c = test_func();");
+            "fn test_func() {} test_func(); test_func(); test_func();");
         let (function_def, test_func_location, position) 
             = generate_empty_function_definition(0);
         assert_eq!(function_def, stack[..position]);
         let (function_call, position_2) 
             = generate_default_function_call(position, test_func_location);
         assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(Op(FIXED(DROP)), stack[position_2]);
         let (function_call, position_3) 
-            = generate_default_function_call(position_2, test_func_location);
-        assert_eq!(function_call, stack[position_2..position_3]);
-        let (function_call, _) 
-            = generate_default_function_call(position_3, test_func_location);
-        assert_eq!(function_call, stack[position_3..]);
+            = generate_default_function_call(position_2 + 1, test_func_location);
+        assert_eq!(function_call, stack[position_2 + 1..position_3]);
+        assert_eq!(Op(FIXED(DROP)), stack[position_3]);
+        let (function_call, position_4) 
+            = generate_default_function_call(position_3 + 1, test_func_location);
+        assert_eq!(function_call, stack[position_3 + 1..position_4]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_4..]);
     }
 
     // Tests defining and calling two functions
     #[test]
     fn double_function() {
         let stack = compile_and_merge(
-            "fn test_func() {} fn test_func_2() {} let a = test_func(); let b = test_func_2();");
+            "fn test_func() {} fn test_func_2() {} test_func(); test_func_2();");
         let (function_def, test_func_location, position) 
             = generate_empty_function_definition(0);
         assert_eq!(function_def, stack[..position]);
@@ -548,9 +1453,11 @@ mod tests {
         let (function_call, position_3) 
             = generate_default_function_call(position_2, test_func_location);
         assert_eq!(function_call, stack[position_2..position_3]);
-        let (function_call, _) 
-            = generate_default_function_call(position_3, test_func_2_location);
-        assert_eq!(function_call, stack[position_3..]);
+        assert_eq!(Op(FIXED(DROP)), stack[position_3]);
+        let (function_call, position_4) 
+            = generate_default_function_call(position_3 + 1, test_func_2_location);
+        assert_eq!(function_call, stack[position_3 + 1..position_4]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_4..]);
     }
 
     // Tests defining a function with content
@@ -558,7 +1465,7 @@ mod tests {
     fn function_with_contents() {
         let function_contents = "let a = 3+4;";
         let stack = compile_and_merge(
-            &format!("fn test_func() {{{}}} let a = test_func();", function_contents));
+            &format!("fn test_func() {{{}}} test_func();", function_contents));
         let (function_def, test_func_location, position) 
             = generate_function_definition(0, function_contents);
         assert_eq!(function_def, stack[..position]);
@@ -567,33 +1474,120 @@ mod tests {
         assert_eq!(function_call, stack[position..position_2]);
     }
 
+    // Tests returning a tuple writes each value into slots reserved by the caller which become the destructured variables
+    #[test]
+    fn function_tuple_return() {
+        let stack = compile_and_merge(
+            "fn pair() -> (f64, f64) { return (1, 2); } let (x, y) = pair(); let mut z = y;");
+        let mut body = vec![Val(1.0), Val(2.0),
+            Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(2)), Op(FIXED(SUB_PTR)), Op(FIXED(SWAP)), Op(FIXED(STK_WRITE)),
+            Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(3)), Op(FIXED(SUB_PTR)), Op(FIXED(SWAP)), Op(FIXED(STK_WRITE))];
+        body.extend(vec![Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(1)), Op(FIXED(ADD_PTR)),
+            Op(FIXED(RCSTK_PTR)), Val(ptr(1)), Op(FIXED(SWAP)), Op(FIXED(STK_WRITE)), Instr(GOTO)]);
+        let (function_def, pair_location, position)
+            = generate_function_def_precompiled(0, body);
+        assert_eq!(function_def, stack[..position]);
+
+        // Caller reserves the return values and doesn't read the return store
+        assert_eq!(vec![Val(0.0), Val(0.0)], stack[position..position + 2]);
+        let (mut function_call, _)
+            = generate_default_function_call(position + 2, pair_location);
+        function_call.truncate(function_call.len() - 2);
+        assert_eq!(function_call, stack[position + 2..position + 2 + function_call.len()]);
+
+        // y is the second local variable
+        assert_eq!(vec![Val(ptr(2)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ))],
+            stack[position + 2 + function_call.len()..]);
+    }
+
+    // Tests destructuring a function with parameters returning a tuple
+    #[test]
+    fn function_tuple_return_with_parameters() {
+        compile_and_merge("fn divmod(a, b) -> (f64, f64) { return (a / b, a % b); } let mut (q, r) = divmod(7, 2); q = r;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Tuples must be destructured into variables!")]
+    fn tuple_construct() {
+        compile_and_merge("let t = (1, 2);");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't destructure a tuple of 2 values into 3 variables!")]
+    fn tuple_destructure_wrong_arity() {
+        compile_and_merge("let (a, b, c) = (1, 2);");
+    }
+
+    #[test]
+    #[should_panic(expected = "Return type of function did not match declared type!")]
+    fn tuple_return_type_mismatch() {
+        compile_and_merge("fn pair() -> (f64, f64) { return (1, 2, 3); } let (a, b) = pair();");
+    }
+
+    // Omitted trailing arguments are filled with the parameter defaults
+    #[test]
+    fn function_default_parameter() {
+        let defaulted = compile_and_merge("fn f(a, b = 5) { let mut c = a + b; } f(1);");
+        let explicit = compile_and_merge("fn f(a, b) { let mut c = a + b; } f(1, 5);");
+        assert_eq!(explicit, defaulted);
+        let overridden = compile_and_merge("fn f(a, b = 5) { let mut c = a + b; } f(1, 2);");
+        let explicit = compile_and_merge("fn f(a, b) { let mut c = a + b; } f(1, 2);");
+        assert_eq!(explicit, overridden);
+    }
+
+    // Defaulted parameters still take part in multiple dispatch
+    #[test]
+    fn function_default_parameter_multiple_dispatch() {
+        compile_and_merge("fn f(a, b = -1) { let mut c = a + b; } f(1); f(true, 2); f(2);");
+    }
+
+    #[test]
+    #[should_panic(expected = "When calling function f, need to use 1 to 3 parameters! (Used 0)")]
+    fn function_default_parameter_too_few_arguments() {
+        compile_and_merge("fn f(a, b = 5, c = 6) { let mut d = a; } f();");
+    }
+
+    #[test]
+    #[should_panic(expected = "Required parameter b can't follow a parameter with a default value!")]
+    fn function_default_parameter_before_required() {
+        compile_and_merge("fn f(a = 5, b) { let mut c = a + b; } f(1, 2);");
+    }
+
+    #[test]
+    #[should_panic(expected = "Default value of parameter b must be a literal!")]
+    fn function_default_parameter_not_literal() {
+        compile_and_merge("fn f(a, b = a + 1) { let mut c = a + b; } f(1);");
+    }
+
     // Checks calling a parameterized function
     #[test]
     fn function_with_parameter_call() {
-        let stack = compile_and_merge("fn test_func(a) {} let a = test_func(4);");
+        let stack = compile_and_merge("fn test_func(a) {} test_func(4);");
         let (function_def, test_func_location, position) 
             = generate_empty_function_definition(0);
         assert_eq!(function_def, stack[..position]);
         assert_eq!(Val(4.0), stack[position]);
         let position = position + 1;
-        let (function_call, _) 
+        let (function_call, position_2) 
             = generate_function_call(position, test_func_location, 1);
-        assert_eq!(function_call, stack[position..]);
+        assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_2..]);
     }
 
     // Checks defining a function with a parameter and then using that parameter
     #[test]
     fn function_with_parameter_used() {
-        let stack = compile_and_merge("fn test_func(a) {let b = a;} let a = test_func(4);");
+        let stack = compile_and_merge("fn test_func(a) {let b = a;} test_func(4);");
         let (function_def, test_func_location, position) 
             = generate_function_def_precompiled(0, 
                 vec![Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(2)), Op(FIXED(SUB_PTR)), Op(FIXED(STK_READ))]);
         assert_eq!(function_def, stack[..position]);
         assert_eq!(Val(4.0), stack[position]);
         let position = position + 1;
-        let (function_call, _) 
+        let (function_call, position_2) 
             = generate_function_call(position, test_func_location, 1);
-        assert_eq!(function_call, stack[position..]);
+        assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_2..]);
     }
 
     // Checks return works
@@ -625,10 +1619,39 @@ mod tests {
         assert_eq!(vec![Val(3.0), Op(FIXED(MUL))], stack[position_2..]);
     }
 
+    // Tests returning without a value only invokes the return handler
+    #[test]
+    fn function_with_empty_return() {
+        let stack = compile_and_merge("fn test_func() { return; } test_func();");
+        let return_handler = vec![Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(1)), Op(FIXED(ADD_PTR)),
+            Op(FIXED(RCSTK_PTR)), Val(ptr(1)), Op(FIXED(SWAP)), Op(FIXED(STK_WRITE)), Instr(GOTO)];
+        let (function_def, test_func_location, position)
+            = generate_function_def_precompiled(0, return_handler);
+        assert_eq!(function_def, stack[..position]);
+        let (function_call, position_2)
+            = generate_default_function_call(position, test_func_location);
+        assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_2..]);
+    }
+
+    // Tests returning early from a void function
+    #[test]
+    fn function_with_early_return() {
+        compile_and_merge("fn f(x) { if x > 1 { return; } print(x); } f(2);");
+        compile_and_merge("fn f(x) -> none { while true { if x > 1 { return; } } } f(2);");
+    }
+
+    // Returning without a value can't be mixed with returning a value
+    #[test]
+    #[should_panic(expected = "Return types should always be equal in a function!")]
+    fn function_with_mixed_empty_return() {
+        compile_and_merge("fn f(x) { if x > 1 { return; } return x; } let mut a = f(2);");
+    }
+
     // Checks that function parameters can be assigned to
     #[test]
     fn function_with_parameter_assigned() {
-        let stack = compile_and_merge("fn test_func(mut a) {a = 3;} let mut b = 4; let const c = test_func(b);");
+        let stack = compile_and_merge("fn test_func(mut a) {a = 3;} let mut b = 4; test_func(b);");
         let (function_def, test_func_location, position) 
             = generate_function_def_precompiled(0, 
             vec![Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(2)), Op(FIXED(SUB_PTR)), // get pointer to parameter
@@ -636,31 +1659,342 @@ mod tests {
         assert_eq!(function_def, stack[..position]);
         assert_eq!(Val(4.0), stack[position]);
         let position = position + 6;
-        let (function_call, _) 
+        let (function_call, position_2) 
             = generate_function_call(position, test_func_location, 1);
-        assert_eq!(function_call, stack[position..]);
+        assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_2..]);
+    }
+
+    // Mutable variables can be passed to const parameters that are never assigned to, sharing the const implementation
+    #[test]
+    fn function_with_mutable_argument_to_const_parameter() {
+        compile_and_assert_equal(
+            "fn f(x) { return x + 1; } let mut a = 3; let mut b = f(a);",
+            "fn f(mut x) { return x + 1; } let mut a = 3; let mut b = f(a);"
+        );
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let program = compiler.compile_str("fn f(x) { return x[1]; } let mut a = [1, 2]; let b = [3, 4]; let mut c = f(a) + f(b);");
+        assert_eq!(1, program.function_count);
+    }
+
+    // Const parameters that are assigned to still need a const argument
+    #[test]
+    #[should_panic(expected = "Type qualifier of parameter \"x\" in function f didn't match the input argument!")]
+    fn function_with_mutable_argument_to_assigned_const_parameter() {
+        compile_and_merge("fn f(x) { if x > 1 { x = 2; } } let mut a = 3; f(a);");
+    }
+
+    // Checks assigning to an element of an array parameter writes through the array the parameter holds
+    #[test]
+    fn function_with_array_parameter_assigned() {
+        let stack = compile_and_merge("fn f(mut a: [f64; 2]) { a[1] = 7; } let mut b = [1, 2]; f(b);");
+        let element_address = vec![Op(FIXED(SUB_PTR)), Op(FIXED(STK_READ)), Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR))];
+        assert!(stack.windows(element_address.len()).any(|window| window == element_address.as_slice()));
     }
 
     // Checks calling the same function twice with differently typed parameters results in two seperate function calls
     #[test]
     fn function_with_multiple_dispatch() {
-        let stack = compile_and_merge("fn test_func(a) {} let b = test_func(4); let c = test_func(&b);");
+        let stack = compile_and_merge("fn test_func(a) {} let b = -1; test_func(4); test_func(&b);");
         let (function_def, test_func_location, position) 
             = generate_empty_function_definition(0);
         assert_eq!(function_def, stack[..position]);
         let (function_def, test_func_2_location, position_2) 
             = generate_empty_function_definition(position);
             assert_eq!(function_def, stack[position..position_2]);
-        assert_eq!(Val(4.0), stack[position_2]);
-        let position_3 = position_2 + 1;
+        assert_eq!(vec![Val(1.0), Op(FIXED(NEGATE)), Val(4.0)], stack[position_2..position_2 + 3]);
+        let position_3 = position_2 + 3;
         let (function_call, position_4) 
             = generate_function_call(position_3, test_func_location, 1);
         assert_eq!(function_call, stack[position_3..position_4]);
-        let position_5 = position_4 + 4;
-        assert_eq!(vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))], stack[position_4..position_5]);
-        let (function_call, _) 
+        assert_eq!(Op(FIXED(DROP)), stack[position_4]);
+        let position_5 = position_4 + 5;
+        assert_eq!(vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))], stack[position_4 + 1..position_5]);
+        let (function_call, position_6) 
             = generate_function_call(position_5, test_func_2_location, 1);
-        assert_eq!(function_call, stack[position_5..]);
+        assert_eq!(function_call, stack[position_5..position_6]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_6..]);
+    }
+
+    // Tests that decorated output marks the source line of each statement.
+    #[test]
+    fn source_line_comments() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let a = 1;\n\nif a {\n    print(a);\n}\n").decorated().to_string();
+        assert!(code.contains("# line 1\n"));
+        assert!(code.contains("# line 3\n"));
+        assert!(code.contains("# line 4\n"));
+        assert!(!code.contains("# line 2\n"));
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert!(!compiler.compile_str("let a = 1;").to_string().contains("# line"));
+    }
+
+    // Tests that literals rounded at 32 bit precision are counted in a warning.
+    #[test]
+    fn precision_loss_warning() {
+        let text = "let mut a = 0.1; let mut b = 0.5; let mut c = 3.14159265358979; let mut d = 16777217; let e = [0.2, 1.0];";
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(32);
+        let code = compiler.compile_str(text);
+        assert_eq!(vec!["4 values lose precision when rounded to f32".to_string()], code.warnings);
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(64);
+        let code = compiler.compile_str(text);
+        assert!(code.warnings.is_empty());
+    }
+
+    // Tests that quantised literals are emitted as the value stored at the output precision.
+    #[test]
+    fn quantised_literals() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(32).set_quantise_literals(true);
+        assert_eq!(vec![Val(0.1f32 as f64)], compile_and_merge_with_compiler("let mut a = 0.1;", compiler));
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(64).set_quantise_literals(true);
+        assert_eq!(vec![Val(0.1)], compile_and_merge_with_compiler("let mut a = 0.1;", compiler));
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(32);
+        assert_eq!(vec![Val(0.1)], compile_and_merge_with_compiler("let mut a = 0.1;", compiler));
+
+        // Values of static arrays are quantised too
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(32).set_quantise_literals(true);
+        let code = compiler.compile_str("let e = [0.2, 1.0];");
+        assert_eq!(vec![0.2f32 as f64, 1.0], code.constant_user_space);
+    }
+
+    #[test]
+    #[should_panic(expected = "Literal 1e300 overflows f32!")]
+    fn quantised_literal_overflow() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(32).set_quantise_literals(true);
+        compile_and_merge_with_compiler("let mut a = 1.0e300;", compiler);
+    }
+
+    // Tests that loops with constant false conditions are warned about and elided at -O1.
+    #[test]
+    fn dead_loop_warning() {
+        let text = "let mut x = 0; while false { x = x + 1; } for (let mut i = 0; 0; i = i + 1) { x = i; }";
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str(text);
+        assert_eq!(vec![
+            "while loop condition is always false so its body never executes".to_string(),
+            "for loop condition is always false so its body never executes".to_string()
+        ], code.warnings);
+        assert_ne!(compile_and_merge(text), compile_and_merge("let mut x = 0; let mut i = 0;"));
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_optimisation_level(1);
+        let elided = compiler.compile_str("let mut x = 0; while false { x = x + 1; }");
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let expected = compiler.compile_str("let mut x = 0;");
+        assert_eq!(expected.values, elided.values);
+        assert_eq!(expected.operations, elided.operations);
+        assert_eq!(expected.instructions, elided.instructions);
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_optimisation_level(1);
+        let elided = compiler.compile_str(text);
+        assert!(!elided.instructions.contains(&GOTO) && !elided.instructions.contains(&GOTO_IF));
+    }
+
+    // Tests that lut samples a built in function into a constant array at compile time.
+    #[test]
+    fn lookup_table() {
+        let values: Vec<String> = (0..4).map(|i| format!("{:?}", (i as f64 * 0.5).sin())).collect();
+        let expected_text = format!("let t = [{}]; let mut x = t[1];", values.join(", "));
+        let text = "let t = lut(__sin, 4, 0.0, 1.5); let mut x = t[1];";
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str(text);
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let expected = compiler.compile_str(&expected_text);
+        assert_eq!(expected, code);
+        assert_eq!(expected.constant_user_space, code.constant_user_space);
+        assert_eq!(vec![0, 4], code.user_space_size);
+    }
+
+    #[test]
+    #[should_panic(expected = "lut can only sample built in functions! (f isn't built in)")]
+    fn lookup_table_non_builtin() {
+        compile_and_merge("fn f(x) -> f64 { return x; } let t = lut(f, 4, 0.0, 1.0);");
+    }
+
+    #[test]
+    #[should_panic(expected = "lut arguments must be compile time constants!")]
+    fn lookup_table_non_constant() {
+        compile_and_merge("let mut n = 4; let t = lut(__sin, n, 0.0, 1.0);");
+    }
+
+    #[test]
+    #[should_panic(expected = "lut sample of LOG at 0 isn't finite! (-inf)")]
+    fn lookup_table_non_finite() {
+        compile_and_merge("let t = lut(__log, 4, 0.0, 1.0);");
+    }
+
+    #[test]
+    #[should_panic(expected = "Literal 1.0e400 overflows f64!")]
+    fn literal_overflow() {
+        compile_and_merge("let mut a = 1.0e400;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Literal 0x1p1024 overflows f64!")]
+    fn hex_float_literal_overflow() {
+        compile_and_merge("let mut a = 0x1p1024;");
+    }
+
+    // Tests that pointers are compared by address, including with null.
+    #[test]
+    fn pointer_equality() {
+        let reference = compile_and_merge("let mut a = 1; let mut c = &a;")[1..].to_vec();
+        let compare = |op| vec![Op(FIXED(SUB_PTR)), Op(FIXED(LONGLONGTODOUBLE)), Val(0.0), Op(FIXED(op))];
+
+        let stack = compile_and_merge("let mut a = 1; let mut c = &a == null;");
+        assert_eq!([reference.clone(), vec![Val(0.0)], compare(EQ)].concat(), stack[1..]);
+
+        let stack = compile_and_merge("let mut a = 1; let mut c = &a != &a;");
+        assert_eq!([reference.clone(), reference.clone(), compare(NEQ)].concat(), stack[1..]);
+
+        // null can be stored in any pointer
+        compile_and_merge("let mut a = 1; let mut p = &a; p = null; let mut q: *f64 = null; let b = p == q;");
+        compile_and_merge("let mut h = malloc(8); let b = h != null; h = null; free(h);");
+
+        // Pointers declared as null keep their declared type so can be dereferenced once assigned
+        let stack = compile_and_merge("let mut a = 1; let mut q: *f64 = null; q = &a; let mut b = *q + 1;");
+        assert_eq!(vec![Op(FIXED(STK_READ)), Val(1.0), Op(FIXED(ADD))], stack[stack.len() - 3..]);
+    }
+
+    // null is only compatible with pointers, it doesn't make other types compatible with each other.
+    #[test]
+    #[should_panic(expected = "Cannot perform operation EQUAL with mismatched types! (POINTER(PRIMITIVE(F64)) vs HEAPPOINTER(PRIMITIVE(F64)))")]
+    fn pointer_equality_mismatched() {
+        compile_and_merge("let mut a = 1; let mut p = &a; let mut h = malloc(8); let b = p == h;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot use operation LESS_THAN on type POINTER(NONE)")]
+    fn pointer_ordering() {
+        compile_and_merge("let a = null < null;");
+    }
+
+    // Tests that malloc returns a heap pointer and free releases it without leaving a value to drop
+    #[test]
+    fn malloc_and_free() {
+        let stack = compile_and_merge("let mut p = malloc(16); free(p);");
+        let mut expected = vec![Val(16.0), Op(FIXED(MALLOC))];
+        expected.extend(generate_variable_call(1));
+        expected.push(Op(FIXED(FREE)));
+        assert_eq!(expected, stack);
+    }
+
+    // Tests that values are written and read through malloc pointers with the heap ops
+    #[test]
+    fn malloc_write_and_read() {
+        let stack = compile_and_merge("let mut p = malloc(16); *p = 3; let mut a = *p;");
+        let address = vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))];
+        let mut expected = vec![Val(16.0), Op(FIXED(MALLOC))];
+        expected.extend(address);
+        expected.extend(vec![Op(FIXED(STK_READ)), Val(3.0), Op(FIXED(WRITE))]);
+        expected.extend(generate_variable_call(1));
+        expected.push(Op(FIXED(READ)));
+        assert_eq!(expected, stack);
+
+        // Pointers to stack variables still use the stack ops
+        let stack = compile_and_merge("let mut a = 1; let mut p = &a; *p = 3;");
+        assert_eq!(Op(FIXED(STK_WRITE)), stack[stack.len() - 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "free expects a pointer!")]
+    fn free_non_pointer() {
+        compile_and_merge("let mut a = 16; free(a);");
+    }
+
+    #[test]
+    #[should_panic(expected = "malloc size must be a number!")]
+    fn malloc_non_numeric_size() {
+        compile_and_merge("let mut a = 16; malloc(&a);");
+    }
+
+    // Tests that seeding and reading the random generator emit the VM's random ops
+    #[test]
+    fn seeded_random() {
+        let stack = compile_and_merge("rand_seed(42); let mut a = rand();");
+        assert_eq!(vec![Val(42.0), Op(FIXED(RAND_SEED)), Op(FIXED(RAND))], stack);
+        compile_and_assert_equal("rand_seed(42); let mut a = rand();", "__rand_seed(42); let mut a = __rand();");
+    }
+
+    // Tests that abs, sign and clamp fold constant arguments and otherwise lower to built ins and comparisons
+    #[test]
+    fn numeric_helpers() {
+        compile_and_assert_equal("let mut a = clamp(5, 0, 3);", "let mut a = 3;");
+        compile_and_assert_equal("let mut a = clamp(-5.5, -1, 1);", "let mut a = -1;");
+        compile_and_assert_equal("let mut a = abs(-2); let mut b = sign(-2); let mut c = sign(0);",
+            "let mut a = 2; let mut b = -1; let mut c = 0;");
+
+        compile_and_assert_equal("let mut x = 5; let mut a = clamp(x, 0, 3);", "let mut x = 5; let mut a = __fmin(__fmax(x, 0), 3);");
+        compile_and_assert_equal("let mut x = 5; let mut a = abs(x);", "let mut x = 5; let mut a = __fabs(x);");
+        let stack = compile_and_merge("let mut x = 5; let mut a = sign(x);");
+        assert_eq!([generate_variable_call(1), vec![Op(FIXED(DUP)), Val(0.0), Op(FIXED(GT)), Op(FIXED(SWAP)), Val(0.0), Op(FIXED(LT)), Op(FIXED(SUB))]].concat(),
+            stack[1..]);
+        // The argument is evaluated once so side effects happen once
+        let stack = compile_and_merge("let mut a = sign(__rand() - 0.5);");
+        assert_eq!(1, stack.iter().filter(|instruction| **instruction == Op(FIXED(RAND))).count());
+
+        // User functions take precedence over the helpers
+        let stack = compile_and_merge("fn abs(x) { return -x; } let mut a = abs(2);");
+        assert!(stack.contains(&Op(FIXED(NEGATE))));
+    }
+
+    #[test]
+    #[should_panic(expected = "clamp lower bound 3 is greater than its upper bound 0!")]
+    fn clamp_inverted_bounds() {
+        compile_and_merge("let mut x = 5; let mut a = clamp(x, 3, 0);");
+    }
+
+    #[test]
+    #[should_panic(expected = "sign expects numbers! Found PRIMITIVE(Bool)")]
+    fn sign_non_numeric() {
+        compile_and_merge("let mut a = sign(true);");
+    }
+
+    // Tests that approx_eq compares the difference of its arguments to a tolerance
+    #[test]
+    fn approx_eq() {
+        compile_and_assert_equal("let mut x = 0.1; let mut a = approx_eq(x, 0.3, 1e-9);",
+            "let mut x = 0.1; let mut a = __fabs(x - 0.3) < 1e-9;");
+        compile_and_assert_equal("let mut a = approx_eq(0.1 + 0.2, 0.3, 1e-9);", "let mut a = __fabs(0.1 + 0.2 - 0.3) < 1e-9;");
+        compile_and_assert_equal("let mut a = approx_eq(1.0, 1.5, 0.1);", "let mut a = false;");
+    }
+
+    // Tests that arrays and argument lists accept a trailing comma
+    #[test]
+    fn trailing_commas() {
+        compile_and_assert_equal("let mut a = [1,2,3];", "let mut a = [1,2,3,];");
+        compile_and_assert_equal("let mut a = [[1,2],[3,4]];", "let mut a = [[1,2,],[3,4],];");
+        compile_and_assert_equal("let mut a = __atan2(1,2);", "let mut a = __atan2(1,2,);");
+        compile_and_assert_equal("fn f(a, b) -> f64 { return a + b; } let mut c = f(1, 2);",
+            "fn f(a, b) -> f64 { return a + b; } let mut c = f(1, 2,);");
+    }
+
+    #[test]
+    #[should_panic(expected = "= expected unary")]
+    fn trailing_comma_only() {
+        compile_and_merge("let mut a = [,];");
+    }
+
+    #[test]
+    #[should_panic(expected = "= expected func_arg")]
+    fn trailing_comma_only_arguments() {
+        compile_and_merge("let mut a = __rand(,);");
+    }
+
+    // Tests that spreading an array into a function call is the same as passing each element
+    #[test]
+    fn function_spread_arguments() {
+        compile_and_assert_equal(
+            "fn g(a, b, c) {} let xs = [1,2,3]; g(...xs);",
+            "fn g(a, b, c) {} let xs = [1,2,3]; g(xs[0], xs[1], xs[2]);");
+        compile_and_assert_equal(
+            "fn g(a, b, c) {} g(...[1,2,3]);",
+            "fn g(a, b, c) {} g(1,2,3);");
+        compile_and_assert_equal(
+            "fn g(a, b, c) {} let xs = [2,3]; g(1, ...xs);",
+            "fn g(a, b, c) {} let xs = [2,3]; g(1, xs[0], xs[1]);");
     }
 
     // Tests that if and else work
@@ -686,27 +2020,202 @@ mod tests {
             Val(ptr(18)), Instr(GOTO), Val(5.0), Op(FIXED(PRINTFF))], stack);
     }
 
+    // Tests that an else if ladder generates each arm once, with every arm jumping to the shared end.
+    #[test]
+    fn else_if_ladder() {
+        let stack = compile_and_merge("if false {print(1);} else if false {print(2);} else if false {print(3);} else {print(4);}");
+        // 3 conditional arms of condition, GOTO_IF, body and GOTO, followed by the else body
+        assert_eq!(3 * 7 + 2, stack.len());
+        let end = ptr(25);
+        assert_eq!(vec![Val(0.0), Val(ptr(9)), Instr(GOTO_IF), Val(1.0), Op(FIXED(PRINTFF)), Val(end), Instr(GOTO),
+            Val(0.0), Val(ptr(16)), Instr(GOTO_IF), Val(2.0), Op(FIXED(PRINTFF)), Val(end), Instr(GOTO),
+            Val(0.0), Val(ptr(23)), Instr(GOTO_IF), Val(3.0), Op(FIXED(PRINTFF)), Val(end), Instr(GOTO),
+            Val(4.0), Op(FIXED(PRINTFF))], stack);
+    }
+
     // Generates a variable call.
     // Takes the position of the variable.
     fn generate_variable_call(position: usize) -> Vec<MergedInstructions> {
         return vec![Val(ptr(position)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ))]
     }
 
-    // Generates a variable assignment.
-    // Takes the position of the variable, and the assignment expression.
-    fn generate_variable_assign(position: usize, expression: &str) -> Vec<MergedInstructions> {
-        let compiled_expression = compile_and_merge(expression);
-        let mut variable_assign = vec![Val(ptr(position)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))];
-        variable_assign.extend(compiled_expression);
-        variable_assign.push(Op(FIXED(STK_WRITE)));
-        return variable_assign
+    // Generates a variable assignment.
+    // Takes the position of the variable, and the assignment expression.
+    fn generate_variable_assign(position: usize, expression: &str) -> Vec<MergedInstructions> {
+        let compiled_expression = compile_and_merge(expression);
+        let mut variable_assign = vec![Val(ptr(position)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))];
+        variable_assign.extend(compiled_expression);
+        variable_assign.push(Op(FIXED(STK_WRITE)));
+        return variable_assign
+    }
+
+    // Tests construct statements.
+    #[test]
+    fn construct() {
+        let stack = compile_and_merge("let mut a = 3;");
+        assert_eq!(vec![Val(3.0)], stack);
+    }
+
+    // Tests that constants initialised with literals are replaced with their value.
+    #[test]
+    fn constant_propagation() {
+        compile_and_assert_equal("let const PI = 3.14159; let mut b = PI * 2.0;", "let mut b = 3.14159 * 2.0;");
+        compile_and_assert_equal("let a = true; let mut b = 0; if a { b = 1; }", "let mut b = 0; if true { b = 1; }");
+        compile_and_assert_equal("let a: f32 = 2; fn f(x) { return x; } let mut b = f(a);", "fn f(x) { return x; } let mut b = f(2);");
+    }
+
+    // Tests that constants that aren't initialised with literals, or are referenced, keep their storage.
+    #[test]
+    fn constant_propagation_skipped() {
+        let stack = compile_and_merge("let a = 1 + 2; let mut b = a;");
+        assert_eq!(vec![Val(1.0), Val(2.0), Op(FIXED(ADD))], stack[..3]);
+        assert_eq!(generate_variable_call(1), stack[3..]);
+
+        let stack = compile_and_merge("let a = 3; let b = &a; let mut c = a;");
+        assert_eq!(Val(3.0), stack[0]);
+        assert_eq!(generate_variable_call(1), stack[stack.len() - 5..]);
+    }
+
+    // Tests that enum variants are replaced with their integer values.
+    #[test]
+    fn enum_variants() {
+        compile_and_assert_equal("enum Color { Red, Green, Blue } let mut c = Color::Blue; let a = c == Color::Green;",
+                                 "let mut c = 2; let a = c == 1;");
+        compile_and_assert_equal("enum Color { Red = 5, Green, Blue = 1, } let mut c = Color::Green + Color::Blue;",
+                                 "let mut c = 6 + 1;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Enum Color has no variant Purple!")]
+    fn enum_unknown_variant() {
+        compile_and_merge("enum Color { Red, Green, Blue } let a = Color::Purple;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Enum Color has more than one variant named Red!")]
+    fn enum_duplicate_variant() {
+        compile_and_merge("enum Color { Red, Green, Red }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Value of enum variant Size::Huge overflows!")]
+    fn enum_value_overflow() {
+        compile_and_merge("enum Size { Large = 18446744073709551615, Huge }");
+    }
+
+    // Tests that struct members are laid out and accessed like array elements.
+    #[test]
+    fn struct_members() {
+        compile_and_assert_equal("struct Point { x: f64, y: f64 } let mut p: Point; p.y = 3; let mut a = p.x + p.y;",
+                                 "let mut p: [f64; 2]; p[1] = 3; let mut a = p[0] + p[1];");
+        compile_and_assert_equal("struct Point { x: f64, y: f64, } let mut p: [Point; 3]; p[2].x = 1; let mut a = p[1].y;",
+                                 "let mut p: [[f64; 2]; 3]; p[2][0] = 1; let mut a = p[1][1];");
+    }
+
+    // Tests that sizeof gives the byte width of types and of the types of expressions
+    #[test]
+    fn sizeof() {
+        compile_and_assert_equal("let mut a = sizeof(i32); let mut b = sizeof(bool); let mut c = sizeof(f64);",
+                                 "let mut a = 4; let mut b = 1; let mut c = 8;");
+        compile_and_assert_equal("let mut x: [i16; 3]; let mut a = sizeof(x); let mut b = sizeof(x[0]); let mut c = sizeof(&x);",
+                                 "let mut x: [i16; 3]; let mut a = 6; let mut b = 2; let mut c = 8;");
+        compile_and_assert_equal("struct Point { x: f32, y: f32 } let mut a = sizeof(Point); let mut b = sizeof([1, 2]);",
+                                 "struct Point { x: f32, y: f32 } let mut a = 8; let mut b = 16;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't take the size of NONE!")]
+    fn sizeof_no_value() {
+        compile_and_merge("fn f() {} let mut a = sizeof(f());");
+    }
+
+    // Tests that chains of indices and members with runtime indices are addressed the same way
+    // when read and assigned.
+    #[test]
+    fn struct_member_chain() {
+        compile_and_assert_equal("struct Point { x: f64, y: f64 } let mut p: [Point; 3]; let mut i = 1; p[i].y = 2; let mut a = p[i].y;",
+                                 "let mut p: [[f64; 2]; 3]; let mut i = 1; p[i][1] = 2; let mut a = p[i][1];");
+        let stack = compile_and_merge("let mut p: [[f64; 2]; 3]; let mut i = 1; p[i][1] = 2; let mut a = p[i][1];");
+        let element_offset = vec![Val(2.0), Op(FIXED(MUL_PTR)), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)),
+                                  Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR))];
+        let occurrences = stack.windows(element_offset.len()).filter(|window| *window == element_offset.as_slice()).count();
+        assert_eq!(2, occurrences);
+    }
+
+    #[test]
+    #[should_panic(expected = "has no member z!")]
+    fn struct_unknown_member() {
+        compile_and_merge("struct Point { x: f64, y: f64 } let mut p: Point; let a = p.z;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't access member x of a non-struct!")]
+    fn member_of_non_struct() {
+        compile_and_merge("let mut p = 1; let a = p.x;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Member p of struct Line must be a primitive or a pointer!")]
+    fn struct_non_primitive_member() {
+        compile_and_merge("struct Line { p: [f64; 2] }");
+    }
+
+    // Structs containing themselves by value would be infinitely sized
+    #[test]
+    #[should_panic(expected = "Struct Node contains itself so would be infinitely sized! Use a pointer to it instead.")]
+    fn struct_containing_itself() {
+        compile_and_merge("struct Node { value: f64, next: Node }");
+    }
+
+    // Structs can point to themselves, members of the struct pointed to are accessed through the pointer
+    #[test]
+    fn struct_pointer_to_itself() {
+        let stack = compile_and_merge("struct Node { value: f64, next: *Node } let mut a: Node; let mut b: Node; b.next = &a; let mut c = (*(b.next)).value;");
+        let mut member_read = generate_variable_call(2);
+        member_read.extend(vec![Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64)),
+            Op(FIXED(STK_READ)), Val(0.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))]);
+        assert_eq!(member_read, stack[stack.len() - member_read.len()..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Struct Point is already defined!")]
+    fn struct_redefinition() {
+        compile_and_merge("struct Point { x: f64 } struct Point { y: f64 }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown datatype Point!")]
+    fn struct_unknown() {
+        compile_and_merge("let mut p: Point;");
+    }
+
+    // Tests that array sizes can name compile time constants.
+    #[test]
+    fn constant_array_size() {
+        compile_and_assert_equal("let N = 3; let a: [f64; N];", "let a: [f64; 3];");
+        compile_and_assert_equal("let const N = 2; let mut a: [[f64; N]; N] = [[1, 2], [3, 4]]; let b = a[1][N - 1];",
+                                 "let mut a: [[f64; 2]; 2] = [[1, 2], [3, 4]]; let b = a[1][2 - 1];");
+        compile_and_assert_equal("let N = 4; let mut x = 0; for (let mut i = 0; i < N; i = i + 1) { x = x + i; }",
+                                 "let mut x = 0; for (let mut i = 0; i < 4; i = i + 1) { x = x + i; }");
+    }
+
+    // Tests that parameter types can name compile time constants as array sizes.
+    #[test]
+    fn constant_array_size_parameter() {
+        compile_and_assert_equal("let N = 2; fn f(a: [f64; N]) { return a[1]; } let mut x: [f64; 2] = [1, 2]; let y = f(x);",
+                                 "let N = 2; fn f(a: [f64; 2]) { return a[1]; } let mut x: [f64; 2] = [1, 2]; let y = f(x);");
+    }
+
+    #[test]
+    #[should_panic(expected = "Array size n isn't a compile time constant!")]
+    fn variable_array_size() {
+        compile_and_merge("let mut n = 3; let a: [f64; n];");
     }
 
-    // Tests construct statements.
     #[test]
-    fn construct() {
-        let stack = compile_and_merge("let a = 3;");
-        assert_eq!(vec![Val(3.0)], stack);
+    #[should_panic(expected = "Array size N must be a non-negative integer!")]
+    fn fractional_array_size() {
+        compile_and_merge("let N = 1.5; let a: [f64; N];");
     }
 
     #[test]
@@ -715,18 +2224,89 @@ mod tests {
         assert_eq!(vec![Val(0.0)], stack);
     }
 
+    // Tests that scalars and pointers declared without a value start as zero and can be assigned later
+    #[test]
+    fn empty_construct_scalar_and_pointer() {
+        compile_and_assert_equal("let mut a: i64; let mut b: *i64;", "let mut a = 0; let mut b = null;");
+        let stack = compile_and_merge("let mut a: i64; let mut b: *i64; let mut c = b == null; b = &a; *b = 4;");
+        assert_eq!(vec![Val(0.0), Val(0.0)], stack[..2]);
+        assert_eq!(generate_variable_call(2), stack[2..7]);
+        assert_eq!(vec![Val(0.0), Op(FIXED(SUB_PTR))], stack[7..9]);
+        assert_eq!(vec![Val(4.0), Op(FIXED(STK_WRITE))], stack[stack.len() - 2..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Tuples must be destructured into variables!")]
+    fn empty_construct_tuple() {
+        compile_and_merge("let mut a: (f64, f64);");
+    }
+
     // Tests using a variable.
     #[test]
     fn use_variable() {
-        let stack = compile_and_merge("let a = 3; let b = a;");
+        let stack = compile_and_merge("let mut a = 3; let b = a;");
         assert_eq!(Val(3.0), stack[0]);
         assert_eq!(generate_variable_call(1), stack[1..]);
     }
 
+    // Tests that integers raised to constant integer powers are expanded into multiplications.
+    #[test]
+    fn integer_pow() {
+        let stack = compile_and_merge("let n: i64 = 10; let mut a: i64 = 2; let b = a ^ n;");
+        let mut expected = vec![Val(2.0)];
+        expected.extend(generate_variable_call(1));
+        expected.extend(vec![
+            Op(FIXED(DUP)),
+            Op(FIXED(DUP)), Op(FIXED(MUL)),
+            Op(FIXED(DUP)), Op(FIXED(MUL)), Op(FIXED(OVER)), Op(FIXED(MUL)),
+            Op(FIXED(DUP)), Op(FIXED(MUL)),
+            Op(FIXED(SWAP)), Op(FIXED(DROP)),
+        ]);
+        assert_eq!(expected, stack);
+
+        // Evaluate the expansion with the base on the stack
+        let mut values = vec![2.0];
+        for instruction in &stack[6..] {
+            match instruction {
+                Op(FIXED(DUP)) => values.push(values[values.len() - 1]),
+                Op(FIXED(OVER)) => values.push(values[values.len() - 2]),
+                Op(FIXED(MUL)) => { let b = values.pop().unwrap(); let a = values.pop().unwrap(); values.push(a * b); }
+                Op(FIXED(SWAP)) => { let length = values.len(); values.swap(length - 1, length - 2); }
+                Op(FIXED(DROP)) => { values.pop(); }
+                _ => unreachable!()
+            }
+        }
+        assert_eq!(vec![1024.0], values);
+
+        let stack = compile_and_merge("let e: i32 = 1; let mut a: i32 = 2; let b = a ^ e;");
+        assert_eq!(generate_variable_call(1), stack[1..]);
+
+        // Mutable primitives keep their declared integer type, which can still be printed
+        let stack = compile_and_merge("let mut a: i64 = 3; print(a);");
+        assert_eq!(Op(FIXED(PRINTI)), stack[stack.len() - 1]);
+    }
+
+    // Tests that pow falls back to the POW instruction for floats and non-constant exponents.
+    #[test]
+    fn integer_pow_fallback() {
+        let stack = compile_and_merge("let mut a = 2; let b = a ^ 10;");
+        assert_eq!(vec![Val(10.0), Op(FIXED(POW))], stack[6..]);
+
+        let stack = compile_and_merge("let mut a: i64 = 2; let mut n: i64 = 10; let b = a ^ n;");
+        assert_eq!(Op(FIXED(POW)), stack[stack.len() - 1]);
+
+        let stack = compile_and_merge("let mut a: i64 = 2; let b = a ^ 0.5;");
+        assert_eq!(vec![Val(0.5), Op(FIXED(POW))], stack[6..]);
+
+        // Both operands must be integers, untyped literals are floats
+        let stack = compile_and_merge("let mut a: i64 = 2; let b = a ^ 10;");
+        assert_eq!(vec![Val(10.0), Op(FIXED(POW))], stack[6..]);
+    }
+
     // Tests using a variable twice.
     #[test]
     fn use_variable_twice() {
-        let stack = compile_and_merge("let a = 3; let b = a; let c = a;");
+        let stack = compile_and_merge("let mut a = 3; let b = a; let c = a;");
         assert_eq!(Val(3.0), stack[0]);
         assert_eq!(generate_variable_call(1), stack[1..6]);
         assert_eq!(generate_variable_call(1), stack[6..]);
@@ -735,7 +2315,7 @@ mod tests {
     // Tests using a second variable.
     #[test]
     fn double_construct_with_use() {
-        let stack = compile_and_merge("let a = 3; let b = 4; let c = b;");
+        let stack = compile_and_merge("let mut a = 3; let mut b = 4; let c = b;");
         assert_eq!(Val(3.0), stack[0]);
         assert_eq!(Val(4.0), stack[1]);
         assert_eq!(generate_variable_call(2), stack[2..]);
@@ -746,16 +2326,16 @@ mod tests {
     fn variable_assignment() {
         let stack = compile_and_merge("let mut a = 3; a = 4;");
         assert_eq!(Val(3.0), stack[0]);
-        assert_eq!(generate_variable_assign(1, "let a = 4;"), stack[1..]);
+        assert_eq!(generate_variable_assign(1, "let mut a = 4;"), stack[1..]);
     }
 
     // Tests variable assignment for a second variable
     #[test]
     fn second_variable_assignment() {
-        let stack = compile_and_merge("let a = 3; let mut b = 4; b = 5;");
+        let stack = compile_and_merge("let mut a = 3; let mut b = 4; b = 5;");
         assert_eq!(Val(3.0), stack[0]);
         assert_eq!(Val(4.0), stack[1]);
-        assert_eq!(generate_variable_assign(2, "let a = 5;"), stack[2..]);
+        assert_eq!(generate_variable_assign(2, "let mut a = 5;"), stack[2..]);
     }
 
     // Tests print statement.
@@ -792,6 +2372,117 @@ mod tests {
         ], stack);
     }
 
+    // Tests a for loop with every section omitted loops forever.
+    #[test]
+    fn for_loop_empty_sections() {
+        let stack = compile_and_merge("for (;;) {print(7);}");
+        assert_eq!(vec![
+            Val(1.0), Val(ptr(9)), Instr(GOTO_IF), // always true condition
+            Val(7.0), Op(FIXED(PRINTFF)), // body
+            Val(ptr(2)), Instr(GOTO) // restart loop
+        ], stack);
+    }
+
+    // Tests for loops with some sections omitted only drop a loop variable when one was declared.
+    #[test]
+    fn for_loop_partial_sections() {
+        let while_loop = compile_and_merge("let mut i = 0; while i < 3 { i = i + 1; }");
+        let stack = compile_and_merge("for (let mut i = 0; i < 3;) { i = i + 1; }");
+        assert_eq!(Op(FIXED(DROP)), stack[stack.len() - 1]);
+        assert_eq!(while_loop, stack[..stack.len() - 1]);
+        let stack = compile_and_merge("let mut i = 0; for (; i < 3;) { i = i + 1; }");
+        assert_eq!(while_loop, stack);
+    }
+
+    // Tests a standalone block drops its locals at its end, so later locals reuse their slots.
+    #[test]
+    fn standalone_scope_block() {
+        let stack = compile_and_merge("let mut a = 1; { let mut b = 2; let mut c = b; a = c; } let mut d = 3; d = a;");
+        let local = |id: usize| vec![Val(ptr(id)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))];
+        let mut expected = vec![Val(1.0), Val(2.0)];
+        expected.extend(local(2));
+        expected.push(Op(FIXED(STK_READ)));
+        expected.extend(local(1));
+        expected.extend(local(3));
+        expected.extend(vec![Op(FIXED(STK_READ)), Op(FIXED(STK_WRITE))]);
+        expected.extend(vec![Op(FIXED(DROP)), Op(FIXED(DROP))]); // b and c dropped at block end
+        expected.push(Val(3.0));
+        expected.extend(local(2)); // d reuses the slot of b
+        expected.extend(local(1));
+        expected.extend(vec![Op(FIXED(STK_READ)), Op(FIXED(STK_WRITE))]);
+        assert_eq!(expected, stack);
+    }
+
+    // Tests that symbols declared in a block aren't visible after it, and may shadow outer symbols.
+    #[test]
+    fn standalone_scope_block_shadowing() {
+        compile_and_merge("let mut a = 1; { let mut a = 2; a = 3; } a = 4;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Identifier b doesn't exist!")]
+    fn standalone_scope_block_symbol_out_of_scope() {
+        compile_and_merge("{ let mut b = 2; } let mut c = b;");
+    }
+
+    // Tests that shadowing an enclosing declaration only warns when enabled
+    #[test]
+    fn shadowing_warning() {
+        let text = "let mut a = 1;\n{\n    let mut a = 2;\n}";
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert!(compiler.compile_str(text).warnings.is_empty());
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_warn_shadowing(true);
+        assert_eq!(vec!["declaration of a on line 3 shadows a declaration in an enclosing scope".to_string()],
+            compiler.compile_str(text).warnings);
+    }
+
+    // Tests that functions with a return type warn when they can reach their end without returning
+    #[test]
+    fn missing_return_warning() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let text = "fn f(x) -> f64 { if x > 0 { return 1; } } let mut a = f(1); let mut b = f(2);";
+        assert_eq!(vec!["function f has a return type but can reach the end of its body without returning".to_string()],
+            compiler.compile_str(text).warnings);
+
+        let returning = vec![
+            "fn f(x) -> f64 { if x > 0 { return 1; } else { return 2; } } let mut a = f(1);",
+            "fn f(x) -> f64 { if x > 0 { return 1; } else if x < 0 { return 2; } else { { return 3; } } } let mut a = f(1);",
+            "fn f(x) -> f64 { let mut y = x; return y; print(y); } let mut a = f(1);",
+            "fn f(x) { if x > 0 { print(x); } } f(1);",
+        ];
+        for text in returning {
+            let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+            assert!(compiler.compile_str(text).warnings.is_empty(), "{}", text);
+        }
+    }
+
+    // Tests that the symbol map records where variables are stored
+    #[test]
+    fn symbol_map() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let text = "let mut a = 1; let mut b: [f64; 2]; let c = [1, 2]; fn f(x) -> f64 { let mut y = x; return y; } let mut d = f(1);";
+        let symbols: Vec<(String, SymbolLocation)> = compiler.compile_str(text).symbols.into_iter()
+            .map(|symbol| (symbol.identifier, symbol.location))
+            .collect();
+        assert_eq!(vec![
+            ("a".to_string(), SymbolLocation::Stack(1)),
+            ("b".to_string(), SymbolLocation::MutableArray { frame_offset: 2, address: 0 }),
+            ("c".to_string(), SymbolLocation::ConstantArray { frame_offset: 3, address: 0 }),
+            ("x".to_string(), SymbolLocation::Stack(-2)),
+            ("y".to_string(), SymbolLocation::Stack(4)),
+            ("d".to_string(), SymbolLocation::Stack(4)),
+        ], symbols);
+    }
+
+    // Tests that function declarations don't shadow the locals of the scope they're called from
+    #[test]
+    fn shadowing_warning_function_call() {
+        let text = "fn f(a) -> f64 { let mut b = a; return b; } { let a = 1; let mut b = 2; let mut c = f(a); }";
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_warn_shadowing(true);
+        assert!(compiler.compile_str(text).warnings.is_empty());
+    }
+
     // Tests reading an external variable
     #[test]
     fn external_f64_variable() {
@@ -828,6 +2519,15 @@ mod tests {
         assert_eq!(vec![Val(ptr(7)), Op(FIXED(LDNX)), Op(FIXED(READ_I32))], stack);
     }
 
+    // Tests reading a boolean external variable, which is normalised to 1 or 0
+    #[test]
+    fn external_bool_variable() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::Bool, Qualifier::CONSTANT, "".to_string());
+        let stack = compile_and_merge_with_env_vars("extern a; let b = a;", env_vars);
+        assert_eq!(vec![Val(ptr(7)), Op(FIXED(LDNX)), Val(0.0), Op(FIXED(NEQ))], stack);
+    }
+
     // Tests reading an external variable with a double pointer (**) qualifier
     #[test]
     fn external_f64_variable_with_double_qualifier() {
@@ -846,6 +2546,45 @@ mod tests {
         assert_eq!(vec![Val(4.0), Val(ptr(7)), Op(FIXED(RCNX))], stack);
     }
 
+    // Tests that a fence is emitted between the extern accesses either side of it
+    #[test]
+    fn fence_between_extern_accesses() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::F64, Qualifier::MUTABLE, "".to_string());
+        let stack = compile_and_merge_with_env_vars("extern a; a = 4; fence(); let b = a;", env_vars);
+        assert_eq!(vec![
+            Val(4.0), Val(ptr(7)), Op(FIXED(RCNX)),
+            Op(FIXED(FENCE)),
+            Val(ptr(7)), Op(FIXED(LDNX))
+        ], stack);
+    }
+
+    // Tests that dynamic array indices are bounds checked at runtime only when enabled
+    #[test]
+    fn runtime_bounds_checking() {
+        use crate::compiler::utils::pack_string_to_f64_array;
+        let text = "let a = [1, 2, 3]; let mut i = 1; let mut b = a[i]; let mut c = a[2];";
+        let unchecked = compile_and_merge(text);
+        assert!(!unchecked.contains(&Instr(GOTO_IF)));
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_bounds_checking(true);
+        let checked = compile_and_merge_with_compiler(text, compiler);
+        let check_start = checked.iter().position(|x| *x == Op(FIXED(GTEQ))).unwrap() - 2;
+        let fault_location = checked.len() + 2 - 2 * pack_string_to_f64_array("Array index out of bounds!\n", 64).len();
+        assert_eq!(vec![
+            Op(FIXED(DUP)), Val(0.0), Op(FIXED(GTEQ)),
+            Op(FIXED(OVER)), Val(3.0), Op(FIXED(LT)),
+            Op(FIXED(AND)), Val(ptr(fault_location)), Instr(GOTO_IF)
+        ], checked[check_start..check_start + 9]);
+
+        // Only the dynamic index is checked
+        assert_eq!(1, checked.iter().filter(|x| **x == Instr(GOTO_IF)).count());
+
+        // Program skips over the fault handler which prints and halts
+        assert_eq!(vec![Val(ptr(checked.len() + 2)), Instr(GOTO)], checked[fault_location - 4..fault_location - 2]);
+        assert_eq!(Op(FIXED(PRINTC)), checked[checked.len() - 1]);
+    }
+
     // Tests writing to an external variable with a single pointer (*) qualifier
     #[test]
     fn external_variable_write_with_qualifier() {
@@ -864,6 +2603,41 @@ mod tests {
         assert_eq!(vec![Val(4.0), Val(ptr(7)), Op(FIXED(LDNX)), Op(FIXED(PTR_DEREF)), Op(FIXED(SWAP)), Op(FIXED(WRITE))], stack);
     }
 
+    // Tests dereferencing pointers to external variables reads with the width of their type
+    #[test]
+    fn external_variable_pointer_deref() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::I32, Qualifier::CONSTANT, "*".to_string());
+        env_vars.add_symbol("b".to_string(), 8, PrimitiveDataType::F64, Qualifier::CONSTANT, "**".to_string());
+        let stack = compile_and_merge_with_env_vars("extern a; extern b; let p = &a; let q = &b; let mut c = *p; let mut d = *q;", env_vars);
+        assert_eq!(vec![Val(ptr(7)), Op(FIXED(LDNX))], stack[..2]);
+        assert_eq!(vec![Val(ptr(8)), Op(FIXED(LDNX)), Op(FIXED(PTR_DEREF))], stack[2..5]);
+        let (p_call, q_call) = (generate_variable_call(1), generate_variable_call(2));
+        assert_eq!(p_call, stack[5..10]);
+        assert_eq!(Op(FIXED(READ_I32)), stack[10]);
+        assert_eq!(q_call, stack[11..16]);
+        assert_eq!(Op(FIXED(READ_F64)), stack[16]);
+    }
+
+    // Tests assigning through pointers to external variables writes with the width of their type
+    #[test]
+    fn external_variable_pointer_assign() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::F32, Qualifier::MUTABLE, "*".to_string());
+        let stack = compile_and_merge_with_env_vars("extern a; let mut p = &a; *p = 2;", env_vars);
+        assert_eq!(generate_variable_call(1), stack[2..7]);
+        assert_eq!(vec![Val(2.0), Op(FIXED(WRITE_F32))], stack[7..]);
+    }
+
+    // Integers can't be written through pointers as the VM has no integer write
+    #[test]
+    #[should_panic(expected = "I32 can't currently be written through pointers to environment variables")]
+    fn external_variable_pointer_assign_integer() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::I32, Qualifier::MUTABLE, "*".to_string());
+        compile_and_merge_with_env_vars("extern a; let mut p = &a; *p = 2;", env_vars);
+    }
+
     // Tests for pointers
     #[test]
     fn reference() {
@@ -891,8 +2665,8 @@ mod tests {
 
     #[test]
     fn pointer_assign() {
-        let old_stack = compile_and_merge("let a = 3; let b = &a;");
-        let stack = compile_and_merge("let a = 3; let mut b = &a; *b = 4;");
+        let old_stack = compile_and_merge("let mut a = 3; let b = &a;");
+        let stack = compile_and_merge("let mut a = 3; let mut b = &a; *b = 4;");
 
         assert_eq!(old_stack, stack[..old_stack.len()]);
         assert_eq!(generate_variable_call(2), stack[old_stack.len()..old_stack.len()+5]);
@@ -901,18 +2675,59 @@ mod tests {
 
     #[test]
     fn triple_pointer_assign() {
-        let old_stack = compile_and_merge("let a = 3; let b = &a; let c = &b; let d = &c;");
-        let stack = compile_and_merge("let a = 3; let b = &a; let c = &b; let mut d = &c; ***d = 4;");
+        let old_stack = compile_and_merge("let mut a = 3; let mut b = &a; let mut c = &b; let d = &c;");
+        let stack = compile_and_merge("let mut a = 3; let mut b = &a; let mut c = &b; let mut d = &c; ***d = 4;");
 
         assert_eq!(old_stack, stack[..old_stack.len()]);
         assert_eq!(generate_variable_call(4), stack[old_stack.len()..old_stack.len()+5]);
         assert_eq!(vec![Op(FIXED(STK_READ)), Op(FIXED(STK_READ)), Val(4.0), Op(FIXED(STK_WRITE))], stack[old_stack.len()+5..]);
     }
 
+    // Arrays can hold pointers, which are dereferenced after indexing
+    #[test]
+    fn array_of_pointers() {
+        let text = "let mut a = 1; let mut b = 2; let mut c: [*i64; 2] = [&a, &b]; let mut d = *(c[1]);";
+        compile_and_assert_equal(text, "let mut a = 1; let mut b = 2; let mut c = [&a, &b]; let mut d = *(c[1]);");
+        let stack = compile_and_merge(text);
+        assert_eq!(generate_variable_call(3), stack[stack.len() - 11..stack.len() - 6]);
+        assert_eq!(vec![Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64)),
+            Op(FIXED(STK_READ))], stack[stack.len() - 6..]);
+    }
+
+    // Pointers to arrays are dereferenced to the array before indexing
+    #[test]
+    fn pointer_to_array() {
+        let old_stack = compile_and_merge("let mut a = [1, 2, 3]; let mut b: *[i64; 3] = &a;");
+        let stack = compile_and_merge("let mut a = [1, 2, 3]; let mut b: *[i64; 3] = &a; let mut c = (*b)[1];");
+        assert_eq!(old_stack, stack[..old_stack.len()]);
+        let position = old_stack.len();
+        assert_eq!(generate_variable_call(2), stack[position..position + 5]);
+        assert_eq!(vec![Op(FIXED(STK_READ)), Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)),
+            Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))], stack[position + 5..]);
+
+        let stack = compile_and_merge("let mut a = [1, 2, 3]; let mut b: *[i64; 3] = &a; *b[1] = 7;");
+        assert_eq!(generate_variable_call(2), stack[position..position + 5]);
+        assert_eq!(vec![Op(FIXED(STK_READ)), Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)),
+            Val(7.0), Op(FIXED(SWAP)), Op(FIXED(RCNX))], stack[position + 5..]);
+    }
+
+    // Pointers to constants can only be held by constants so constants can't be written through them
+    #[test]
+    #[should_panic(expected = "Can't take a mutable reference to constant a!")]
+    fn mutable_reference_to_constant() {
+        compile_and_merge("let const a = 3; let mut b = &a; *b = 4;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't take a mutable reference to constant a!")]
+    fn mutable_reference_to_constant_assignment() {
+        compile_and_merge("let const a = 3; let mut c = 1; let mut b = &c; b = true ? &c : &a;");
+    }
+
     //Check that parameters can also use pointer assign syntax
     #[test]
     fn parameter_pointer_assign() {
-        let stack = compile_and_merge("fn test_func(mut a: *i64) {*a = 3;} let mut b = 1; let c = test_func(&b);"); // TODO: Investigate this, I don't think this should pass without b being mutable..
+        let stack = compile_and_merge("fn test_func(mut a: *i64) {*a = 3;} let mut b = 1; test_func(&b);"); // TODO: Investigate this, I don't think this should pass without b being mutable..
         let (function_def, test_func_location, position) 
             = generate_function_def_precompiled(0, 
             vec![Val(ptr(1)), Op(FIXED(STK_READ)), Val(ptr(2)), Op(FIXED(SUB_PTR)), Op(FIXED(STK_READ)), // get pointer
@@ -920,9 +2735,10 @@ mod tests {
         assert_eq!(function_def, stack[..position]);
         assert_eq!(vec![Val(1.0), Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR))], stack[position..position+5]);
         let position = position + 5;
-        let (function_call, _) 
+        let (function_call, position_2) 
             = generate_function_call(position, test_func_location, 1);
-        assert_eq!(function_call, stack[position..]);
+        assert_eq!(function_call, stack[position..position_2]);
+        assert_eq!(vec!(Op(FIXED(DROP))), stack[position_2..]);
     }
 
     // Tests for arrays
@@ -979,6 +2795,16 @@ mod tests {
         assert_eq!(vec![Val(0.0)], stack);
     }
 
+    // Items made only of literals are evaluated at compile time so the array can be preallocated
+    #[test]
+    fn constant_expression_array() {
+        compile_and_assert_equal("let mut a = [1+1, 2*2];", "let mut a = [2, 4];");
+        compile_and_assert_equal("let mut a = [-(1+2), 3 > 2];", "let mut a = [-3, true];");
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let mut a = [1+1, 2*2];");
+        assert_eq!(vec![2.0, 4.0], code.mutable_user_space);
+    }
+
     #[test]
     fn array_access() {
         let old_stack = compile_and_merge("let mut a = [1];");
@@ -1030,8 +2856,20 @@ mod tests {
 
         assert_eq!(old_stack, stack[..old_stack.len()]);
         assert_eq!(vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ)),
-                        Val(0.0), Val(1.0), Op(FIXED(MUL_PTR)), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), // Enter first level
-                        Val(0.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))], // Enter second level
+                        Val(ptr(0)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))], // Constant indices are folded
+            stack[old_stack.len()..stack.len()]);
+    }
+
+    #[test]
+    fn multidimensional_array_access_non_constant() {
+        let old_stack = compile_and_merge("let mut a = [[1, 2], [3, 4]]; let mut i = 1;");
+        let stack = compile_and_merge("let mut a = [[1, 2], [3, 4]]; let mut i = 1; let mut b = a[i][1];");
+
+        assert_eq!(old_stack, stack[..old_stack.len()]);
+        assert_eq!(vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ)),
+                        Val(ptr(2)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ)),
+                        Val(2.0), Op(FIXED(MUL_PTR)), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), // Enter first level
+                        Val(1.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))], // Enter second level
             stack[old_stack.len()..stack.len()]);
     }
 
@@ -1045,10 +2883,7 @@ mod tests {
 
         assert_eq!(old_stack, stack[..old_stack.len()]);
         assert_eq!(vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ)),
-            Val(1.0), Val(8.0), Op(FIXED(MUL_PTR)), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), 
-            Val(0.0), Val(4.0), Op(FIXED(MUL_PTR)), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), 
-            Val(1.0), Val(2.0), Op(FIXED(MUL_PTR)), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), 
-            Val(0.0), Op(FIXED(DOUBLETOLONGLONG)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))], stack[old_stack.len()..stack.len()]);
+            Val(ptr(10)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))], stack[old_stack.len()..stack.len()]);
     }
 
     // Tests for arrays
@@ -1170,6 +3005,53 @@ mod tests {
         }
     }
 
+    // Integer literals widen implicitly to initialise float declarations
+    #[test]
+    fn integer_literal_float_type() {
+        compile_and_assert_equal("let mut a = 3.0;", "let mut a: f64 = 3;");
+        compile_and_assert_equal("let mut a = -3.0;", "let mut a: f32 = -3;");
+        compile_and_assert_equal("let mut a = 2.5;", "let mut a: f32 = 2.5;");
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert!(compiler.compile_str("let mut a: f64 = 0; let mut b: f32 = -3; let mut c = 3; let mut d: i32 = 3;").warnings.is_empty());
+    }
+
+    // Tests that widening warns only when the float type can't hold the integer exactly.
+    #[test]
+    fn integer_literal_float_type_rounded() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let mut a: f32 = 16777217;");
+        assert!(code.warnings.contains(&"integer literal 16777217 can't be held exactly by the float type of a so is rounded when widened".to_string()));
+
+        // Large powers of two only need one mantissa bit
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert!(compiler.compile_str("let mut a: f32 = 16777216; let mut b: f16 = 2048;").warnings.is_empty());
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert_eq!(vec!["integer literal 2049 can't be held exactly by the float type of a so is rounded when widened".to_string()],
+                   compiler.compile_str("let mut a: f16 = 2049;").warnings);
+    }
+
+    // Float literals are rejected by integer declarations, even when whole
+    #[test]
+    #[should_panic(expected = "Can't initialise PRIMITIVE(I32) with float literal 3.0! Use an integer literal instead.")]
+    fn whole_float_literal_integer_type() {
+        compile_and_merge("let mut a: i32 = 3.0;");
+    }
+
+    // Fractional literals would be truncated by integer declarations so are rejected
+    #[test]
+    #[should_panic(expected = "Can't initialise PRIMITIVE(I64) with float literal 3.5! Use an integer literal instead.")]
+    fn float_literal_integer_type() {
+        compile_and_merge("let mut a: i64 = 3.5;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't initialise PRIMITIVE(I32) with float literal -2.5! Use an integer literal instead.")]
+    fn negative_float_literal_integer_type() {
+        compile_and_merge("let mut a: i32 = -2.5;");
+    }
+
     #[test]
     fn binary_operator_types() {
         compile_and_assert_equal("let a = 0; let b = 1; let c = a+b;", "let a:i8 = 0; let b:i16 = 1; let c:i32 = a+b;"); //currently, all literal types are equal.
@@ -1191,6 +3073,20 @@ mod tests {
         compile_and_assert_equal("let a = 0; let b = -a;", "let a = 0; let b: i64 = -a;");
     }
 
+    // Tests that the built in function list gives the source names and argument counts
+    #[test]
+    fn builtin_function_list() {
+        let functions = super::builtin_functions();
+        assert_eq!(BARRACUDA_BUILT_IN_FUNCTIONS.len(), functions.len());
+        let signatures: Vec<(String, i8)> = functions.iter()
+            .map(|function| (function.name.to_string(), function.argument_count))
+            .collect();
+        assert!(signatures.contains(&(String::from("__sin"), 1)));
+        assert!(signatures.contains(&(String::from("__atan2"), 2)));
+        assert!(signatures.contains(&(String::from("__rand"), 0)));
+        free_builtin_functions(functions);
+    }
+
     #[test]
     fn builtin_function_types() {
         let functions = vec![ACOS,ACOSH,ASIN,ASINH,ATAN,ATAN2,ATANH,CBRT,CEIL,CPYSGN,COS,COSH,
@@ -1212,13 +3108,26 @@ mod tests {
     }
 
     #[test]
+    #[should_panic(expected = "Can't bind an expression with no value to a variable!")]
     fn empty_function_type() {
-        compile_and_assert_equal("fn func() {} let a = func();", "fn func() {} let a: none = func();");
+        compile_and_merge("fn f(){} let a = f();");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't bind an expression with no value to a variable!")]
+    fn empty_function_declared_type() {
+        compile_and_merge("fn func() {} let a: none = func();");
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't assign an expression with no value to a variable!")]
+    fn empty_function_assignment() {
+        compile_and_merge("fn func() {} let mut a = 1; a = func();");
     }
 
     #[test]
     fn empty_function_return_type() {
-        compile_and_assert_equal("fn func() {} let a = func();", "fn func() -> none {} let a = func();");
+        compile_and_assert_equal("fn func() {} func();", "fn func() -> none {} func();");
     }
 
     #[test]
@@ -1311,7 +3220,7 @@ mod tests {
 
     #[test]
     fn pointer_assign_type() {
-        compile_and_assert_equal("let a = 3; let mut b = &a; *b = 4;", "let a: i64 = 3; let mut b: *i64 = &a; *b = 4;");
+        compile_and_assert_equal("let mut a = 3; let mut b = &a; *b = 4;", "let mut a: i64 = 3; let mut b: *i64 = &a; *b = 4;");
     }
 
     #[test]
@@ -1362,9 +3271,83 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn bad_array_literal() {
-        compile_and_merge("let a = [1,2,3] == [1,2,3];");
+    #[should_panic(expected = "Array index 5 is out of bounds for array of size 3!")]
+    fn array_index_out_of_bounds() {
+        compile_and_merge("let a = [1, 2, 3]; let mut b = a[5];");
+    }
+
+    #[test]
+    #[should_panic(expected = "Array index 2 is out of bounds for array of size 2!")]
+    fn multidimensional_array_index_out_of_bounds() {
+        compile_and_merge("let a = [[1, 2], [3, 4], [5, 6]]; let mut b = a[1][2];");
+    }
+
+    #[test]
+    #[should_panic(expected = "Array index 3 is out of bounds for array of size 3!")]
+    fn array_assignment_out_of_bounds() {
+        compile_and_merge("let mut a = [[1, 2], [3, 4], [5, 6]]; a[3][0] = 1;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Array index -1 is negative!")]
+    fn array_assignment_negative_index() {
+        compile_and_merge("let mut a = [1, 2, 3]; a[-1] = 0;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Array index -2 is negative!")]
+    fn array_index_negative() {
+        compile_and_merge("let a = [1, 2, 3]; let mut b = a[-2];");
+    }
+
+    #[test]
+    #[should_panic(expected = "Array index -1 is negative!")]
+    fn external_array_negative_index() {
+        let mut env_vars = EnvironmentSymbolContext::new();
+        env_vars.add_symbol("a".to_string(), 7, PrimitiveDataType::F64, Qualifier::MUTABLE, "*".to_string());
+        compile_and_merge_with_env_vars("extern a; let mut b = a[-1];", env_vars);
+    }
+
+    // Tests that in bounds literal indices and dynamic indices are still accepted.
+    #[test]
+    fn array_index_in_bounds() {
+        compile_and_merge("let a = [[1, 2], [3, 4], [5, 6]]; let mut i = 7; let mut b = a[2][1]; let mut c = a[i][0];");
+    }
+
+    // Tests that arrays are compared element by element, jumping out at the first mismatch
+    #[test]
+    fn array_literal_comparison() {
+        let stack = compile_and_merge("let mut a = [1,2] == [1,3];");
+        assert_eq!(vec![Val(1.0), Val(1.0), Op(FIXED(EQ)), Val(ptr(15)), Instr(GOTO_IF),
+            Val(2.0), Val(3.0), Op(FIXED(EQ)), Val(ptr(15)), Instr(GOTO_IF),
+            Val(1.0), Val(ptr(16)), Instr(GOTO), Val(0.0)], stack);
+        let stack = compile_and_merge("let mut a = [[1],[2]] != [[1],[3]];");
+        assert_eq!(vec![Val(1.0), Val(1.0), Op(FIXED(EQ)), Val(ptr(15)), Instr(GOTO_IF),
+            Val(2.0), Val(3.0), Op(FIXED(EQ)), Val(ptr(15)), Instr(GOTO_IF),
+            Val(0.0), Val(ptr(16)), Instr(GOTO), Val(1.0)], stack);
+    }
+
+    #[test]
+    fn array_comparison() {
+        let stack = compile_and_merge("let mut a = [1,2]; let b = [1,2]; let mut c = a == b;");
+        let position = stack.len() - 42;
+        assert_eq!([generate_variable_call(1), generate_variable_call(2)].concat(), stack[position..position + 10]);
+        let element = |index: usize, load: Vec<MergedInstructions>|
+            [vec![Op(FIXED(OVER)), Val(ptr(index)), Op(FIXED(ADD_PTR))], load].concat();
+        let mutable_load = vec![Op(FIXED(LDNXPTR)), Op(FIXED(READ_F64))];
+        let constant_load = vec![Op(FIXED(LDCUX))];
+        let mismatch = Val(ptr(stack.len() - 1));
+        assert_eq!([
+            element(0, mutable_load.clone()), element(0, constant_load.clone()), vec![Op(FIXED(EQ)), mismatch.clone(), Instr(GOTO_IF)],
+            element(1, mutable_load), element(1, constant_load), vec![Op(FIXED(EQ)), mismatch, Instr(GOTO_IF)],
+            vec![Op(FIXED(DROP)), Op(FIXED(DROP)), Val(1.0), Val(ptr(stack.len() + 2)), Instr(GOTO), Op(FIXED(DROP)), Op(FIXED(DROP)), Val(0.0)]
+        ].concat(), stack[position + 10..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot perform operation EQUAL with mismatched types!")]
+    fn array_comparison_length_mismatch() {
+        compile_and_merge("let a = [1,2,3] == [1,2];");
     }
 
     #[test]
@@ -1392,11 +3375,44 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    #[should_panic(expected = "Invalid assignment target testfunc! Functions can't be assigned to.")]
     fn function_reassign() {
         compile_and_merge("fn testfunc() {} testfunc = 3;");
     }
 
+    // Tests that assigning to values that aren't stored anywhere is reported with its location
+    #[test]
+    fn invalid_assignment_targets() {
+        let targets = [
+            ("fn f() -> f64 { return 1; } f() = 3;", "f()", 29),
+            ("let mut a = 1; (a + 1) = 3;", "(a + 1)", 16),
+            ("3 = 4;", "3", 1),
+            ("fn g() { let mut a = 1; a * 2 = 3; }", "a * 2", 25),
+            ("let mut a = 1; let mut p = &a; (*(&p)) = 3;", "(*(&p))", 32),
+        ];
+        for (text, target, column) in targets {
+            let response = check_request(text);
+            assert!(!response.success, "{} should be rejected", text);
+            assert_eq!(format!("Invalid assignment target {}! Only variables and pointer variables dereferenced with *, along with their array elements and struct members, can be assigned to.", target),
+                       response.diagnostics[0].message.to_str());
+            assert_eq!((1, column), (response.diagnostics[0].line, response.diagnostics[0].column));
+            free_check_response(response);
+        }
+        compile_and_merge("let mut a = 1; a = a == 1;");
+    }
+
+    // Tests that a parenthesised dereference of a pointer variable is the same target as the bare dereference
+    #[test]
+    fn parenthesised_dereference_assignment() {
+        compile_and_assert_equal("let mut a = 1; let mut p = &a; (*p) = 3;", "let mut a = 1; let mut p = &a; *p = 3;");
+        compile_and_assert_equal("let mut a = 1; let mut p = &a; let mut q = &p; ((**q)) = 3;",
+                                 "let mut a = 1; let mut p = &a; let mut q = &p; **q = 3;");
+        compile_and_assert_equal("let mut a = [1, 2]; let mut p = &a; (*p)[1] = 3;", "let mut a = [1, 2]; let mut p = &a; *p[1] = 3;");
+        compile_and_assert_equal("fn f(p: *f64) { (*p) = 3; }", "fn f(p: *f64) { *p = 3; }");
+        compile_and_assert_equal("struct Point { x: f64, y: f64 } let mut a: Point; let mut p = &a; (*p).y = 2;",
+                                 "struct Point { x: f64, y: f64 } let mut a: Point; let mut p = &a; *p.y = 2;");
+    }
+
     #[test]
     #[should_panic]
     fn bad_extern_reference() {
@@ -1489,6 +3505,31 @@ mod tests {
         compile_and_merge("fn testfunc() {} fn testfunc() {}");
     }
 
+    #[test]
+    #[should_panic]
+    fn spread_non_array() {
+        compile_and_merge("fn g(a) {} let a = 3; g(...a);");
+    }
+
+    #[test]
+    #[should_panic]
+    fn spread_wrong_arity() {
+        compile_and_merge("fn g(a, b) {} let a = [1,2,3]; g(...a);");
+    }
+
+    #[test]
+    fn max_function_count() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_max_function_count(2);
+        compiler.compile_str("fn a() {} fn b() {}");
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many functions!")]
+    fn too_many_functions() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_max_function_count(2);
+        compiler.compile_str("fn a() {} fn b() {} fn c() {}");
+    }
+
     #[test]
     #[should_panic]
     fn bad_builtin_function() {
@@ -1555,6 +3596,23 @@ mod tests {
         assert_eq!(vec![Val(ptr(0))], stack);
     }
 
+    // Integers are printed with the integer print op, the largest exact integer and negative values included
+    #[test]
+    fn print_integer() {
+        let stack = compile_and_merge("let mut a: i64 = 9007199254740992; print(a);");
+        assert_eq!([vec![Val(9007199254740992.0)], generate_variable_call(1), vec![Op(FIXED(PRINTI))]].concat(), stack);
+        let stack = compile_and_merge("let mut a: i64 = -5; print(a);");
+        assert_eq!([vec![Val(5.0), Op(FIXED(NEGATE))], generate_variable_call(1), vec![Op(FIXED(PRINTI))]].concat(), stack);
+        let stack = compile_and_merge("let mut a: i32 = 3; print(a);");
+        assert_eq!(Some(&Op(FIXED(PRINTI))), stack.last());
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default().set_precision(64);
+        let stack = compile_and_merge_with_compiler("let mut a: i64 = 9223372036854775807; print(a);", compiler);
+        assert_eq!(Some(&Op(FIXED(PRINTI))), stack.last());
+        // Array elements are still printed individually as floats
+        let stack = compile_and_merge("let mut a: [i32; 1] = [-1]; print(a);");
+        assert_eq!(Some(&Op(FIXED(PRINTFF))), stack.last());
+    }
+
     #[test]
     fn print_mut_string() {
         let stack = compile_and_merge(r#"let mut a = "hello world"; print(a);"#);
@@ -1573,5 +3631,155 @@ mod tests {
                         Op(FIXED(DROP))], stack);
     }
 
+    // Characters of constant strings are read at compile time, including across packed values
+    #[test]
+    fn string_index() {
+        let indexed = compile_and_merge(r#"let const s = "hello world"; let mut a = s[0]; let mut b = s[8];"#);
+        let expected = compile_and_merge(r#"let const s = "hello world"; let mut a = 104; let mut b = 114;"#);
+        assert_eq!(expected, indexed);
+    }
+
+    #[test]
+    fn string_length() {
+        let length = compile_and_merge(r#"let const s = "hello world"; let mut a = len(s); let mut b = len("hi"); let mut c = len([1, 2, 3]);"#);
+        let expected = compile_and_merge(r#"let const s = "hello world"; let mut a = 11; let mut b = 2; let mut c = 3;"#);
+        assert_eq!(expected, length);
+    }
+
+    #[test]
+    #[should_panic(expected = "String index 5 is out of bounds for string of length 5!")]
+    fn string_index_out_of_bounds() {
+        compile_and_merge(r#"let const s = "hello"; let mut c = s[5];"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can only index string literals or constant strings!")]
+    fn string_index_mutable() {
+        compile_and_merge(r#"let mut s = "hello"; let mut c = s[0];"#);
+    }
+
+    // Strings are stored in the user space matching their qualifier
+    #[test]
+    fn string_user_space() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str(r#"let const s = "hello world";"#);
+        assert_eq!(vec![0, 2], code.user_space_size);
+        assert_eq!((0, 2), (code.mutable_user_space.len(), code.constant_user_space.len()));
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str(r#"let mut s = "hello world";"#);
+        assert_eq!(vec![2, 0], code.user_space_size);
+        assert_eq!((2, 0), (code.mutable_user_space.len(), code.constant_user_space.len()));
+    }
+
+    // Characters of mutable strings are written to their byte, accounting for the padding at
+    // the start of the final packed value.
+    #[test]
+    fn string_character_assignment() {
+        let stack = compile_and_merge(r#"let mut s = "hello world"; s[9] = 68; print(s);"#);
+        let string_address = vec![Val(ptr(1)), Val(ptr(1)), Op(FIXED(STK_READ)), Op(FIXED(ADD_PTR)), Op(FIXED(STK_READ))];
+        let assignment = [string_address.clone(), vec![Val(ptr(1)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)),
+                          Val(ptr(6)), Op(FIXED(ADD_PTR)), Val(68.0), Op(FIXED(WRITE_CHAR))]].concat();
+        let print = compile_and_merge(r#"let mut s = "hello world"; print(s);"#)[1..].to_vec();
+        assert_eq!([vec![Val(0.0)], assignment, print].concat(), stack);
+
+        let stack = compile_and_merge(r#"let mut s = "hello world"; s[2] = 76;"#);
+        assert_eq!(vec![Val(ptr(0)), Op(FIXED(ADD_PTR)), Op(FIXED(LDNXPTR)), Val(ptr(2)), Op(FIXED(ADD_PTR))], stack[6..11]);
+    }
+
+    #[test]
+    #[should_panic(expected = "String index 5 is out of bounds for string of length 5!")]
+    fn string_character_assignment_out_of_bounds() {
+        compile_and_merge(r#"let mut s = "hello"; s[5] = 72;"#);
+    }
+
+    // Raw strings take their contents as written, including backslashes and quotes
+    #[test]
+    fn raw_string() {
+        compile_and_assert_equal(r#"let mut a = r"hello";"#, r#"let mut a = "hello";"#);
+        let raw = compile_and_merge(r##"let const s = r"C:\path"; let const t = r#"say "hi""#;
+            let mut a = len(s); let mut b = s[2]; let mut c = len(t); let mut d = t[4];"##);
+        let expected = compile_and_merge(r##"let const s = r"C:\path"; let const t = r#"say "hi""#;
+            let mut a = 7; let mut b = 92; let mut c = 8; let mut d = 34;"##);
+        assert_eq!(expected, raw);
+    }
+
+    // Strings can span multiple lines, keeping the newline characters
+    #[test]
+    fn multi_line_string() {
+        let strings = "let const s = \"a\nb\"; let const t = r\"c\nd\";";
+        let multi_line = compile_and_merge(&format!("{} let mut a = len(s); let mut b = s[1]; let mut c = len(t); let mut d = t[1];", strings));
+        let expected = compile_and_merge(&format!("{} let mut a = 3; let mut b = 10; let mut c = 3; let mut d = 10;", strings));
+        assert_eq!(expected, multi_line);
+    }
+
+    // The AST dump shows the tree structure along with the types and qualifiers of typed nodes
+    #[test]
+    fn dump_ast() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let dump = compiler.dump_ast("let mut a = 1 + 2;\nprint(a);");
+        assert_eq!(dump, "\
+STATEMENT_LIST
+  SOURCE_LINE 1
+    CONSTRUCT
+      identifier: IDENTIFIER a <PRIMITIVE(F64), mut>
+      datatype: DATATYPE PRIMITIVE(F64)
+      qualifier: QUALIFIER mut
+      expression: BINARY_OP ADD <PRIMITIVE(F64), mut>
+        lhs: LITERAL FLOAT(1.0) <PRIMITIVE(F64), const>
+        rhs: LITERAL FLOAT(2.0) <PRIMITIVE(F64), const>
+  SOURCE_LINE 2
+    PRINT
+      expression: IDENTIFIER a <PRIMITIVE(F64), mut>
+");
+    }
+
+    // Tests that the stack estimate of a looping program is complete.
+    #[test]
+    fn stack_estimate_bounded() {
+        use crate::compiler::backend::analysis::stack_estimator::StackEstimator;
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let mut a = 0; while a < 3 { if a == 1 { a = a + 2; } else { a = a + 1; } }");
+        let estimate = StackEstimator::estimate_max_stacksize(&code, 512);
+        assert!(estimate.is_bounded());
+        assert!(estimate.depth_reached > 0);
+        assert_eq!(estimate.max_stacksize, code.max_stack_size);
+    }
+
+    // Tests that the estimate records where the stack first reaches its peak, and that decorated output marks it.
+    #[test]
+    fn stack_estimate_peak() {
+        use crate::compiler::backend::analysis::stack_estimator::StackEstimator;
+        let code = barracuda_common::ProgramCode::new(vec![1.0, 2.0, 0.0, 3.0],
+            vec![FIXED(NULL), FIXED(NULL), FIXED(ADD), FIXED(NULL)], vec![VALUE, VALUE, OP, VALUE]);
+        let estimate = StackEstimator::estimate_max_stacksize(&code, 512);
+        assert_eq!(2, estimate.max_stacksize);
+        assert_eq!(2, estimate.peak_instruction);
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("let mut a = 1; let mut b = a + 2;");
+        let peak_comment = format!("# PEAK STACK SIZE {}\n", code.max_stack_size);
+        assert!(code.decorated().to_string().contains(&peak_comment));
+    }
+
+    // Tests that a jump re-entering code with a larger stack is detected as recursion.
+    #[test]
+    fn stack_estimate_recursive() {
+        use crate::compiler::backend::analysis::stack_estimator::StackEstimator;
+        let code = barracuda_common::ProgramCode::new(vec![1.0, ptr(0)], vec![], vec![VALUE, VALUE, GOTO]);
+        let estimate = StackEstimator::estimate_max_stacksize(&code, 512);
+        assert!(estimate.recursive);
+        assert!(!estimate.is_bounded());
+    }
 
-}
\ No newline at end of file
+    // Tests that program code counts each generated function implementation.
+    #[test]
+    fn function_count() {
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        assert_eq!(0, compiler.compile_str("let mut a = 1;").function_count);
+
+        let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default();
+        let code = compiler.compile_str("fn f(a) {} fn g() { print(1); } let b = -1; f(4); f(&b); g();");
+        assert_eq!(3, code.function_count);
+    }
+}
@@ -13,16 +13,22 @@ use compiler::EnvironmentSymbolContext;
 use compiler::PrimitiveDataType;
 use compiler::Qualifier;
 
-use barracuda_common::CLIEnvVarDescriptor;
+use barracuda_common::{BarracudaInstructions, CLIEnvVarDescriptor, ProgramCode};
 
 // Standard Imports
 use clap::Parser;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Read;
 
 // Basic Compiler Configuration
 type PARSER = compiler::PestBarracudaParser;
 type ANALYSER = compiler::BarracudaSemanticAnalyser;
 type GENERATOR = compiler::BarracudaByteCodeGenerator;
 
+/// Output path used when the source is read from stdin and no output is given
+const STDIN_OUTPUT_PATH: &str = "stdin.bct";
+
 
 
 /// Command Line interface struct
@@ -30,29 +36,58 @@ type GENERATOR = compiler::BarracudaByteCodeGenerator;
 #[derive(Parser)]
 struct CompilerCLIOptions {
     /// Path of file to compile. Barracuda source files end in .bc
+    /// Use - to read the source from stdin
     #[clap(parse(from_os_str))]
     path: std::path::PathBuf,
 
-    /// Path to output file, default is <path_filename>.bct
+    /// Path to output file, default is <path_filename>.bct or stdin.bct when reading from stdin
     #[clap(short, long, parse(from_os_str))]
     output: Option<std::path::PathBuf>,
 
     // Configuration
 
     /// Environment variables definitions space separated identifiers
-    /// Syntax: identifier(:address)?
+    /// Syntax: identifier(:address(:datatype(:qualifier(:ptr_levels)?)?)?)? e.g. grid:4:f64:mut:*
     #[clap(long, multiple = true)]
     env: Option<Vec<CLIEnvVarDescriptor>>,
 
+    /// Name defined for conditional compilation with #if, repeat the flag to define several names
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    define: Option<Vec<String>>,
+
+    /// Optimisation level, 0 generates every statement as written
+    #[clap(short = 'O', long = "optimise", default_value_t = 0)]
+    optimisation_level: usize,
+
     // Flags
 
     /// Write compilation result to stdout instead of output
     #[clap(long, action)]
     stdout: bool,
 
-    /// Generates code with debug decorations
+    /// Generates code with debug decorations and a symbol map (.sym) of variable locations
+    #[clap(long, action)]
+    debug: bool,
+
+    /// Checks array accesses with dynamic indices at runtime, halting on out of range indices
+    #[clap(long, action)]
+    bounds_check: bool,
+
+    /// Prints the analysed abstract syntax tree to stdout instead of compiling
+    #[clap(long, action)]
+    dump_ast: bool,
+
+    /// Warns when a declaration shadows a variable of an enclosing scope
+    #[clap(long, action)]
+    warn_shadowing: bool,
+
+    /// Prints statistics of the compiled program such as its instruction count and stack size
     #[clap(long, action)]
-    debug: bool
+    stats: bool,
+
+    /// Prints statistics of the compiled program along with a count of each operation used
+    #[clap(long, action)]
+    stats_verbose: bool
 }
 
 impl CompilerCLIOptions {
@@ -62,12 +97,32 @@ impl CompilerCLIOptions {
     fn derive_defaults(mut self) -> Self {
         // Derive output file path from input file path if not set
         if self.output.is_none() {
-            self.output = Some(self.path.with_extension("bct"))
+            if self.reads_stdin() {
+                self.output = Some(std::path::PathBuf::from(STDIN_OUTPUT_PATH))
+            } else {
+                self.output = Some(self.path.with_extension("bct"))
+            }
         }
 
         return self;
     }
 
+    /// Source is read from stdin when the path is given as '-'
+    fn reads_stdin(&self) -> bool {
+        self.path.as_os_str() == "-"
+    }
+
+    /// Reads the source to compile from the input path, or stdin if the path is '-'
+    fn read_source(&self) -> std::io::Result<String> {
+        if self.reads_stdin() {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source)?;
+            Ok(source)
+        } else {
+            std::fs::read_to_string(self.path.as_path())
+        }
+    }
+
     /// Generate EnvironmentSymbolContext from CLI arguments.
     /// If addresses were not specified from the args they will be linearly
     /// set. eg 0, 1, 2. Note this does not check for conflict with user specified addresses
@@ -87,7 +142,8 @@ impl CompilerCLIOptions {
                     }
                 };
                 let identifier = env_var_descriptor.identifier.clone();
-                let datatype = PrimitiveDataType::parse(env_var_descriptor.datatype.clone()).unwrap();
+                let datatype = PrimitiveDataType::parse(env_var_descriptor.datatype.clone())
+                    .unwrap_or_else(|| panic!("Environment variable {} has unknown datatype {}!", env_var_descriptor.identifier, env_var_descriptor.datatype));
                 let qualifier = Qualifier::from_str(env_var_descriptor.qualifier.clone());
                 let ptr_levels = env_var_descriptor.ptr_levels.clone();
 
@@ -99,43 +155,103 @@ impl CompilerCLIOptions {
     }
 }
 
+/// Summarises the size of compiled program code for the --stats flags.
+/// Verbose statistics also count how many times each operation is used.
+/// @return: Statistics formatted with one per line
+fn program_statistics(program_code: &ProgramCode, verbose: bool) -> String {
+    let count_instructions = |instruction: BarracudaInstructions| {
+        program_code.instructions.iter().filter(|&&other| other == instruction).count()
+    };
+
+    let mut output = String::new();
+    writeln!(output, "Program statistics:").unwrap();
+    writeln!(output, "  Instructions: {}", program_code.instructions.len()).unwrap();
+    writeln!(output, "  Values: {}", count_instructions(BarracudaInstructions::VALUE)).unwrap();
+    writeln!(output, "  Operations: {}", count_instructions(BarracudaInstructions::OP)).unwrap();
+    writeln!(output, "  Functions: {}", program_code.function_count).unwrap();
+    writeln!(output, "  Estimated stack size: {}", program_code.max_stack_size).unwrap();
+    writeln!(output, "  Mutable user space: {}", program_code.user_space_size[0]).unwrap();
+    writeln!(output, "  Constant user space: {}", program_code.user_space_size[1]).unwrap();
+
+    if verbose {
+        let mut operation_counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (instruction, operation) in program_code.instructions.iter().zip(&program_code.operations) {
+            if *instruction == BarracudaInstructions::OP {
+                *operation_counts.entry(operation.to_string()).or_insert(0) += 1;
+            }
+        }
+
+        writeln!(output, "Operation counts:").unwrap();
+        for (operation, count) in operation_counts {
+            writeln!(output, "  {}: {}", operation, count).unwrap();
+        }
+    }
+
+    output
+}
+
 fn main() {
     // Parse Command line arguments
     let cli_args = CompilerCLIOptions::parse().derive_defaults();
 
     let compiler: Compiler<PARSER, ANALYSER, GENERATOR> = Compiler::default()
-        .set_environment_variables(cli_args.get_environment_variables());
-    let source_path = cli_args.path.as_path();
+        .set_environment_variables(cli_args.get_environment_variables())
+        .set_optimisation_level(cli_args.optimisation_level)
+        .set_bounds_checking(cli_args.bounds_check)
+        .set_warn_shadowing(cli_args.warn_shadowing)
+        .set_defines(cli_args.define.clone().unwrap_or_default());
+    let source = match cli_args.read_source() {
+        Ok(source) => source,
+        Err(why) => {
+            eprintln!("Compile Error: {:?}", why);
+            std::process::exit(exitcode::SOFTWARE);
+        }
+    };
+
+    if cli_args.dump_ast {
+        print!("{}", compiler.dump_ast(source.as_str()));
+        std::process::exit(exitcode::OK);
+    }
+
+    let program_code = compiler.compile_str(source.as_str());
+    let statistics = if cli_args.stats || cli_args.stats_verbose {
+        Some(program_statistics(&program_code, cli_args.stats_verbose))
+    } else {
+        None
+    };
 
     // Check if output should be to stdout
     let result = if cli_args.stdout {
-        match compiler.compile(source_path) {
-            Ok(program_code) => {
-                if cli_args.debug {
-                    print!("{}", program_code.decorated());
-                } else {
-                    print!("{}", program_code);
-                }
-                Ok(())
-            }
-            Err(result) => { Err(result) }
+        for warning in &program_code.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        if cli_args.debug {
+            print!("{}", program_code.decorated());
+        } else {
+            print!("{}", program_code);
         }
+        Ok(())
     } else {
         let dest_path = cli_args.output.unwrap(); // Can unwrap as output will always be derived
         let dest_path = dest_path.as_path();
-        compiler.compile_and_save(source_path, dest_path, cli_args.debug)
+        Compiler::<PARSER, ANALYSER, GENERATOR>::save_program_code(program_code, dest_path, cli_args.debug)
     };
 
+    // Only program output goes to stdout, so statistics and status messages go to stderr
+    if let Some(statistics) = statistics {
+        eprint!("{}", statistics);
+    }
+
     // Check result
     match result {
         Ok(_) => {
-            if !cli_args.stdout { // Don't pollute stdout if it has been selected
-                println!("Compile success!");
+            if !cli_args.stdout {
+                eprintln!("Compile success!");
             }
             std::process::exit(exitcode::OK);
         },
         Err(why) => {
-            println!("Compile Error: {:?}", why);
+            eprintln!("Compile Error: {:?}", why);
             std::process::exit(exitcode::SOFTWARE);
         }
     };